@@ -36,7 +36,11 @@ struct Vote {
     raw_fragment: String,
 }
 
-pub fn generate_archive_files(jormungandr_database: &Path, output_dir: &Path) -> Result<(), Error> {
+/// Walk the blocks of a node block store, from the tip down to block0
+pub fn for_each_block<F>(jormungandr_database: &Path, mut f: F) -> Result<(), Error>
+where
+    F: FnMut(&Block) -> Result<(), Error>,
+{
     let db = chain_storage::BlockStore::file(
         jormungandr_database,
         HeaderId::zero_hash()
@@ -49,15 +53,21 @@ pub fn generate_archive_files(jormungandr_database: &Path, output_dir: &Path) ->
     let tip_id = db.get_tag(MAIN_TAG)?.unwrap();
     let distance = db.get_block_info(tip_id.as_ref())?.chain_length();
 
-    let mut vote_plan_files = HashMap::new();
-
     let block_iter = db.iter(tip_id.as_ref(), distance)?;
 
     for iter_res in block_iter {
         let block_bin = iter_res?;
         let mut buf = ReadBuf::from(block_bin.as_ref());
         let block: Block = Readable::read(&mut buf).unwrap();
+        f(&block)?;
+    }
+    Ok(())
+}
 
+pub fn generate_archive_files(jormungandr_database: &Path, output_dir: &Path) -> Result<(), Error> {
+    let mut vote_plan_files = HashMap::new();
+
+    for_each_block(jormungandr_database, |block| {
         for fragment in block.fragments() {
             if let Fragment::VoteCast(tx) = fragment {
                 let fragment_id = fragment.hash();
@@ -99,6 +109,6 @@ pub fn generate_archive_files(jormungandr_database: &Path, output_dir: &Path) ->
                 })?;
             }
         }
-    }
-    Ok(())
+        Ok(())
+    })
 }
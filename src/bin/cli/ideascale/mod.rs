@@ -1,11 +1,27 @@
+use catalyst_toolbox::community_advisors::impact_scores::{
+    impact_scores, Error as ImpactScoresError, ImpactScoreFormula,
+};
+use catalyst_toolbox::community_advisors::models::AdvisorReviewRow;
+use catalyst_toolbox::ideascale::chain::ChainLinks;
+use catalyst_toolbox::ideascale::db::write_to_db;
+use catalyst_toolbox::ideascale::models::se::Proposal;
+use catalyst_toolbox::ideascale::voteplans::{
+    generate_voteplans, Error as VotePlansError, ProposalVotePlanEntry, SplitStrategy,
+    VotePlanSettings,
+};
 use catalyst_toolbox::ideascale::{
-    build_challenges, build_fund, build_proposals, fetch_all, CustomFieldTags,
-    Error as IdeascaleError, Scores,
+    budget_summary, build_challenges, build_fund, build_proposals, fetch_raw, process_raw,
+    snapshot, validate, CustomFieldTags, Error as IdeascaleError, IdeascaleClient, IdsMapping,
+    RawIdeascaleData, Scores, BASE_IDEASCALE_URL,
 };
+use chain_core::property::Deserialize;
+use chain_impl_mockchain::block::{Block, BlockDate};
 use jcli_lib::utils::io as io_utils;
 use jormungandr_lib::interfaces::VotePrivacy;
 use std::collections::HashSet;
+use std::io::BufReader;
 
+use reqwest::Url;
 use structopt::StructOpt;
 
 use serde::de::DeserializeOwned;
@@ -25,11 +41,30 @@ pub enum Error {
 
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    VotePlans(#[from] VotePlansError),
+
+    #[error(transparent)]
+    ImpactScores(#[from] ImpactScoresError),
+
+    #[error("Could not load block0")]
+    Block0Loading(#[source] std::io::Error),
+
+    #[error("Import validation failed with {0} issues, check the validation report")]
+    ValidationFailed(usize),
+
+    #[error("Either an ideascale API token or a snapshot directory is required")]
+    MissingDataSource,
 }
 
 #[derive(Debug, StructOpt)]
 pub enum Ideascale {
     Import(Import),
+    /// Save the raw ideascale API responses into a directory, to be imported later on
+    Snapshot(Snapshot),
+    /// Generate block0 voteplan definitions for imported proposals
+    Voteplans(Voteplans),
 }
 
 // We need this type because structopt uses Vec<String> as a special type, so it is not compatible
@@ -51,9 +86,12 @@ pub struct Import {
     #[structopt(long, default_value = "Assess")]
     stage_label: String,
 
-    /// ideascale API token
-    #[structopt(long, env = "IDEASCALE_API_TOKEN")]
-    api_token: String,
+    #[structopt(flatten)]
+    api: ApiOpts,
+
+    /// Import from a directory created by the `snapshot` command instead of the ideascale API
+    #[structopt(long, conflicts_with = "api-token")]
+    from_snapshot: Option<PathBuf>,
 
     /// Fund approval threshold setting
     #[structopt(long)]
@@ -67,9 +105,13 @@ pub struct Import {
     #[structopt(long)]
     output_dir: PathBuf,
 
-    /// Path to proposal scores csv file
-    #[structopt(long)]
-    scores: PathBuf,
+    /// Path to proposal scores csv file. Scores in it override the ones computed from
+    /// `assessments`
+    #[structopt(long, required_unless = "assessments")]
+    scores: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    impact_scores: ImpactScoresOpts,
 
     /// Path to json or json like file containing tag configuration for ideascale custom fields
     #[structopt(long)]
@@ -79,15 +121,140 @@ pub struct Import {
     #[structopt(long)]
     excluded_proposals: Option<PathBuf>,
 
+    /// Path to json file mapping ideascale ids to assigned challenge and proposal ids. Ids in
+    /// it are kept, new ones are assigned and the file is updated after the import
+    #[structopt(long)]
+    ids_mapping: Option<PathBuf>,
+
+    /// Path to a vit-servicing-station sqlite database where fund, challenges and proposals will
+    /// also be written. The database schema is created if needed
+    #[structopt(long)]
+    output_db: Option<PathBuf>,
+
+    /// Path to block0, used to link proposals written to `output-db` to their voteplans
+    #[structopt(long, requires_all = &["output-db", "voteplans-mapping"])]
+    block0_path: Option<PathBuf>,
+
+    /// Path to the proposals to voteplans mapping written by `ideascale voteplans`, locating the
+    /// proposals in the block0 voteplans
+    #[structopt(long, requires = "block0-path")]
+    voteplans_mapping: Option<PathBuf>,
+
+    /// Fail the import if the validation report contains any issue
+    #[structopt(long)]
+    strict: bool,
+
     /// Ideascale stages list,
     #[structopt(long, parse(from_str=parse_from_csv), default_value = "Governance phase;Assess QA")]
     stages_filters: Filters,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+pub struct ImpactScoresOpts {
+    /// Path to advisor assessments csv file, used to compute proposals impact scores
+    #[structopt(long)]
+    assessments: Option<PathBuf>,
+
+    /// Weight of reviews rated as excellent when computing impact scores
+    #[structopt(long, default_value = "1")]
+    excellent_review_weight: f32,
+
+    /// Weight of reviews rated as good when computing impact scores
+    #[structopt(long, default_value = "1")]
+    good_review_weight: f32,
+
+    /// Weight of reviews not rated by veteran advisors when computing impact scores
+    #[structopt(long, default_value = "1")]
+    unrated_review_weight: f32,
+
+    /// Take filtered out reviews into account, weighted as unrated reviews
+    #[structopt(long)]
+    include_filtered_out_reviews: bool,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+pub struct ApiOpts {
+    /// ideascale API token
+    #[structopt(long, env = "IDEASCALE_API_TOKEN")]
+    api_token: Option<String>,
+
+    /// ideascale API base url, can be used to point to a local mock of the API
+    #[structopt(long)]
+    api_url: Option<Url>,
+
+    /// Number of proposals requested per page
+    #[structopt(long, default_value = "1000")]
+    page_size: usize,
+
+    /// Max number of requests sent to ideascale at the same time
+    #[structopt(long, default_value = "8")]
+    concurrency: usize,
+
+    /// Max number of retries for rate limited (429) or failed (5xx) requests
+    #[structopt(long, default_value = "3")]
+    max_retries: u32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+pub struct Snapshot {
+    #[structopt(flatten)]
+    api: ApiOpts,
+
+    /// Path to the directory where the raw responses and their checksums will be saved
+    #[structopt(long)]
+    output_dir: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+pub struct Voteplans {
+    /// Path to the proposals json file generated by the import command
+    #[structopt(long)]
+    proposals: PathBuf,
+
+    /// Vote start block date, as epoch.slot
+    #[structopt(long, parse(try_from_str = parse_block_date))]
+    vote_start: BlockDate,
+
+    /// Vote end block date, as epoch.slot
+    #[structopt(long, parse(try_from_str = parse_block_date))]
+    vote_end: BlockDate,
+
+    /// Committee end block date, as epoch.slot
+    #[structopt(long, parse(try_from_str = parse_block_date))]
+    committee_end: BlockDate,
+
+    /// either "public" or "private"
+    #[structopt(long)]
+    chain_vote_type: VotePrivacy,
+
+    /// Bech32 encoded committee member public keys, required for private voteplans
+    #[structopt(long = "committee-key")]
+    committee_keys: Vec<String>,
+
+    /// Max number of proposals per voteplan
+    #[structopt(long, default_value = "255")]
+    max_proposals: usize,
+
+    /// How proposals are split among voteplans, either "per-challenge" or "sequential"
+    #[structopt(long, default_value = "per-challenge")]
+    split: SplitStrategy,
+
+    /// Path to folder where voteplan definitions and the proposals to voteplans mapping will
+    /// be dumped
+    #[structopt(long)]
+    output_dir: PathBuf,
+}
+
 impl Ideascale {
     pub fn exec(&self) -> Result<(), Error> {
         match self {
             Ideascale::Import(import) => import.exec(),
+            Ideascale::Snapshot(snapshot) => snapshot.exec(),
+            Ideascale::Voteplans(voteplans) => voteplans.exec(),
         }
     }
 }
@@ -98,13 +265,20 @@ impl Import {
             fund,
             fund_goal,
             stage_label,
-            api_token,
+            api,
+            from_snapshot,
             threshold,
             chain_vote_type,
             output_dir: save_folder,
             scores,
+            impact_scores,
             tags,
             excluded_proposals,
+            ids_mapping,
+            output_db,
+            block0_path,
+            voteplans_mapping,
+            strict,
             stages_filters,
         } = self;
 
@@ -120,22 +294,86 @@ impl Import {
             Default::default()
         };
 
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .enable_io()
-            .enable_time()
-            .build()?;
-
-        let scores = read_scores_file(scores)?;
-        let idescale_data = runtime.block_on(fetch_all(
+        let mut proposal_scores = impact_scores.compute()?;
+        if let Some(scores_path) = scores {
+            proposal_scores.extend(read_scores_file(scores_path)?);
+        }
+        let scores = proposal_scores;
+        let raw_data = match from_snapshot {
+            Some(snapshot_dir) => snapshot::load(snapshot_dir).map_err(IdeascaleError::from)?,
+            None => api.fetch_raw()?,
+        };
+        let mut idescale_data = process_raw(
+            &raw_data,
             *fund,
             &stage_label.to_lowercase(),
             &stages_filters.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
             &excluded_proposals,
-            api_token.clone(),
-        ))?;
+        )?;
+
+        let report = validate(&idescale_data, &scores, &tags);
+        dump_content_to_file(
+            &report,
+            save_folder
+                .join(format!("fund{}_validation_report.json", fund))
+                .as_path(),
+        )?;
+        if !report.is_empty() {
+            println!(
+                "WARNING!, {} issues found while validating imported data",
+                report.issues.len()
+            );
+            if *strict {
+                return Err(Error::ValidationFailed(report.issues.len()));
+            }
+        }
+        let skipped_challenges = idescale_data.remove_challenges_without_funnel();
+        if !skipped_challenges.is_empty() {
+            println!(
+                "WARNING!, skipping challenges {:?} and their proposals, their funnel was not found",
+                skipped_challenges
+            );
+        }
+
+        let budgets = budget_summary(&idescale_data, &tags);
+        for budget in &budgets {
+            println!(
+                "{}challenge {} ({}): {} proposals requesting {} of {} budget{}",
+                if budget.is_over_budget() {
+                    "OVER BUDGET "
+                } else {
+                    ""
+                },
+                budget.challenge_id,
+                budget.title,
+                budget.proposals,
+                budget.requested_funds,
+                budget.budget,
+                if budget.unparsed_proposals > 0 {
+                    format!(
+                        ", {} with unparsed requested funds",
+                        budget.unparsed_proposals
+                    )
+                } else {
+                    String::new()
+                }
+            );
+        }
+        dump_content_to_file(
+            &budgets,
+            save_folder
+                .join(format!("fund{}_budget_summary.json", fund))
+                .as_path(),
+        )?;
+
+        let mut ids = match ids_mapping {
+            Some(path) if path.exists() => read_json_from_file(path)?,
+            _ => IdsMapping::default(),
+        };
+        ids.update(&idescale_data);
 
         let funds = build_fund(*fund as i32, fund_goal.clone(), *threshold);
-        let challenges = build_challenges(*fund as i32, &idescale_data);
+        let challenges = build_challenges(*fund as i32, &idescale_data, &ids)?;
         let proposals = build_proposals(
             &idescale_data,
             &challenges,
@@ -143,38 +381,168 @@ impl Import {
             &chain_vote_type.to_string(),
             *fund,
             &tags,
-        );
+            &ids,
+        )?;
 
         let mut challenges: Vec<_> = challenges.values().collect();
-        // even if final id type is string, they are just sequentially added, so it should be safe
+        // even if final id type is string, they are assigned from integers, so it should be safe
         // to parse and unwrap here
-        challenges.sort_by_key(|c| c.id.parse::<i32>().unwrap());
+        challenges.sort_by_key(|c| c.id.parse::<u32>().unwrap());
 
         dump_content_to_file(
-            funds,
+            &funds,
             save_folder
                 .join(format!("fund{}_funds.json", fund))
                 .as_path(),
         )?;
 
         dump_content_to_file(
-            challenges,
+            &challenges,
             save_folder
                 .join(format!("fund{}_challenges.json", fund))
                 .as_path(),
         )?;
 
         dump_content_to_file(
-            proposals,
+            &proposals,
             save_folder
                 .join(format!("fund{}_proposals.json", fund))
                 .as_path(),
         )?;
 
+        if let Some(db_path) = output_db {
+            let chain_links = block0_path
+                .as_ref()
+                .zip(voteplans_mapping.as_ref())
+                .map(|(path, mapping_path)| -> Result<_, Error> {
+                    let reader = std::fs::File::open(path)?;
+                    let block0 =
+                        Block::deserialize(BufReader::new(reader)).map_err(Error::Block0Loading)?;
+                    let mapping: Vec<ProposalVotePlanEntry> = read_json_from_file(mapping_path)?;
+                    Ok(ChainLinks::from_block0(&block0, &mapping).map_err(IdeascaleError::from)?)
+                })
+                .transpose()?;
+            let import = write_to_db(
+                &db_path.to_string_lossy(),
+                &funds[0],
+                &challenges,
+                &proposals,
+                chain_links.as_ref(),
+            )
+            .map_err(IdeascaleError::from)?;
+            println!(
+                "Written {} challenges, {} proposals and {} voteplans to {}",
+                import.challenges,
+                import.proposals,
+                import.voteplans,
+                db_path.display()
+            );
+            if chain_links.is_some() && import.unlinked_proposals > 0 {
+                println!(
+                    "WARNING!, {} proposals not found in the voteplans mapping",
+                    import.unlinked_proposals
+                );
+            }
+        }
+
+        if let Some(path) = ids_mapping {
+            dump_content_to_file(ids, path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Snapshot {
+    fn exec(&self) -> Result<(), Error> {
+        let raw_data = self.api.fetch_raw()?;
+        let checksums =
+            snapshot::save(&raw_data, &self.output_dir).map_err(IdeascaleError::from)?;
+        println!(
+            "Saved {} files to {}",
+            checksums.len(),
+            self.output_dir.display()
+        );
+        Ok(())
+    }
+}
+
+impl Voteplans {
+    fn exec(&self) -> Result<(), Error> {
+        let proposals: Vec<Proposal> = read_json_from_file(&self.proposals)?;
+        let settings = VotePlanSettings {
+            vote_start: self.vote_start,
+            vote_end: self.vote_end,
+            committee_end: self.committee_end,
+            payload_type: self.chain_vote_type.into(),
+            committee_member_public_keys: self.committee_keys.clone(),
+            max_proposals_per_voteplan: self.max_proposals,
+            split: self.split,
+        };
+        let generated = generate_voteplans(&proposals, &settings)?;
+
+        std::fs::create_dir_all(&self.output_dir)?;
+        for (i, (voteplan_id, definition)) in generated.voteplans.iter().enumerate() {
+            println!(
+                "voteplan {}: {} with {} proposals",
+                i,
+                voteplan_id,
+                definition.proposals.len()
+            );
+            dump_content_to_file(
+                definition,
+                self.output_dir
+                    .join(format!("voteplan_{}.json", i))
+                    .as_path(),
+            )?;
+        }
+        dump_content_to_file(
+            &generated.mapping,
+            self.output_dir.join("proposals_voteplans.json").as_path(),
+        )?;
         Ok(())
     }
 }
 
+impl ImpactScoresOpts {
+    fn compute(&self) -> Result<Scores, Error> {
+        let assessments = match &self.assessments {
+            Some(path) => path,
+            None => return Ok(Scores::new()),
+        };
+        let reviews: Vec<AdvisorReviewRow> =
+            catalyst_toolbox::utils::csv::load_data_from_csv::<_, b','>(assessments)?;
+        let formula = ImpactScoreFormula {
+            excellent_weight: self.excellent_review_weight,
+            good_weight: self.good_review_weight,
+            unrated_weight: self.unrated_review_weight,
+            exclude_filtered_out: !self.include_filtered_out_reviews,
+        };
+        Ok(impact_scores(&reviews, &formula)?)
+    }
+}
+
+impl ApiOpts {
+    fn fetch_raw(&self) -> Result<RawIdeascaleData, Error> {
+        let api_token = self.api_token.clone().ok_or(Error::MissingDataSource)?;
+        let client = IdeascaleClient::new(api_token)
+            .with_base_url(
+                self.api_url
+                    .clone()
+                    .unwrap_or_else(|| BASE_IDEASCALE_URL.clone()),
+            )
+            .with_page_size(self.page_size)
+            .with_concurrency(self.concurrency)
+            .with_max_retries(self.max_retries);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_io()
+            .enable_time()
+            .build()?;
+        Ok(runtime.block_on(fetch_raw(&client))?)
+    }
+}
+
 fn dump_content_to_file(content: impl Serialize, file_path: &Path) -> Result<(), Error> {
     let writer = jcli_lib::utils::io::open_file_write(&Some(file_path))?;
     serde_json::to_writer_pretty(writer, &content).map_err(Error::Serde)
@@ -185,6 +553,18 @@ fn read_json_from_file<T: DeserializeOwned>(file_path: &Path) -> Result<T, Error
     serde_json::from_reader(reader).map_err(Error::Serde)
 }
 
+fn parse_block_date(s: &str) -> Result<BlockDate, String> {
+    let (epoch, slot_id) = s
+        .split_once('.')
+        .ok_or_else(|| format!("invalid block date '{}', expected epoch.slot", s))?;
+    Ok(BlockDate {
+        epoch: epoch.parse().map_err(|e| format!("invalid epoch: {}", e))?,
+        slot_id: slot_id
+            .parse()
+            .map_err(|e| format!("invalid slot: {}", e))?,
+    })
+}
+
 fn parse_from_csv(s: &str) -> Filters {
     s.split(';').map(|x| x.to_string()).collect()
 }
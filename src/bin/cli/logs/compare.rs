@@ -1,13 +1,18 @@
-use catalyst_toolbox::logs::compare::{compare_logs, LogCmpStats};
+use catalyst_toolbox::logs::compare::{
+    compare_logs, logs_discrepancies, LogCmpStats, LogDiscrepancyReport,
+};
 use catalyst_toolbox::logs::sentry;
 use catalyst_toolbox::logs::sentry::{RawLog, SentryFragmentLog};
+use catalyst_toolbox::utils::csv::dump_data_to_csv;
 use chain_core::property::Fragment;
 use jcli_lib::utils::io;
 use jormungandr_lib::interfaces::{
     load_persistent_fragments_logs_from_folder_path, PersistentFragmentLog,
 };
 use serde::de::DeserializeOwned;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use structopt::StructOpt;
 
 #[derive(thiserror::Error, Debug)]
@@ -26,6 +31,21 @@ pub enum Error {
         path: PathBuf,
         source: serde_json::Error,
     },
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("unknown report format '{0}', expected one of: csv, json")]
+    UnknownReportFormat(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Csv,
+    Json,
 }
 
 #[derive(StructOpt)]
@@ -36,6 +56,43 @@ pub struct Compare {
 
     #[structopt(long)]
     permanent_logs: PathBuf,
+
+    /// Directory where to write a detailed discrepancies report (per fragment, per voter and per proposal)
+    #[structopt(long)]
+    report_dir: Option<PathBuf>,
+
+    /// Format of the report files [csv, json]
+    #[structopt(long, default_value = "csv")]
+    report_format: ReportFormat,
+}
+
+impl FromStr for ReportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(Error::UnknownReportFormat(other.to_string())),
+        }
+    }
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn dump<T: Serialize>(&self, data: &[T], path: &Path) -> Result<(), Error> {
+        match self {
+            Self::Csv => dump_data_to_csv(data, path)?,
+            Self::Json => serde_json::to_writer_pretty(std::fs::File::create(path)?, data)?,
+        };
+        Ok(())
+    }
 }
 
 impl Compare {
@@ -43,56 +100,81 @@ impl Compare {
         let Self {
             sentry_logs,
             permanent_logs,
+            report_dir,
+            report_format,
         } = self;
-        let sentry_logs: Vec<RawLog> = load_logs_from_file(sentry_logs)?;
-
-        let sentry_logs_data: Vec<SentryFragmentLog> = sentry_logs
-            .iter()
-            .enumerate()
-            .filter_map(
-                |(i, raw_log)| match raw_log.get("message").and_then(|v| v.as_str()) {
-                    None => {
-                        // if we could deserialize should be safe to re-serialize it again
-                        eprintln!(
-                            "couldn't load sentry log for entry {}: {}",
-                            i,
-                            serde_json::to_string(raw_log).unwrap()
-                        );
-                        None
-                    }
-                    Some(value) => match value.parse::<SentryFragmentLog>() {
-                        Ok(log) => Some(log),
-                        Err(e) => {
-                            eprintln!(
-                                "couldn't load sentry log for entry {} with message '{}' due to: {:?}",
-                                i, value, e
-                            );
-                            None
-                        }
-                    },
-                },
-            )
-            .collect();
+        let sentry_logs_data = load_sentry_fragment_logs(sentry_logs)?;
+        let permanent_logs_data = load_permanent_fragment_logs(&permanent_logs)?;
+
+        let cmp_result = compare_logs(&sentry_logs_data, &permanent_logs_data);
+        print_results(&cmp_result);
+
+        if let Some(report_dir) = report_dir {
+            let report = logs_discrepancies(&sentry_logs_data, &permanent_logs_data);
+            dump_report(&report, &report_dir, report_format)?;
+        }
+        Ok(())
+    }
+}
 
-        let permanent_logs_data: Vec<PersistentFragmentLog> =
-            load_persistent_fragments_logs_from_folder_path(&permanent_logs)?
-                .enumerate()
-                .filter_map(|(i, res)| match res {
+pub fn dump_report(
+    report: &LogDiscrepancyReport,
+    report_dir: &Path,
+    format: ReportFormat,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(report_dir)?;
+    let file_path = |name: &str| report_dir.join(format!("{}.{}", name, format.extension()));
+    format.dump(&report.discrepancies, &file_path("discrepancies"))?;
+    format.dump(&report.per_voter, &file_path("per_voter"))?;
+    format.dump(&report.per_proposal, &file_path("per_proposal"))?;
+    Ok(())
+}
+
+pub fn load_sentry_fragment_logs(path: PathBuf) -> Result<Vec<SentryFragmentLog>, Error> {
+    let sentry_logs: Vec<RawLog> = load_logs_from_file(path)?;
+    Ok(sentry_logs
+        .iter()
+        .enumerate()
+        .filter_map(
+            |(i, raw_log)| match raw_log.get("message").and_then(|v| v.as_str()) {
+                None => {
+                    // if we could deserialize should be safe to re-serialize it again
+                    eprintln!(
+                        "couldn't load sentry log for entry {}: {}",
+                        i,
+                        serde_json::to_string(raw_log).unwrap()
+                    );
+                    None
+                }
+                Some(value) => match value.parse::<SentryFragmentLog>() {
                     Ok(log) => Some(log),
                     Err(e) => {
                         eprintln!(
-                            "Error deserializing persistent fragment log entry {}: {:?}",
-                            i, e
+                            "couldn't load sentry log for entry {} with message '{}' due to: {:?}",
+                            i, value, e
                         );
                         None
                     }
-                })
-                .collect();
+                },
+            },
+        )
+        .collect())
+}
 
-        let cmp_result = compare_logs(&sentry_logs_data, &permanent_logs_data);
-        print_results(&cmp_result);
-        Ok(())
-    }
+pub fn load_permanent_fragment_logs(path: &Path) -> Result<Vec<PersistentFragmentLog>, Error> {
+    Ok(load_persistent_fragments_logs_from_folder_path(path)?
+        .enumerate()
+        .filter_map(|(i, res)| match res {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!(
+                    "Error deserializing persistent fragment log entry {}: {:?}",
+                    i, e
+                );
+                None
+            }
+        })
+        .collect())
 }
 
 pub fn load_logs_from_file<L: DeserializeOwned>(path: PathBuf) -> Result<Vec<L>, Error> {
@@ -107,6 +189,7 @@ pub fn print_results(results: &LogCmpStats) {
         duplicated_sentry_logs,
         duplicated_fragment_logs,
         fragment_ids_differ,
+        fragment_ids_missing_in_sentry,
         unhandled_fragment_logs,
     } = results;
     for (unhandled_fragment, e) in unhandled_fragment_logs {
@@ -128,4 +211,12 @@ pub fn print_results(results: &LogCmpStats) {
     } else {
         println!("All fragment ids match (sentry over persistent logs)");
     }
+    if !fragment_ids_missing_in_sentry.is_empty() {
+        println!("Non matching (persistent over sentry logs) fragment id's:");
+        for id in fragment_ids_missing_in_sentry {
+            println!("\t{}", id);
+        }
+    } else {
+        println!("All fragment ids match (persistent over sentry logs)");
+    }
 }
@@ -1,8 +1,10 @@
 mod compare;
 mod sentry;
+mod spending_counters;
 
 use structopt::StructOpt;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
@@ -10,6 +12,9 @@ pub enum Error {
 
     #[error(transparent)]
     CompareError(#[from] compare::Error),
+
+    #[error(transparent)]
+    SpendingCountersError(#[from] spending_counters::Error),
 }
 
 #[derive(StructOpt)]
@@ -19,6 +24,8 @@ pub enum Logs {
     Sentry(sentry::SentryLogs),
     /// Compare Sentry and Persistent fragment logs
     Compare(compare::Compare),
+    /// Compare spending counters reported in sentry logs with the ones recovered from persistent logs
+    SpendingCounters(spending_counters::SpendingCounters),
 }
 
 impl Logs {
@@ -26,6 +33,7 @@ impl Logs {
         match self {
             Logs::Sentry(sentry_logs) => sentry_logs.exec()?,
             Logs::Compare(compare) => compare.exec()?,
+            Logs::SpendingCounters(spending_counters) => spending_counters.exec()?,
         };
         Ok(())
     }
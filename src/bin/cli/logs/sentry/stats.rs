@@ -1,6 +1,6 @@
 use super::Error;
 use catalyst_toolbox::logs::sentry::{
-    RawLog, RegexMatch, SentryLogsStatChecker, SentryLogsStatsExecutor, Stat,
+    RawLog, RegexMatch, SentryLogsStatChecker, SentryLogsStatsExecutor, Stat, StatCheckersConfig,
 };
 use jcli_lib::utils::io::open_file_read;
 
@@ -45,6 +45,10 @@ pub struct Stats {
 
     #[structopt(flatten)]
     matches: Matches,
+
+    /// Path to a yaml/json file with named custom checkers to run along the selected ones
+    #[structopt(long)]
+    checkers_config: Option<PathBuf>,
 }
 
 impl Scans {
@@ -70,15 +74,20 @@ impl Matches {
 }
 
 impl Stats {
-    fn build_checkers(&self) -> SentryLogsStatsExecutor {
+    fn build_checkers(&self) -> Result<SentryLogsStatsExecutor, Error> {
         let mut checkers = Vec::new();
         self.scans.build_checkers(&mut checkers, self.all);
         self.matches.build_checkers(&mut checkers, self.all);
-        SentryLogsStatsExecutor::new(checkers)
+        if let Some(config_path) = &self.checkers_config {
+            let config_reader = open_file_read(&Some(config_path))?;
+            let config: StatCheckersConfig = serde_yaml::from_reader(config_reader)?;
+            checkers.extend(config.build_checkers()?);
+        }
+        Ok(SentryLogsStatsExecutor::new(checkers))
     }
 
     pub fn exec(self) -> Result<(), Error> {
-        let mut checker = self.build_checkers();
+        let mut checker = self.build_checkers()?;
         let logs_reader = open_file_read(&Some(self.file))?;
         let logs: Vec<RawLog> = serde_json::from_reader(logs_reader)?;
         checker.process_raw_logs(logs.iter());
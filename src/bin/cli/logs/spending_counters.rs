@@ -0,0 +1,123 @@
+use super::compare::{
+    load_permanent_fragment_logs, load_sentry_fragment_logs, Error as CompareError, ReportFormat,
+};
+use catalyst_toolbox::logs::compare::{compare_spending_counters, SpendingCounterReport};
+use catalyst_toolbox::recovery::tally::{Error as TallyError, VoteFragmentFilter};
+use chain_core::property::Deserialize;
+use chain_impl_mockchain::block::Block;
+
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[allow(clippy::large_enum_variant)]
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Compare(#[from] CompareError),
+
+    #[error(transparent)]
+    Tally(#[from] TallyError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not load block0")]
+    Block0Loading(#[source] std::io::Error),
+}
+
+/// Compare the spending counters reported in sentry logs with the ones that verified
+/// the fragments signatures in the persistent logs
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SpendingCounters {
+    #[structopt(long)]
+    sentry_logs: PathBuf,
+
+    #[structopt(long)]
+    permanent_logs: PathBuf,
+
+    /// Path to the block0 binary file
+    #[structopt(long)]
+    block0_path: PathBuf,
+
+    /// Range of spending counter values tried around the expected one when verifying signatures
+    #[structopt(long, default_value = "1000")]
+    range_check: u32,
+
+    /// Directory where to write the mismatches and per voter reports
+    #[structopt(long)]
+    report_dir: Option<PathBuf>,
+
+    /// Format of the report files [csv, json]
+    #[structopt(long, default_value = "csv")]
+    report_format: ReportFormat,
+}
+
+impl SpendingCounters {
+    pub fn exec(self) -> Result<(), Error> {
+        let Self {
+            sentry_logs,
+            permanent_logs,
+            block0_path,
+            range_check,
+            report_dir,
+            report_format,
+        } = self;
+
+        let reader = std::fs::File::open(block0_path)?;
+        let block0 = Block::deserialize(BufReader::new(reader)).map_err(Error::Block0Loading)?;
+
+        let sentry_logs_data = load_sentry_fragment_logs(sentry_logs)?;
+        let permanent_logs_data = load_permanent_fragment_logs(&permanent_logs)?;
+
+        let validated_fragments: Vec<_> =
+            VoteFragmentFilter::new(block0, 0..range_check, permanent_logs_data.into_iter())?
+                .filter_map(Result::ok)
+                .collect();
+
+        let report = compare_spending_counters(&sentry_logs_data, &validated_fragments);
+        print_results(&report);
+
+        if let Some(report_dir) = report_dir {
+            dump_report(&report, &report_dir, report_format)?;
+        }
+        Ok(())
+    }
+}
+
+fn dump_report(
+    report: &SpendingCounterReport,
+    report_dir: &Path,
+    format: ReportFormat,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(report_dir)?;
+    let file_path = |name: &str| report_dir.join(format!("{}.{}", name, format.extension()));
+    format.dump(
+        &report.mismatches,
+        &file_path("spending_counter_mismatches"),
+    )?;
+    format.dump(&report.per_voter, &file_path("spending_counters_per_voter"))?;
+    Ok(())
+}
+
+fn print_results(report: &SpendingCounterReport) {
+    let affected_voters = report
+        .per_voter
+        .iter()
+        .filter(|voter| voter.out_of_order_counters > 0)
+        .count();
+    println!(
+        "Sentry logs without a validated fragment {}",
+        report.unmatched_sentry_logs
+    );
+    println!(
+        "Ballots with out of order spending counter {}",
+        report.mismatches.len()
+    );
+    println!(
+        "Voters with out of order spending counters {}/{}",
+        affected_voters,
+        report.per_voter.len()
+    );
+}
@@ -1,6 +1,18 @@
+use catalyst_toolbox::notifications::{
+    ledger::SentLedger,
+    requests::Request,
+    responses::{GenericResponse, MessageCodes},
+    send::{RetryPolicy, SendOutcome, Sender},
+    Error,
+};
+
 use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
 use structopt::StructOpt;
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 pub const DEFAULT_PUSHWOOSH_API_URL: &str = "https://cp.pushwoosh.com/json/1.3/";
 
 #[derive(StructOpt)]
@@ -11,3 +23,81 @@ pub struct ApiParams {
     #[structopt(long)]
     pub access_token: String,
 }
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RetryParams {
+    /// Max number of retries on network or server errors
+    #[structopt(long, default_value = "3")]
+    pub retries: u32,
+
+    /// Milliseconds to wait before the first retry, doubled on every following retry
+    #[structopt(long, default_value = "500")]
+    pub retry_backoff_ms: u64,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SendParams {
+    #[structopt(flatten)]
+    pub retry_params: RetryParams,
+
+    /// Path to a ledger file of sent messages, requests already in the ledger are not sent again
+    #[structopt(long)]
+    pub ledger: Option<PathBuf>,
+}
+
+impl RetryParams {
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.retries,
+            backoff: Duration::from_millis(self.retry_backoff_ms),
+        }
+    }
+
+    /// Sender without ledger, for requests which do not change anything (queries)
+    pub fn sender(&self) -> Sender {
+        Sender::new(self.retry_policy(), None)
+    }
+}
+
+impl SendParams {
+    pub fn sender(&self) -> Result<Sender, Error> {
+        let ledger = self
+            .ledger
+            .as_ref()
+            .map(|path| SentLedger::load(path))
+            .transpose()?;
+        Ok(Sender::new(self.retry_params.retry_policy(), ledger))
+    }
+}
+
+/// Send the request to its method endpoint under `api_url` and print the response
+pub fn send_to_endpoint_and_print(
+    sender: &mut Sender,
+    api_url: &Url,
+    request: &Request,
+) -> Result<(), Error> {
+    let url = api_url.join(request.endpoint())?;
+    send_and_print::<GenericResponse>(sender, url, request)
+}
+
+/// Send the request and print the response, or the ledger entry if it was already sent
+pub fn send_and_print<R: DeserializeOwned + MessageCodes + Serialize>(
+    sender: &mut Sender,
+    url: Url,
+    request: &Request,
+) -> Result<(), Error> {
+    match sender.send::<R>(url, request)? {
+        SendOutcome::Sent(response) => {
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        SendOutcome::AlreadySent(entry) => {
+            println!(
+                "Skipping already sent request (at {}), message codes: {:?}",
+                entry.sent_at, entry.message_codes
+            );
+        }
+    }
+    Ok(())
+}
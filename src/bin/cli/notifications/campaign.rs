@@ -0,0 +1,87 @@
+use super::api_params::{send_and_print, ApiParams, SendParams};
+use super::{load_block0_schedule, Error};
+use catalyst_toolbox::notifications::{
+    campaign::CampaignPlan,
+    requests::{Request, RequestData},
+    responses::create_message::CreateMessageResponse,
+    Error as NotificationError,
+};
+use jcli_lib::utils::io;
+
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Send the notifications of a campaign plan file
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Campaign {
+    #[structopt(flatten)]
+    api_params: ApiParams,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+
+    /// Path to the yaml campaign plan file
+    #[structopt(long)]
+    plan: PathBuf,
+
+    /// Path to the block0 binary file, its voteplans dates are used for `send_at` entries and
+    /// are available to templates as `vote_start`, `vote_end` and `committee_end` variables
+    #[structopt(long)]
+    block0: Option<PathBuf>,
+
+    /// Template variables in the form of `name=value`, overrides the ones in the plan file
+    #[structopt(long = "var", parse(try_from_str = parse_variable))]
+    variables: Vec<(String, String)>,
+
+    /// Validate the plan and print the rendered payloads without sending them
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+impl Campaign {
+    pub fn exec(self) -> Result<(), Error> {
+        let mut plan = load_plan(&self.plan)?;
+        plan.extend_variables(self.variables);
+        if let Some(block0) = &self.block0 {
+            plan = plan.with_schedule(load_block0_schedule(block0)?);
+        }
+        let notifications = plan
+            .expand(&self.api_params.access_token)
+            .map_err(NotificationError::from)?;
+
+        let url = self.api_params.api_url.join("createMessage").unwrap();
+        let mut sender = self.send_params.sender()?;
+        for notification in notifications {
+            let request = Request::new(RequestData::CreateMessageRequest(notification.message));
+            if self.dry_run {
+                println!("{}:\n{}", notification.name, redacted_json(&request)?);
+            } else {
+                println!("{}:", notification.name);
+                send_and_print::<CreateMessageResponse>(&mut sender, url.clone(), &request)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pretty json of the request, with the access token hidden
+fn redacted_json(request: &Request) -> Result<String, serde_json::Error> {
+    let mut json = serde_json::to_value(request)?;
+    if let Some(auth) = json.pointer_mut("/request/auth") {
+        *auth = "<redacted>".into();
+    }
+    serde_json::to_string_pretty(&json)
+}
+
+pub fn load_plan(path: &Path) -> Result<CampaignPlan, Error> {
+    let reader = io::open_file_read(&Some(path))?;
+    serde_yaml::from_reader(reader).map_err(Into::into)
+}
+
+fn parse_variable(variable: &str) -> Result<(String, String), String> {
+    variable
+        .split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid variable '{}', expected `name=value`", variable))
+}
@@ -0,0 +1,110 @@
+use super::api_params::{send_to_endpoint_and_print, SendParams, DEFAULT_PUSHWOOSH_API_URL};
+use catalyst_toolbox::notifications::{
+    requests::{
+        devices::{RegisterDevice, SetTags, TagValue, Tags},
+        Request, RequestData,
+    },
+    Error,
+};
+
+use reqwest::Url;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Register {
+    /// Pushwoosh API url
+    #[structopt(long, default_value = DEFAULT_PUSHWOOSH_API_URL)]
+    api_url: Url,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+
+    /// Pushwoosh application code
+    #[structopt(long)]
+    application: String,
+
+    /// Push token of the device
+    #[structopt(long)]
+    push_token: String,
+
+    /// Unique identifier of the device
+    #[structopt(long)]
+    hwid: String,
+
+    /// Device type as described by pushwoosh API (1 for iOS, 3 for Android)
+    #[structopt(long)]
+    device_type: u8,
+
+    /// Language locale of the device, for example "en"
+    #[structopt(long)]
+    language: Option<String>,
+
+    /// Timezone offset of the device in seconds
+    #[structopt(long)]
+    timezone: Option<i64>,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SetDeviceTags {
+    /// Pushwoosh API url
+    #[structopt(long, default_value = DEFAULT_PUSHWOOSH_API_URL)]
+    api_url: Url,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+
+    /// Pushwoosh application code
+    #[structopt(long)]
+    application: String,
+
+    /// Unique identifier of the device
+    #[structopt(long)]
+    hwid: String,
+
+    /// Tags to set, in the form of `name=value`, for example `FundParticipation=fund7`
+    #[structopt(long = "tag", parse(try_from_str = parse_tag), required = true)]
+    tags: Vec<(String, TagValue)>,
+}
+
+impl Register {
+    pub fn exec(self) -> Result<(), Error> {
+        let data = RequestData::RegisterDeviceRequest(
+            RegisterDevice::new(
+                self.application,
+                self.push_token,
+                self.hwid,
+                self.device_type,
+            )
+            .with_language(self.language)
+            .with_timezone(self.timezone),
+        );
+        send_to_endpoint_and_print(
+            &mut self.send_params.sender()?,
+            &self.api_url,
+            &Request::new(data),
+        )
+    }
+}
+
+impl SetDeviceTags {
+    pub fn exec(self) -> Result<(), Error> {
+        let tags: Tags = self.tags.into_iter().collect();
+        let data = RequestData::SetTagsRequest(SetTags::new(self.application, self.hwid, tags));
+        send_to_endpoint_and_print(
+            &mut self.send_params.sender()?,
+            &self.api_url,
+            &Request::new(data),
+        )
+    }
+}
+
+fn parse_tag(tag: &str) -> Result<(String, TagValue), String> {
+    let (name, value) = tag
+        .split_once('=')
+        .ok_or_else(|| format!("invalid tag '{}', expected `name=value`", tag))?;
+    // keep json values (numbers, booleans, lists) typed, fallback to plain string values
+    let value = serde_json::from_str(value).unwrap_or_else(|_| TagValue::String(value.to_string()));
+    Ok((name.to_string(), value))
+}
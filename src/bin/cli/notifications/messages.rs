@@ -0,0 +1,106 @@
+use super::api_params::{send_to_endpoint_and_print, ApiParams, RetryParams, SendParams};
+use catalyst_toolbox::notifications::{
+    requests::{
+        delete_message::DeleteMessage, get_message_details::GetMessageDetails,
+        get_push_history::GetPushHistory, Request, RequestData,
+    },
+    Error,
+};
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Delete {
+    #[structopt(flatten)]
+    api_params: ApiParams,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+
+    /// Code of the message to delete, as returned when it was created
+    message: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Details {
+    #[structopt(flatten)]
+    api_params: ApiParams,
+
+    #[structopt(flatten)]
+    retry_params: RetryParams,
+
+    /// Code or id of the message
+    message: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct History {
+    #[structopt(flatten)]
+    api_params: ApiParams,
+
+    #[structopt(flatten)]
+    retry_params: RetryParams,
+
+    /// Filter messages by source, for example "API" or "CP"
+    #[structopt(long)]
+    source: Option<String>,
+
+    /// Field to search by, for example "applicationCode" or "notificationCode"
+    #[structopt(long, requires("value"))]
+    search_by: Option<String>,
+
+    /// Value to search for in the `search-by` field
+    #[structopt(long, requires("search-by"))]
+    value: Option<String>,
+
+    /// Last message id from a previous request, used for pagination
+    #[structopt(long)]
+    last_notification_id: Option<String>,
+}
+
+impl Delete {
+    pub fn exec(self) -> Result<(), Error> {
+        let data = RequestData::DeleteMessageRequest(DeleteMessage::new(
+            self.api_params.access_token.clone(),
+            self.message,
+        ));
+        send_to_endpoint_and_print(
+            &mut self.send_params.sender()?,
+            &self.api_params.api_url,
+            &Request::new(data),
+        )
+    }
+}
+
+impl Details {
+    pub fn exec(self) -> Result<(), Error> {
+        let data = RequestData::GetMessageDetailsRequest(GetMessageDetails::new(
+            self.api_params.access_token.clone(),
+            self.message,
+        ));
+        send_to_endpoint_and_print(
+            &mut self.retry_params.sender(),
+            &self.api_params.api_url,
+            &Request::new(data),
+        )
+    }
+}
+
+impl History {
+    pub fn exec(self) -> Result<(), Error> {
+        let data = RequestData::GetPushHistoryRequest(
+            GetPushHistory::new(self.api_params.access_token.clone())
+                .with_source(self.source)
+                .with_search(self.search_by, self.value)
+                .with_last_notification_id(self.last_notification_id),
+        );
+        send_to_endpoint_and_print(
+            &mut self.retry_params.sender(),
+            &self.api_params.api_url,
+            &Request::new(data),
+        )
+    }
+}
@@ -1,9 +1,18 @@
 mod api_params;
+mod campaign;
+mod devices;
+mod messages;
 mod send;
 
+use catalyst_toolbox::notifications::schedule::VotePlansSchedule;
+use chain_core::property::Deserialize;
+use chain_impl_mockchain::block::Block;
 use structopt::StructOpt;
 use thiserror::Error;
 
+use std::io::BufReader;
+use std::path::Path;
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Error)]
 pub enum Error {
@@ -12,12 +21,30 @@ pub enum Error {
 
     #[error(transparent)]
     NotificationError(#[from] catalyst_toolbox::notifications::Error),
+
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("error reading campaign plan, source: {0}")]
+    PlanError(#[from] serde_yaml::Error),
 }
 
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub enum PushNotifications {
     Send(send::SendNotification),
+    /// Send the notifications of a campaign plan file
+    Campaign(campaign::Campaign),
+    /// Delete a scheduled message
+    DeleteMessage(messages::Delete),
+    /// Get the details of a message
+    MessageDetails(messages::Details),
+    /// Get the history of sent messages
+    PushHistory(messages::History),
+    /// Register a device in a pushwoosh application
+    RegisterDevice(devices::Register),
+    /// Set tags for a device, used to segment notifications receivers
+    SetTags(devices::SetDeviceTags),
 }
 
 impl PushNotifications {
@@ -25,7 +52,22 @@ impl PushNotifications {
         use self::PushNotifications::*;
         match self {
             Send(cmd) => cmd.exec()?,
+            Campaign(cmd) => cmd.exec()?,
+            DeleteMessage(cmd) => cmd.exec()?,
+            MessageDetails(cmd) => cmd.exec()?,
+            PushHistory(cmd) => cmd.exec()?,
+            RegisterDevice(cmd) => cmd.exec()?,
+            SetTags(cmd) => cmd.exec()?,
         };
         Ok(())
     }
 }
+
+/// Load voteplans dates from a block0 binary file
+pub fn load_block0_schedule(
+    block0_path: &Path,
+) -> Result<VotePlansSchedule, catalyst_toolbox::notifications::Error> {
+    let reader = std::fs::File::open(block0_path)?;
+    let block0 = Block::deserialize(BufReader::new(reader))?;
+    VotePlansSchedule::from_block0(&block0).map_err(Into::into)
+}
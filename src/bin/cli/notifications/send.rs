@@ -1,12 +1,18 @@
-use super::api_params::{ApiParams, DEFAULT_PUSHWOOSH_API_URL};
+use super::api_params::{send_and_print, ApiParams, SendParams, DEFAULT_PUSHWOOSH_API_URL};
+use super::load_block0_schedule;
 use catalyst_toolbox::notifications::{
     requests::{
         create_message::{
             ContentSettingsBuilder, ContentType, CreateMessage, CreateMessageBuilder, DATETIME_FMT,
         },
+        create_targeted_message::{CreateTargetedMessage, CreateTargetedMessageBuilder},
         Request, RequestData,
     },
-    send::send_create_message,
+    responses::{
+        create_message::CreateMessageResponse,
+        create_targeted_message::CreateTargetedMessageResponse,
+    },
+    schedule::SendAt,
     Error,
 };
 use jcli_lib::utils::io;
@@ -34,14 +40,26 @@ pub struct Args {
     #[structopt(flatten)]
     content_path: Content,
 
+    #[structopt(flatten)]
+    send_params: SendParams,
+
     /// Pushwoosh application code where message will be send
     #[structopt(long)]
     application: String,
 
     /// Date and time to send notification of format  "Y-m-d H:M"
-    #[structopt(long, parse(try_from_str=parse_date_time))]
+    #[structopt(long, parse(try_from_str=parse_date_time), conflicts_with = "send-at")]
     send_date: Option<DateTime<FixedOffset>>,
 
+    /// Path to the block0 binary file used to compute send dates from its voteplans
+    #[structopt(long, requires = "send-at")]
+    block0: Option<PathBuf>,
+
+    /// Send date relative to block0 voteplans events (vote_start, vote_end, committee_end),
+    /// for example "vote_end-24h". One notification is scheduled for every distinct voteplan date
+    #[structopt(long, requires = "block0")]
+    send_at: Option<SendAt>,
+
     /// Ignore user timezones when sending a message
     #[structopt(long)]
     ignore_user_timezones: bool,
@@ -54,8 +72,9 @@ pub struct Args {
     #[structopt(long)]
     filter: Option<String>,
 
-    /// Timezone of send date, for example "America/New_York"
-    #[structopt(long)]
+    /// Timezone of send date, for example "America/New_York". Dates derived from block0 are
+    /// always sent in UTC
+    #[structopt(long, conflicts_with = "send-at")]
     timezone: Option<String>,
 }
 
@@ -66,10 +85,47 @@ pub struct Json {
     #[structopt(long, default_value = DEFAULT_PUSHWOOSH_API_URL)]
     pub api_url: Url,
 
-    /// Path to file with the json representation of the notification,
+    /// Path to file with the json representation of the createMessage request data,
     /// if not provided will be read from stdin
     #[structopt(flatten)]
     json_path: Content,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Targeted {
+    #[structopt(flatten)]
+    api_params: ApiParams,
+
+    #[structopt(flatten)]
+    content_path: Content,
+
+    #[structopt(flatten)]
+    send_params: SendParams,
+
+    /// Filter expression selecting the devices to send the message to, as described by pushwoosh API.
+    /// For example `A("APP_CODE") * T("FundParticipation", EQ, "fund7")`
+    #[structopt(long)]
+    devices_filter: String,
+
+    /// Date and time to send notification of format  "Y-m-d H:M"
+    #[structopt(long, parse(try_from_str=parse_date_time))]
+    send_date: Option<DateTime<FixedOffset>>,
+
+    /// Ignore user timezones when sending a message
+    #[structopt(long)]
+    ignore_user_timezones: bool,
+
+    /// Select an specific campaign to send the message to
+    #[structopt(long)]
+    campaign: Option<String>,
+
+    /// Timezone of send date, for example "America/New_York"
+    #[structopt(long)]
+    timezone: Option<String>,
 }
 
 #[derive(StructOpt)]
@@ -79,6 +135,8 @@ pub enum SendNotification {
     FromArgs(Args),
     /// Push an already built notification from a json object
     FromJson(Json),
+    /// Push a notification to the devices matching a filter expression
+    Targeted(Targeted),
 }
 
 impl Args {
@@ -86,43 +144,89 @@ impl Args {
         let url = self.api_params.api_url.join("createMessage").unwrap();
         let message = self.build_create_message()?;
         let request = Request::new(RequestData::CreateMessageRequest(message));
-        let response = send_create_message(url, &request)?;
-
-        println!("{}", serde_json::to_string_pretty(&response)?);
-        Ok(())
+        send_and_print::<CreateMessageResponse>(&mut self.send_params.sender()?, url, &request)
     }
 
     pub fn build_create_message(&self) -> Result<CreateMessage, Error> {
         let content: ContentType = serde_json::from_str(&self.content_path.get_content()?)?;
-        let mut content_builder = ContentSettingsBuilder::new()
-            .with_timezone(self.timezone.clone())
-            .with_campaign(self.campaign.clone())
-            .with_filter(self.filter.clone())
-            .with_ignore_user_timezones(self.ignore_user_timezones)
-            .with_content(content);
-
-        if let Some(datetime) = self.send_date {
-            content_builder = content_builder.with_send_date(datetime);
+        let content_builder = || {
+            ContentSettingsBuilder::new()
+                .with_timezone(self.timezone.clone())
+                .with_campaign(self.campaign.clone())
+                .with_filter(self.filter.clone())
+                .with_ignore_user_timezones(self.ignore_user_timezones)
+                .with_content(content.clone())
+        };
+
+        let mut message_builder = CreateMessageBuilder::new()
+            .with_auth(self.api_params.access_token.clone())
+            .with_application(self.application.clone());
+
+        match (&self.block0, &self.send_at) {
+            (Some(block0), Some(send_at)) => {
+                let schedule = load_block0_schedule(block0)?;
+                for send_date in schedule.send_dates(send_at) {
+                    // block0 dates are computed in UTC
+                    let content_settings = content_builder()
+                        .with_send_date(send_date)
+                        .with_timezone(Some("UTC".to_string()))
+                        .build()?;
+                    message_builder = message_builder.add_content_settings(content_settings);
+                }
+            }
+            _ => {
+                let mut content_settings = content_builder();
+                if let Some(datetime) = self.send_date {
+                    content_settings = content_settings.with_send_date(datetime);
+                }
+                message_builder = message_builder.add_content_settings(content_settings.build()?);
+            }
         }
 
-        CreateMessageBuilder::new()
-            .with_auth(self.api_params.access_token.clone())
-            .with_application(self.application.clone())
-            .add_content_settings(content_builder.build()?)
-            .build()
-            .map_err(Into::into)
+        message_builder.build().map_err(Into::into)
     }
 }
 
 impl Json {
     pub fn exec(self) -> Result<(), Error> {
         let url = self.api_url.join("createMessage").unwrap();
-        let message_data: RequestData = serde_json::from_str(&self.json_path.get_content()?)?;
-        let request: Request = Request::new(message_data);
-        let response = send_create_message(url, &request)?;
+        let message: CreateMessage = serde_json::from_str(&self.json_path.get_content()?)?;
+        let request = Request::new(RequestData::CreateMessageRequest(message));
+        send_and_print::<CreateMessageResponse>(&mut self.send_params.sender()?, url, &request)
+    }
+}
+
+impl Targeted {
+    pub fn exec(self) -> Result<(), Error> {
+        let url = self
+            .api_params
+            .api_url
+            .join("createTargetedMessage")
+            .unwrap();
+        let message = self.build_create_targeted_message()?;
+        let request = Request::new(RequestData::CreateTargetedMessageRequest(message));
+        send_and_print::<CreateTargetedMessageResponse>(
+            &mut self.send_params.sender()?,
+            url,
+            &request,
+        )
+    }
+
+    pub fn build_create_targeted_message(&self) -> Result<CreateTargetedMessage, Error> {
+        let content: ContentType = serde_json::from_str(&self.content_path.get_content()?)?;
+        let mut builder = CreateTargetedMessageBuilder::new()
+            .with_auth(self.api_params.access_token.clone())
+            .with_devices_filter(self.devices_filter.clone())
+            .with_timezone(self.timezone.clone())
+            .with_campaign(self.campaign.clone())
+            .with_ignore_user_timezones(self.ignore_user_timezones)
+            .with_content(content);
+
+        if let Some(datetime) = self.send_date {
+            builder = builder.with_send_date(datetime);
+        }
 
-        println!("{}", serde_json::to_string_pretty(&response)?);
-        Ok(())
+        builder.build().map_err(Into::into)
     }
 }
 
@@ -131,6 +235,7 @@ impl SendNotification {
         match self {
             SendNotification::FromArgs(args) => args.exec(),
             SendNotification::FromJson(json) => json.exec(),
+            SendNotification::Targeted(targeted) => targeted.exec(),
         }
     }
 }
@@ -1,13 +1,19 @@
-use chain_crypto::digest::DigestOf;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use super::Error;
+use catalyst_toolbox::rewards::community_advisors::scheme::{
+    scheme_by_name, RewardScheme, SchemeConfig, SCHEMES,
+};
+use catalyst_toolbox::rewards::community_advisors::seed::{seed_from_string, SeedSource};
+use catalyst_toolbox::rewards::community_advisors::validation::{
+    validate_reviews, DuplicatePolicy, IssueKind,
+};
 use catalyst_toolbox::rewards::community_advisors::{
-    calculate_ca_rewards, ApprovedProposals, CaRewards, FundSetting, Funds, ProposalRewardSlots,
-    ProposalsReviews, Rewards, Seed,
+    calculate_ca_rewards_with_audit, ApprovedProposals, CaRewards, FundSetting, Funds, ProposalId,
+    ProposalLotteryAudit, ProposalRewardSlots, Rewards,
 };
 use catalyst_toolbox::utils;
 
@@ -33,22 +39,116 @@ struct FundSettingOpt {
 #[derive(StructOpt)]
 struct ProposalRewardsSlotsOpt {
     /// excellent reviews amount of rewards tickets
-    #[structopt(long)]
-    excellent_slots: u64,
+    #[structopt(long, required_unless = "reward-scheme-config")]
+    excellent_slots: Option<u64>,
     /// good reviews amount of rewards tickets
-    #[structopt(long)]
-    good_slots: u64,
+    #[structopt(long, required_unless = "reward-scheme-config")]
+    good_slots: Option<u64>,
     /// maximum number of excellent reviews being rewarded per proposal
-    #[structopt(long)]
-    max_excellent_reviews: u64,
+    #[structopt(long, required_unless = "reward-scheme-config")]
+    max_excellent_reviews: Option<u64>,
     /// maximum number of good reviews being rewarded per proposal
+    #[structopt(long, required_unless = "reward-scheme-config")]
+    max_good_reviews: Option<u64>,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+struct RewardSchemeOpt {
+    /// Reward scheme turning reviews into lottery tickets: auto, legacy or fund7
+    #[structopt(long, default_value = "auto")]
+    reward_scheme: String,
+
+    /// Yaml or json file with the `scheme` name and its rewards `slots`, replacing the
+    /// reward scheme and slots options
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "reward-scheme",
+            "excellent-slots",
+            "good-slots",
+            "max-excellent-reviews",
+            "max-good-reviews",
+        ]
+    )]
+    reward_scheme_config: Option<PathBuf>,
+}
+
+impl RewardSchemeOpt {
+    fn load(
+        self,
+        slots: ProposalRewardsSlotsOpt,
+    ) -> Result<(Box<dyn RewardScheme>, ProposalRewardSlots), Error> {
+        let (name, slots) = match self.reward_scheme_config {
+            Some(path) => {
+                let config: SchemeConfig =
+                    serde_yaml::from_reader(jcli_lib::utils::io::open_file_read(&Some(path))?)?;
+                (config.scheme, config.slots)
+            }
+            None => (self.reward_scheme, slots.into()),
+        };
+        let scheme = scheme_by_name(&name).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "unknown reward scheme '{}', expected one of {:?}",
+                name, SCHEMES
+            ))
+        })?;
+        Ok((scheme, slots))
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+struct SeedOpt {
+    /// Free-form lottery seed
+    #[structopt(
+        long,
+        required_unless_one = &["seed-block-store", "seed-commit-reveal"],
+        conflicts_with_all = &["seed-block-store", "seed-commit-reveal"]
+    )]
+    seed: Option<String>,
+
+    /// Node block store holding the block whose hash is used as lottery seed
+    #[structopt(
+        long,
+        requires = "seed-block-height",
+        conflicts_with = "seed-commit-reveal"
+    )]
+    seed_block_store: Option<PathBuf>,
+
+    /// Chain height of the block whose hash is used as lottery seed
+    #[structopt(long, requires = "seed-block-store")]
+    seed_block_height: Option<u32>,
+
+    /// Json file with a `commitment` published before the lottery and the revealed value used
+    /// as lottery seed
     #[structopt(long)]
-    max_good_reviews: u64,
+    seed_commit_reveal: Option<PathBuf>,
+}
+
+impl SeedOpt {
+    fn source(self) -> SeedSource {
+        match (
+            self.seed,
+            self.seed_block_store,
+            self.seed_block_height,
+            self.seed_commit_reveal,
+        ) {
+            (Some(seed), ..) => SeedSource::String(seed),
+            (None, Some(block_store), Some(height), _) => SeedSource::BlockHash {
+                block_store,
+                height,
+            },
+            (None, None, None, Some(path)) => SeedSource::CommitReveal(path),
+            _ => unreachable!("structopt validates the seed options"),
+        }
+    }
 }
 
+/// Published inputs of the community advisors rewards lottery
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
-pub struct CommunityAdvisors {
+struct LotteryInputs {
     #[structopt(long = "assessments")]
     assessments_path: PathBuf,
 
@@ -61,22 +161,38 @@ pub struct CommunityAdvisors {
     #[structopt(flatten)]
     rewards_slots: ProposalRewardsSlotsOpt,
 
+    #[structopt(flatten)]
+    reward_scheme: RewardSchemeOpt,
+
+    #[structopt(flatten)]
+    seed: SeedOpt,
+
+    /// How several reviews of an assessor for the same proposal are resolved: keep-first,
+    /// keep-best or drop-all
+    #[structopt(long, default_value = "keep-first")]
+    duplicate_reviews: DuplicatePolicy,
+
+    /// Path where the reviews validation report will be written, as json
     #[structopt(long)]
-    output: PathBuf,
+    validation_report: Option<PathBuf>,
 
+    /// Abort if reviews validation reports any issue
     #[structopt(long)]
-    seed: String,
+    strict: bool,
 }
 
-impl CommunityAdvisors {
-    pub fn exec(self) -> Result<(), Error> {
+impl LotteryInputs {
+    fn calculate(self) -> Result<(CaRewards, Vec<ProposalLotteryAudit>), Error> {
         let Self {
             assessments_path,
             approved_proposals_path,
             fund_settings,
             rewards_slots,
-            output,
+            reward_scheme,
             seed,
+            duplicate_reviews,
+            validation_report,
+            strict,
         } = self;
 
         if fund_settings.bonus_ratio + fund_settings.proposal_ratio != 100 {
@@ -85,8 +201,34 @@ impl CommunityAdvisors {
             ));
         }
 
-        let proposal_reviews = read_proposal_reviews(&assessments_path)?;
-        let approved_proposals = read_approved_proposals(&approved_proposals_path)?;
+        let (scheme, rewards_slots) = reward_scheme.load(rewards_slots)?;
+        let seed = seed.source().seed_string()?;
+        println!("Lottery seed: {}", seed);
+
+        let reviews: Vec<AdvisorReviewRow> =
+            utils::csv::load_data_from_csv::<_, b','>(&assessments_path)?;
+        let (approved_proposals, known_proposals) = read_proposals(&approved_proposals_path)?;
+
+        let (proposal_reviews, report) =
+            validate_reviews(reviews, &known_proposals, duplicate_reviews);
+        if !report.is_empty() {
+            println!(
+                "WARNING!, reviews validation found {} conflicting scores, {} duplicate reviews, {} proposals with all reviews filtered out and {} unknown proposals",
+                report.count(IssueKind::ConflictingScores),
+                report.count(IssueKind::DuplicateReview),
+                report.count(IssueKind::AllReviewsFilteredOut),
+                report.count(IssueKind::UnknownProposal),
+            );
+        }
+        if let Some(path) = validation_report {
+            serde_json::to_writer_pretty(
+                jcli_lib::utils::io::open_file_write(&Some(path))?,
+                &report,
+            )?;
+        }
+        if strict && !report.is_empty() {
+            return Err(Error::ReviewsValidationFailed(report.issues.len()));
+        }
 
         let approved_set = approved_proposals.keys().cloned().collect::<BTreeSet<_>>();
         let proposal_reviews_set = proposal_reviews.keys().cloned().collect::<BTreeSet<_>>();
@@ -102,38 +244,135 @@ impl CommunityAdvisors {
             );
         }
 
-        let rewards = calculate_ca_rewards(
+        Ok(calculate_ca_rewards_with_audit(
             proposal_reviews,
             &approved_proposals,
             &fund_settings.into(),
-            &rewards_slots.into(),
-            Seed::from(DigestOf::digest(&seed)),
-        );
+            &rewards_slots,
+            scheme.as_ref(),
+            seed_from_string(&seed),
+        ))
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CommunityAdvisors {
+    #[structopt(flatten)]
+    inputs: LotteryInputs,
+
+    #[structopt(long)]
+    output: PathBuf,
+
+    /// Path where the per proposal lottery breakdown will be written, as json
+    #[structopt(long)]
+    audit_json: Option<PathBuf>,
+
+    /// Path where the per proposal lottery breakdown will be written, as csv with one row per
+    /// proposal assessor
+    #[structopt(long)]
+    audit_csv: Option<PathBuf>,
+}
+
+impl CommunityAdvisors {
+    pub fn exec(self) -> Result<(), Error> {
+        let Self {
+            inputs,
+            output,
+            audit_json,
+            audit_csv,
+        } = self;
+
+        let (rewards, audits) = inputs.calculate()?;
 
         let csv_data = rewards_to_csv_data(&rewards);
         dump_data_to_csv(&csv_data, &output)?;
+
+        if let Some(path) = audit_json {
+            serde_json::to_writer_pretty(
+                jcli_lib::utils::io::open_file_write(&Some(path))?,
+                &audits,
+            )?;
+        }
+        if let Some(path) = audit_csv {
+            dump_data_to_csv(&audits_to_csv_data(&audits), &path)?;
+        }
         Ok(())
     }
 }
 
-fn read_proposal_reviews(path: &Path) -> Result<ProposalsReviews, Error> {
-    let reviews: Vec<AdvisorReviewRow> = utils::csv::load_data_from_csv::<_, b','>(path)?;
-    let mut proposal_reviews = ProposalsReviews::new();
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct VerifyLottery {
+    #[structopt(flatten)]
+    inputs: LotteryInputs,
 
-    for review in reviews.into_iter() {
-        proposal_reviews
-            .entry(review.proposal_id.clone())
-            .or_default()
-            .push(review);
-    }
+    /// Published community advisors rewards csv to check against
+    #[structopt(long)]
+    rewards: PathBuf,
+}
 
-    Ok(proposal_reviews)
+impl VerifyLottery {
+    pub fn exec(self) -> Result<(), Error> {
+        // rewards are read as strings, deserializing them as decimals would go through f64 and
+        // lose precision
+        #[derive(Deserialize)]
+        struct Entry {
+            id: String,
+            rewards: String,
+        }
+
+        let Self { inputs, rewards } = self;
+        let (expected, _) = inputs.calculate()?;
+        let published: Vec<Entry> = utils::csv::load_data_from_csv::<_, b','>(&rewards)?;
+
+        let mut mismatches = 0;
+        let mut published_ids = BTreeSet::new();
+        for entry in &published {
+            published_ids.insert(entry.id.as_str());
+            let published_rewards = Rewards::from_str(&entry.rewards)
+                .map_err(|e| Error::InvalidRewards(format!("{}: {}", entry.id, e)))?;
+            match expected.get(&entry.id) {
+                Some(rewards) if *rewards == published_rewards => {}
+                Some(rewards) => {
+                    mismatches += 1;
+                    println!(
+                        "{}: published {}, recomputed {}",
+                        entry.id, published_rewards, rewards
+                    );
+                }
+                None => {
+                    mismatches += 1;
+                    println!(
+                        "{}: published {}, not rewarded",
+                        entry.id, published_rewards
+                    );
+                }
+            }
+        }
+        for (id, rewards) in &expected {
+            if !published_ids.contains(id.as_str()) {
+                mismatches += 1;
+                println!("{}: not published, recomputed {}", id, rewards);
+            }
+        }
+
+        if mismatches > 0 {
+            return Err(Error::LotteryMismatch(mismatches));
+        }
+        println!("{} rewards match the recomputed lottery", published.len());
+        Ok(())
+    }
 }
 
-fn read_approved_proposals(path: &Path) -> Result<ApprovedProposals, Error> {
-    let approved_proposals: Vec<ApprovedProposalRow> =
-        utils::csv::load_data_from_csv::<_, b','>(path)?;
-    approved_proposals
+/// Approved proposals with their budget, along with the ids of every proposal in the file
+fn read_proposals(path: &Path) -> Result<(ApprovedProposals, BTreeSet<ProposalId>), Error> {
+    let proposals: Vec<ApprovedProposalRow> = utils::csv::load_data_from_csv::<_, b','>(path)?;
+    let known_proposals = proposals
+        .iter()
+        .map(|proposal| proposal.proposal_id.clone())
+        .collect();
+    let approved_proposals = proposals
         .into_iter()
         .filter_map(|proposal| match proposal.status {
             ProposalStatus::Approved => Some(
@@ -143,7 +382,8 @@ fn read_approved_proposals(path: &Path) -> Result<ApprovedProposals, Error> {
             ProposalStatus::NotApproved => None,
         })
         .collect::<Result<_, _>>()
-        .map_err(|e| Error::InvalidRequestedFunds(e.to_string())) // ParseFixedError does not implement std::Error
+        .map_err(|e| Error::InvalidRequestedFunds(e.to_string()))?; // ParseFixedError does not implement std::Error
+    Ok((approved_proposals, known_proposals))
 }
 
 impl From<FundSettingOpt> for FundSetting {
@@ -157,12 +397,13 @@ impl From<FundSettingOpt> for FundSetting {
 }
 
 impl From<ProposalRewardsSlotsOpt> for ProposalRewardSlots {
+    // slots options are required unless a reward scheme config is given
     fn from(settings: ProposalRewardsSlotsOpt) -> Self {
         Self {
-            excellent_slots: settings.excellent_slots,
-            good_slots: settings.good_slots,
-            max_good_reviews: settings.max_good_reviews,
-            max_excellent_reviews: settings.max_excellent_reviews,
+            excellent_slots: settings.excellent_slots.unwrap(),
+            good_slots: settings.good_slots.unwrap(),
+            max_good_reviews: settings.max_good_reviews.unwrap(),
+            max_excellent_reviews: settings.max_excellent_reviews.unwrap(),
         }
     }
 }
@@ -182,3 +423,157 @@ fn rewards_to_csv_data(rewards: &CaRewards) -> Vec<impl Serialize> {
         })
         .collect()
 }
+
+fn audits_to_csv_data(audits: &[ProposalLotteryAudit]) -> Vec<impl Serialize + '_> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        proposal_id: &'a str,
+        scheme: &'static str,
+        per_ticket_reward: Rewards,
+        bonus_reward: Rewards,
+        assessor: &'a str,
+        excellent_tickets: u64,
+        good_tickets: u64,
+        legacy_tickets: u64,
+        /// tickets won in each lottery stage, separated by `;`
+        won_per_stage: String,
+        rewards: Rewards,
+    }
+
+    audits
+        .iter()
+        .flat_map(|audit| {
+            audit.assessors.iter().map(move |assessor| Entry {
+                proposal_id: &audit.proposal_id,
+                scheme: audit.scheme,
+                per_ticket_reward: audit.per_ticket_reward,
+                bonus_reward: audit.bonus_reward,
+                assessor: &assessor.assessor,
+                excellent_tickets: assessor.excellent_tickets,
+                good_tickets: assessor.good_tickets,
+                legacy_tickets: assessor.legacy_tickets,
+                won_per_stage: assessor
+                    .won_per_stage
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                rewards: assessor.rewards,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+
+    const VALID_ASSESSMENTS: &str = "./resources/testing/valid_assessments.csv";
+
+    fn lottery_args(temp_dir: &TempDir, assessments: &str) -> Vec<String> {
+        let proposals = temp_dir.child("proposals.csv");
+        proposals
+            .write_str("internal_id,meets_approval_threshold,requested_dollars\n53123,yes,10000\n")
+            .unwrap();
+        let proposals_path = proposals.path().to_string_lossy().to_string();
+        [
+            "--assessments",
+            assessments,
+            "--proposals",
+            proposals_path.as_str(),
+            "--rewards-ratio",
+            "80",
+            "--bonus-ratio",
+            "20",
+            "--funds",
+            "1000",
+            "--excellent-slots",
+            "12",
+            "--good-slots",
+            "4",
+            "--max-excellent-reviews",
+            "2",
+            "--max-good-reviews",
+            "3",
+            "--seed",
+            "seed",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+    }
+
+    fn verify_lottery(temp_dir: &TempDir, assessments: &str, rewards: &Path) -> Result<(), Error> {
+        VerifyLottery::from_iter(
+            std::iter::once("verify-lottery".to_string())
+                .chain(lottery_args(temp_dir, assessments))
+                .chain(vec![
+                    "--rewards".to_string(),
+                    rewards.to_string_lossy().to_string(),
+                ]),
+        )
+        .exec()
+    }
+
+    fn community_advisors(temp_dir: &TempDir, assessments: &str, output: &Path) {
+        CommunityAdvisors::from_iter(
+            std::iter::once("community-advisors".to_string())
+                .chain(lottery_args(temp_dir, assessments))
+                .chain(vec![
+                    "--output".to_string(),
+                    output.to_string_lossy().to_string(),
+                ]),
+        )
+        .exec()
+        .unwrap();
+    }
+
+    #[test]
+    fn verify_lottery_matches_computed_rewards() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.child("rewards.csv");
+        community_advisors(&temp_dir, VALID_ASSESSMENTS, output.path());
+
+        verify_lottery(&temp_dir, VALID_ASSESSMENTS, output.path()).unwrap();
+    }
+
+    #[test]
+    fn verify_lottery_matches_non_terminating_rewards() {
+        let temp_dir = TempDir::new().unwrap();
+        let assessments = temp_dir.child("assessments.csv");
+        let mut content = "proposal_id,Idea URL,Assessor,Impact / Alignment Note,Impact / Alignment Rating,Feasibility Note,Feasibility Rating,Auditability Note,Auditability Rating,Excellent,Good\n".to_string();
+        for assessor in &["a_assessor", "b_assessor", "c_assessor"] {
+            content.push_str(&format!("53123,url,{},note,4,note,4,note,4,,x\n", assessor));
+        }
+        assessments.write_str(&content).unwrap();
+        let assessments = assessments.path().to_string_lossy().to_string();
+        let output = temp_dir.child("rewards.csv");
+        community_advisors(&temp_dir, &assessments, output.path());
+
+        // 1000 split among the tickets of three reviews, rewards have more digits than a f64
+        // can hold
+        let published = std::fs::read_to_string(output.path()).unwrap();
+        assert!(published
+            .lines()
+            .skip(1)
+            .any(|line| line.split(',').nth(1).unwrap().len() > 20));
+
+        verify_lottery(&temp_dir, &assessments, output.path()).unwrap();
+    }
+
+    #[test]
+    fn verify_lottery_reports_mismatches() {
+        let temp_dir = TempDir::new().unwrap();
+        let published = temp_dir.child("published.csv");
+        published
+            .write_str("id,rewards\nz_assessor_101,1\nunknown_assessor,10\n")
+            .unwrap();
+
+        assert!(matches!(
+            verify_lottery(&temp_dir, VALID_ASSESSMENTS, published.path()),
+            Err(Error::LotteryMismatch(2))
+        ));
+    }
+}
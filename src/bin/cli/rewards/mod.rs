@@ -1,4 +1,5 @@
 mod community_advisors;
+mod payout;
 mod veterans;
 mod voters;
 
@@ -14,6 +15,9 @@ pub enum Error {
     #[error("requested funds cannot be parsed: {0}")]
     InvalidRequestedFunds(String),
 
+    #[error("published rewards cannot be parsed: {0}")]
+    InvalidRewards(String),
+
     #[error(transparent)]
     Other(#[from] jcli_lib::jcli_lib::block::Error),
 
@@ -25,6 +29,36 @@ pub enum Error {
 
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    VoteCount(#[from] catalyst_toolbox::rewards::vote_count::Error),
+
+    #[error(transparent)]
+    Rules(#[from] catalyst_toolbox::rewards::voters::RulesError),
+
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    Seed(#[from] catalyst_toolbox::rewards::community_advisors::seed::Error),
+
+    #[error("Reviews validation failed with {0} issues, check the validation report")]
+    ReviewsValidationFailed(usize),
+
+    #[error("{0} published rewards do not match the recomputed lottery")]
+    LotteryMismatch(usize),
+
+    #[error(transparent)]
+    Payout(#[from] catalyst_toolbox::rewards::payout::Error),
+
+    #[error(transparent)]
+    Registrations(#[from] catalyst_toolbox::rewards::registrations::Error),
+
+    #[error(transparent)]
+    VcaReviews(#[from] catalyst_toolbox::vca_reviews::Error),
+
+    #[error(transparent)]
+    Veterans(#[from] catalyst_toolbox::rewards::veterans::Error),
 }
 
 #[derive(StructOpt)]
@@ -38,6 +72,13 @@ pub enum Rewards {
 
     /// Calculate rewards for veteran community advisors
     Veterans(veterans::VeteransRewards),
+
+    /// Recompute community advisors rewards from published inputs and check them against a
+    /// published rewards file
+    VerifyLottery(community_advisors::VerifyLottery),
+
+    /// Plan the payment of a rewards file in batched transactions
+    Payout(payout::Payout),
 }
 
 impl Rewards {
@@ -46,6 +87,8 @@ impl Rewards {
             Rewards::Voters(cmd) => cmd.exec(),
             Rewards::CommunityAdvisors(cmd) => cmd.exec(),
             Rewards::Veterans(cmd) => cmd.exec(),
+            Rewards::VerifyLottery(cmd) => cmd.exec(),
+            Rewards::Payout(cmd) => cmd.exec(),
         }
     }
 }
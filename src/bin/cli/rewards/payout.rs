@@ -0,0 +1,113 @@
+use super::Error;
+use catalyst_toolbox::rewards::payout::{
+    load_rewards, payment_plan, PayoutSettings, RewardsFormat,
+};
+
+use serde::Deserialize;
+use structopt::StructOpt;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Payout {
+    /// Rewards csv file written by one of the rewards commands
+    #[structopt(long)]
+    rewards: PathBuf,
+
+    /// Format of the rewards file: voters, community-advisors or veterans
+    #[structopt(long)]
+    format: RewardsFormat,
+
+    /// Csv file with `id,address` columns mapping reward ids to payment addresses. Without it,
+    /// reward ids are used as payment addresses.
+    #[structopt(long)]
+    addresses: Option<PathBuf>,
+
+    /// Maximum amount of outputs in a single transaction
+    #[structopt(long, default_value = "100")]
+    max_outputs: usize,
+
+    /// Maximum estimated size of a transaction, in bytes
+    #[structopt(long, default_value = "16384")]
+    max_tx_size: usize,
+
+    /// Minimum UTxO value (in lovelace), smaller outputs are left out of the plan
+    #[structopt(long, default_value = "1000000")]
+    min_utxo: u64,
+
+    /// Outputs (in lovelace) below this amount are flagged as dust
+    #[structopt(long, default_value = "0")]
+    dust_threshold: u64,
+
+    /// Lovelace the rewards file should add up to, usually the total rewards given to the
+    /// rewards command. The plan is not written if they differ.
+    #[structopt(long)]
+    expected_total: Option<u64>,
+
+    /// Path where the json payment plan will be written
+    #[structopt(long)]
+    output: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct AddressMapping {
+    id: String,
+    address: String,
+}
+
+impl Payout {
+    pub fn exec(self) -> Result<(), Error> {
+        let Self {
+            rewards,
+            format,
+            addresses,
+            max_outputs,
+            max_tx_size,
+            min_utxo,
+            dust_threshold,
+            expected_total,
+            output,
+        } = self;
+
+        if max_outputs == 0 {
+            return Err(Error::InvalidInput(
+                "max outputs should be at least 1".to_string(),
+            ));
+        }
+
+        let rewards = load_rewards(&rewards, format)?;
+        let addresses = addresses
+            .map(|path| {
+                catalyst_toolbox::utils::csv::load_data_from_csv::<AddressMapping, b','>(&path)
+            })
+            .transpose()?
+            .map(|mappings| {
+                mappings
+                    .into_iter()
+                    .map(|mapping| (mapping.id, mapping.address))
+                    .collect::<HashMap<_, _>>()
+            });
+
+        let settings = PayoutSettings {
+            max_outputs,
+            max_tx_size,
+            min_utxo,
+            dust_threshold,
+            expected_total,
+        };
+        let plan = payment_plan(&rewards, addresses.as_ref(), &settings)?;
+
+        println!(
+            "{} lovelace planned in {} transactions, {} lovelace below minimum UTxO in {} outputs, {} lovelace flagged as dust",
+            plan.totals.planned,
+            plan.transactions.len(),
+            plan.totals.below_min_utxo,
+            plan.below_min_utxo.len(),
+            plan.totals.dust,
+        );
+        serde_json::to_writer_pretty(jcli_lib::utils::io::open_file_write(&Some(output))?, &plan)?;
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use super::Error;
 use catalyst_toolbox::rewards::veterans;
 use catalyst_toolbox::rewards::veterans::VeteranAdvisorReward;
 use catalyst_toolbox::utils::csv;
+use catalyst_toolbox::vca_reviews::read_vca_reviews_aggregated_rows_with_vcas;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -17,6 +18,20 @@ pub struct VeteransRewards {
     /// Reward to be distributed
     #[structopt(long = "total-rewards")]
     total_rewards: VeteranAdvisorReward,
+
+    /// Rewards tiers, cap and review outcome weights yaml file path
+    #[structopt(long)]
+    config: Option<PathBuf>,
+
+    /// vCA aggregated reviews csv file path. When given, reviews are counted from it and
+    /// weighted by their outcome instead of using the counts of the reviews file
+    #[structopt(long)]
+    vca_aggregated: Option<PathBuf>,
+
+    /// Column of the vCA aggregated file listing the names of the vCAs who reviewed each
+    /// assessment, separated by commas
+    #[structopt(long, default_value = "vCAs", requires = "vca-aggregated")]
+    vcas_column: String,
 }
 
 impl VeteransRewards {
@@ -25,10 +40,40 @@ impl VeteransRewards {
             from,
             to,
             total_rewards,
+            config,
+            vca_aggregated,
+            vcas_column,
         } = self;
-        let reviews: Vec<veterans::VeteranReviewsCount> =
-            csv::load_data_from_csv::<_, b','>(&from)?;
-        let results = veterans::calculate_veteran_advisors_rewards(&reviews, total_rewards);
+        let counts: Vec<veterans::VeteranReviewsCount> = csv::load_data_from_csv::<_, b','>(&from)?;
+        let config: veterans::VeteranRewardsConfig = match config {
+            Some(path) => {
+                serde_yaml::from_reader(jcli_lib::utils::io::open_file_read(&Some(path))?)?
+            }
+            None => Default::default(),
+        };
+        let reviews = match vca_aggregated {
+            Some(path) => {
+                let rows = read_vca_reviews_aggregated_rows_with_vcas(&path, &vcas_column)?;
+                let (reviews, mismatches) = veterans::veteran_reviews_from_aggregated(
+                    &counts,
+                    &rows,
+                    &config.outcome_weights,
+                )?;
+                for mismatch in mismatches {
+                    eprintln!(
+                        "{}: {} reviews entered, {} found in the vCA aggregated file",
+                        mismatch.name, mismatch.entered, mismatch.derived
+                    );
+                }
+                reviews
+            }
+            None => veterans::veteran_reviews_from_counts(&counts),
+        };
+        let results = veterans::calculate_veteran_advisors_rewards_with_config(
+            &reviews,
+            total_rewards,
+            &config,
+        );
         csv::dump_data_to_csv(&results, &to)?;
 
         Ok(())
@@ -52,6 +97,9 @@ mod tests {
             from: resource_input.into(),
             to: tmp_file.path().into(),
             total_rewards: 1000.into(),
+            config: None,
+            vca_aggregated: None,
+            vcas_column: "vCAs".to_string(),
         };
 
         export.exec().unwrap();
@@ -1,19 +1,30 @@
 use super::Error;
+use catalyst_toolbox::ideascale::voteplans::ProposalVotePlanEntry;
+use catalyst_toolbox::rewards::registrations::{
+    reward_share_per_reward_address, stake_per_voting_key, Registration, RewardAddressShare,
+};
+use catalyst_toolbox::rewards::vote_count::{
+    ballots_from_block_store, ballots_from_fragment_logs, vote_count, Ballots,
+};
 use catalyst_toolbox::rewards::voters::{
-    calculate_reward_share, calculate_stake, reward_from_share, vote_count_with_addresses,
-    AddressesVoteCount, Rewards, VoteCount, ADA_TO_LOVELACE_FACTOR,
+    calculate_reward_share_with_rules, calculate_stake, participation_from_ballots,
+    participation_from_vote_count, rewards_to_lovelace, vote_count_with_addresses,
+    AddressesParticipation, Eligibility, ParticipationRule, ParticipationRules, ProposalChallenges,
+    Rewards, VoteCount, ADA_TO_LOVELACE_FACTOR,
 };
 
 use chain_addr::{Discrimination, Kind};
 use chain_impl_mockchain::vote::CommitteeId;
 use jcli_lib::jcli_lib::block::Common;
-use jormungandr_lib::interfaces::{Address, Block0Configuration};
+use jormungandr_lib::interfaces::{
+    load_persistent_fragments_logs_from_folder_path, Address, Block0Configuration,
+};
 
 use structopt::StructOpt;
 
 use std::collections::{HashMap, HashSet};
-use std::ops::Div;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -24,17 +35,59 @@ pub struct VotersRewards {
     #[structopt(long)]
     total_rewards: u64,
 
-    #[structopt(long)]
-    votes_count_path: PathBuf,
+    /// Path to a json file mapping hex encoded voter accounts to their amount of votes
+    #[structopt(long, required_unless_one = &["block-store", "fragment-logs"])]
+    votes_count_path: Option<PathBuf>,
+
+    /// Path to a node block store, votes are counted from the ballots in it
+    #[structopt(long, conflicts_with_all = &["votes-count-path", "fragment-logs"])]
+    block_store: Option<PathBuf>,
+
+    /// Path to a folder with persistent fragment logs, votes are counted from the ballots in them
+    #[structopt(long, conflicts_with = "votes-count-path")]
+    fragment_logs: Option<PathBuf>,
+
+    /// Range of spending counters checked when validating fragment logs
+    #[structopt(long, default_value = "1000")]
+    range_check: u32,
+
+    /// Only count ballots for block0 voteplans, cast within their voting period. Ballots
+    /// rejected when validating fragment logs are never counted
+    #[structopt(long, conflicts_with = "votes-count-path")]
+    only_valid_ballots: bool,
+
+    /// Path where computed votes count will be written, in the `votes-count-path` format
+    #[structopt(long, conflicts_with = "votes-count-path")]
+    dump_votes_count: Option<PathBuf>,
 
     #[structopt(long, default_value)]
     vote_threshold: u64,
+
+    /// Path to a yaml or json file with participation rules deciding which voters are eligible
+    /// for rewards, applied on top of `vote-threshold`
+    #[structopt(long)]
+    rules: Option<PathBuf>,
+
+    /// Path to the proposals to voteplans mapping generated along with the voteplans, needed by
+    /// per challenge rules
+    #[structopt(long)]
+    proposals_voteplans: Option<PathBuf>,
+
+    /// Path to a json registrations snapshot delegating voting power from stake keys to voting
+    /// keys. When set, voting power is taken from the snapshot instead of block0 funds and
+    /// rewards are paid to the registrations reward addresses.
+    #[structopt(long)]
+    registrations: Option<PathBuf>,
+}
+
+fn lovelace_to_ada(lovelace: u64) -> Rewards {
+    Rewards::from(lovelace) / Rewards::from(ADA_TO_LOVELACE_FACTOR)
 }
 
 fn write_rewards_results(
     common: Common,
     stake_per_voter: &HashMap<&Address, u64>,
-    share_results: &HashMap<&Address, Rewards>,
+    share_results: &HashMap<&Address, (Rewards, Eligibility)>,
     total_rewards: u64,
 ) -> Result<(), Error> {
     let writer = common.open_output()?;
@@ -43,20 +96,83 @@ fn write_rewards_results(
         "Stake of the voter (ADA)",
         "Reward for the voter (ADA)",
         "Reward for the voter (lovelace)",
+        "Excluded by rule",
     ];
     let mut csv_writer = csv::Writer::from_writer(writer);
     csv_writer.write_record(&header).map_err(Error::Csv)?;
 
-    for (address, share) in share_results.iter() {
-        let stake = stake_per_voter.get(*address).unwrap();
-        let voter_reward = reward_from_share(*share, total_rewards);
+    let addresses: HashMap<String, &Address> = share_results
+        .keys()
+        .map(|address| (address.to_string(), *address))
+        .collect();
+    let lovelace_rewards = rewards_to_lovelace(
+        share_results
+            .iter()
+            .map(|(address, (share, _))| (address.to_string(), *share)),
+        total_rewards,
+    );
+    for (address_str, voter_reward) in lovelace_rewards {
+        let address = addresses[&address_str];
+        let stake = stake_per_voter.get(address).unwrap();
+        let eligibility = &share_results[address].1;
         let record = [
-            address.to_string(),
+            address_str,
             stake.to_string(),
-            voter_reward
-                .div(&(ADA_TO_LOVELACE_FACTOR as u128))
+            lovelace_to_ada(voter_reward).to_string(),
+            voter_reward.to_string(),
+            eligibility.to_string(),
+        ];
+        csv_writer.write_record(&record).map_err(Error::Csv)?;
+    }
+    Ok(())
+}
+
+fn write_registrations_rewards_results(
+    common: Common,
+    registrations: &[Registration],
+    share_results: &HashMap<String, RewardAddressShare>,
+    total_rewards: u64,
+) -> Result<(), Error> {
+    let mut delegated_stake: HashMap<&str, u64> = HashMap::new();
+    for registration in registrations {
+        *delegated_stake
+            .entry(&registration.reward_address)
+            .or_default() += registration.weight;
+    }
+
+    let writer = common.open_output()?;
+    let header = [
+        "Reward address",
+        "Stake delegated (lovelace)",
+        "Reward for the delegations (ADA)",
+        "Reward for the delegations (lovelace)",
+        "Excluded by rule",
+    ];
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(&header).map_err(Error::Csv)?;
+
+    let lovelace_rewards = rewards_to_lovelace(
+        share_results
+            .iter()
+            .map(|(reward_address, share)| (reward_address, share.share)),
+        total_rewards,
+    );
+    for (reward_address, reward) in lovelace_rewards {
+        let record = [
+            reward_address.clone(),
+            delegated_stake
+                .get(reward_address.as_str())
+                .copied()
+                .unwrap_or_default()
                 .to_string(),
-            voter_reward.int().to_string(),
+            lovelace_to_ada(reward).to_string(),
+            reward.to_string(),
+            share_results[reward_address]
+                .excluded_by
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
         ];
         csv_writer.write_record(&record).map_err(Error::Csv)?;
     }
@@ -69,18 +185,97 @@ impl VotersRewards {
             common,
             total_rewards,
             votes_count_path,
+            block_store,
+            fragment_logs,
+            range_check,
+            only_valid_ballots,
+            dump_votes_count,
             vote_threshold,
+            rules,
+            proposals_voteplans,
+            registrations,
         } = self;
         let block = common.input.load_block()?;
         let block0 = Block0Configuration::from_block(&block)
             .map_err(jcli_lib::jcli_lib::block::Error::BuildingGenesisFromBlock0Failed)?;
 
-        let vote_count: VoteCount = serde_json::from_reader(jcli_lib::utils::io::open_file_read(
-            &Some(votes_count_path),
-        )?)?;
+        let mut rules: ParticipationRules = match rules {
+            Some(path) => {
+                serde_yaml::from_reader(jcli_lib::utils::io::open_file_read(&Some(path))?)?
+            }
+            None => ParticipationRules::default(),
+        };
+        if vote_threshold > 0 {
+            rules.rules.insert(
+                0,
+                ParticipationRule::MinVotes {
+                    votes: vote_threshold,
+                },
+            );
+        }
 
-        let addresses_vote_count: AddressesVoteCount =
-            vote_count_with_addresses(vote_count, &block0);
+        let ballots: Option<Ballots> = match (block_store, fragment_logs) {
+            (Some(block_store), _) => Some(ballots_from_block_store(
+                &block_store,
+                &block,
+                only_valid_ballots,
+            )?),
+            (None, Some(fragment_logs)) => {
+                // a skipped entry could be a ballot, so unreadable entries are not ignored
+                let fragments = load_persistent_fragments_logs_from_folder_path(&fragment_logs)?
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        entry.map_err(|e| {
+                            Error::InvalidInput(format!(
+                                "unreadable persistent fragment log entry {}: {:?}",
+                                i, e
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Some(ballots_from_fragment_logs(
+                    block.clone(),
+                    0..range_check,
+                    fragments.into_iter(),
+                    only_valid_ballots,
+                )?)
+            }
+            (None, None) => None,
+        };
+
+        let participation: AddressesParticipation = match (ballots, votes_count_path) {
+            (Some(ballots), _) => {
+                if let Some(path) = dump_votes_count {
+                    serde_json::to_writer_pretty(
+                        jcli_lib::utils::io::open_file_write(&Some(path))?,
+                        &vote_count(&ballots),
+                    )?;
+                }
+                let proposal_challenges = proposals_voteplans
+                    .as_deref()
+                    .map(read_proposal_challenges)
+                    .transpose()?;
+                if rules.needs_challenges() && proposal_challenges.is_none() {
+                    return Err(Error::InvalidInput(
+                        "per challenge rules need --proposals-voteplans".to_string(),
+                    ));
+                }
+                participation_from_ballots(&ballots, proposal_challenges.as_ref(), &block0)?
+            }
+            (None, Some(votes_count_path)) => {
+                if rules.needs_ballots() {
+                    return Err(Error::InvalidInput(
+                        "per voteplan or per challenge rules need ballots from --block-store or --fragment-logs"
+                            .to_string(),
+                    ));
+                }
+                let vote_count: VoteCount = serde_json::from_reader(
+                    jcli_lib::utils::io::open_file_read(&Some(votes_count_path))?,
+                )?;
+                participation_from_vote_count(vote_count_with_addresses(vote_count, &block0))
+            }
+            (None, None) => unreachable!("structopt requires one votes count source"),
+        };
 
         let committee_keys: HashSet<Address> = block0
             .blockchain_configuration
@@ -95,14 +290,48 @@ impl VotersRewards {
             })
             .collect();
 
-        let (total_stake, stake_per_voter) = calculate_stake(&committee_keys, &block0);
-        let rewards = calculate_reward_share(
-            total_stake,
-            &stake_per_voter,
-            &addresses_vote_count,
-            vote_threshold,
-        );
+        if let Some(registrations) = registrations {
+            let registrations: Vec<Registration> = serde_json::from_reader(
+                jcli_lib::utils::io::open_file_read(&Some(registrations))?,
+            )?;
+            let discrimination = block0.blockchain_configuration.discrimination;
+            let stakes = stake_per_voting_key(&registrations, &committee_keys, discrimination)?;
+            let stake_per_voter: HashMap<&Address, u64> = stakes
+                .iter()
+                .map(|(address, stake)| (address, *stake))
+                .collect();
+            let rewards =
+                calculate_reward_share_with_rules(&stake_per_voter, &participation, &rules)?;
+            let shares = reward_share_per_reward_address(&rewards, &registrations, discrimination)?;
+            return write_registrations_rewards_results(
+                common,
+                &registrations,
+                &shares,
+                total_rewards,
+            );
+        }
+
+        let (_total_stake, stake_per_voter) = calculate_stake(&committee_keys, &block0);
+        let rewards = calculate_reward_share_with_rules(&stake_per_voter, &participation, &rules)?;
         write_rewards_results(common, &stake_per_voter, &rewards, total_rewards)?;
         Ok(())
     }
 }
+
+fn read_proposal_challenges(path: &Path) -> Result<ProposalChallenges, Error> {
+    let entries: Vec<ProposalVotePlanEntry> =
+        serde_json::from_reader(jcli_lib::utils::io::open_file_read(&Some(path))?)?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let invalid = || Error::InvalidInput(format!("invalid mapping entry {:?}", entry));
+            Ok((
+                (
+                    FromStr::from_str(&entry.chain_voteplan_id).map_err(|_| invalid())?,
+                    entry.chain_proposal_index,
+                ),
+                entry.challenge_id.parse().map_err(|_| invalid())?,
+            ))
+        })
+        .collect()
+}
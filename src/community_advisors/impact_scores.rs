@@ -0,0 +1,130 @@
+use crate::community_advisors::models::{AdvisorReviewRow, ReviewScore};
+
+use std::collections::HashMap;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid proposal id '{0}' in advisor reviews, expected an ideascale proposal id")]
+    InvalidProposalId(String),
+}
+
+/// How advisor reviews ratings are combined into a proposal impact score
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactScoreFormula {
+    /// weight of reviews rated as excellent by veteran community advisors
+    pub excellent_weight: f32,
+    /// weight of reviews rated as good by veteran community advisors
+    pub good_weight: f32,
+    /// weight of reviews not rated by veteran community advisors
+    pub unrated_weight: f32,
+    /// if false, filtered out reviews are weighted as unrated ones
+    pub exclude_filtered_out: bool,
+}
+
+impl Default for ImpactScoreFormula {
+    /// Plain mean of every review, without filtered out ones
+    fn default() -> Self {
+        Self {
+            excellent_weight: 1.0,
+            good_weight: 1.0,
+            unrated_weight: 1.0,
+            exclude_filtered_out: true,
+        }
+    }
+}
+
+impl ImpactScoreFormula {
+    fn weight(&self, score: ReviewScore) -> f32 {
+        match score {
+            ReviewScore::Excellent => self.excellent_weight,
+            ReviewScore::Good => self.good_weight,
+            ReviewScore::NA => self.unrated_weight,
+            ReviewScore::FilteredOut if self.exclude_filtered_out => 0.0,
+            ReviewScore::FilteredOut => self.unrated_weight,
+            // malformed reviews are left out of the score
+            ReviewScore::Conflicting => 0.0,
+        }
+    }
+}
+
+/// Mean of the impact, feasibility and auditability ratings of a review, in range [0, 5]
+pub fn review_rating(review: &AdvisorReviewRow) -> f32 {
+    (review.impact_alignment_rating as f32
+        + review.feasibility_rating as f32
+        + review.auditability_rating as f32)
+        / 3.0
+}
+
+/// Compute proposals impact scores, in range [0, 5], indexed by ideascale proposal id, as the
+/// weighted mean of their reviews ratings. Proposals without any review with a positive weight
+/// do not get a score.
+pub fn impact_scores(
+    reviews: &[AdvisorReviewRow],
+    formula: &ImpactScoreFormula,
+) -> Result<HashMap<u32, f32>, Error> {
+    // proposal id -> (weighted ratings sum, weights sum)
+    let mut sums: HashMap<u32, (f32, f32)> = HashMap::new();
+    for review in reviews {
+        let proposal_id = review
+            .proposal_id
+            .parse()
+            .map_err(|_| Error::InvalidProposalId(review.proposal_id.clone()))?;
+        let weight = formula.weight(review.score());
+        let (ratings, weights) = sums.entry(proposal_id).or_default();
+        *ratings += review_rating(review) * weight;
+        *weights += weight;
+    }
+
+    Ok(sums
+        .into_iter()
+        .filter(|(_, (_, weights))| *weights > 0.0)
+        .map(|(proposal_id, (ratings, weights))| (proposal_id, ratings / weights))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(proposal_id: &str, score: ReviewScore, ratings: (u8, u8, u8)) -> AdvisorReviewRow {
+        let mut review = AdvisorReviewRow::dummy(score);
+        review.proposal_id = proposal_id.to_string();
+        review.impact_alignment_rating = ratings.0;
+        review.feasibility_rating = ratings.1;
+        review.auditability_rating = ratings.2;
+        review
+    }
+
+    #[test]
+    fn weighted_mean_of_reviews() {
+        let reviews = vec![
+            review("1", ReviewScore::Excellent, (5, 5, 5)),
+            review("1", ReviewScore::Good, (2, 2, 2)),
+            review("1", ReviewScore::FilteredOut, (0, 0, 0)),
+            review("1", ReviewScore::Conflicting, (0, 0, 0)),
+            review("2", ReviewScore::FilteredOut, (1, 1, 1)),
+        ];
+
+        let scores = impact_scores(&reviews, &ImpactScoreFormula::default()).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert!((scores[&1] - 3.5).abs() < f32::EPSILON);
+
+        let formula = ImpactScoreFormula {
+            excellent_weight: 3.0,
+            exclude_filtered_out: false,
+            ..Default::default()
+        };
+        let scores = impact_scores(&reviews, &formula).unwrap();
+        assert!((scores[&1] - 17.0 / 5.0).abs() < 1e-6);
+        assert!((scores[&2] - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn non_numeric_proposal_id() {
+        let reviews = vec![review("abc", ReviewScore::Good, (1, 1, 1))];
+        assert!(matches!(
+            impact_scores(&reviews, &ImpactScoreFormula::default()),
+            Err(Error::InvalidProposalId(_))
+        ));
+    }
+}
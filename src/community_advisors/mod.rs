@@ -1 +1,2 @@
+pub mod impact_scores;
 pub mod models;
@@ -1,7 +1,7 @@
 use crate::utils::serde::deserialize_truthy_falsy;
 use serde::Deserialize;
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct AdvisorReviewRow {
     pub proposal_id: String,
     #[serde(alias = "Idea URL")]
@@ -24,30 +24,35 @@ pub struct AdvisorReviewRow {
     excellent: bool,
     #[serde(alias = "Good", deserialize_with = "deserialize_truthy_falsy")]
     good: bool,
+    #[serde(
+        alias = "Filtered Out",
+        default,
+        deserialize_with = "deserialize_truthy_falsy"
+    )]
+    filtered_out: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReviewScore {
     Excellent,
     Good,
     FilteredOut,
     NA, // not reviewed by vCAs
+    /// marked both excellent and good, which the source of information should never do. Such
+    /// reviews are malformed and neither rewarded nor counted.
+    Conflicting,
 }
 
 impl AdvisorReviewRow {
     pub fn score(&self) -> ReviewScore {
+        if self.filtered_out {
+            return ReviewScore::FilteredOut;
+        }
         match (self.excellent, self.good) {
             (true, false) => ReviewScore::Excellent,
             (false, true) => ReviewScore::Good,
             (false, false) => ReviewScore::NA,
-            _ => {
-                // This should never happen, from the source of information a review could be either
-                // Excellent or Good or not assessed. It cannot be both and it is considered
-                // a malformed information input.
-                panic!(
-                    "Invalid combination of scores from assessor {} for proposal {}",
-                    self.assessor, self.proposal_id
-                )
-            }
+            (true, true) => ReviewScore::Conflicting,
         }
     }
 }
@@ -68,13 +73,21 @@ mod tests {
         assert_eq!(data.len(), 1);
     }
 
+    #[test]
+    fn conflicting_scores() {
+        let mut review = AdvisorReviewRow::dummy(ReviewScore::Excellent);
+        review.set_good(true);
+        assert_eq!(review.score(), ReviewScore::Conflicting);
+    }
+
     impl AdvisorReviewRow {
         pub fn dummy(score: ReviewScore) -> Self {
             let (excellent, good) = match score {
                 ReviewScore::Good => (false, true),
                 ReviewScore::Excellent => (true, false),
                 ReviewScore::NA => (false, false),
-                _ => unimplemented!(),
+                ReviewScore::FilteredOut => (false, false),
+                ReviewScore::Conflicting => (true, true),
             };
 
             AdvisorReviewRow {
@@ -91,7 +104,12 @@ mod tests {
                 auditability_rating: 0,
                 excellent,
                 good,
+                filtered_out: matches!(score, ReviewScore::FilteredOut),
             }
         }
+
+        pub fn set_good(&mut self, good: bool) {
+            self.good = good;
+        }
     }
 }
@@ -0,0 +1,147 @@
+use crate::ideascale::models::custom_fields::CustomFieldTags;
+use crate::ideascale::models::de::parse_requested_funds;
+use crate::ideascale::{get_from_extra_fields, IdeaScaleData};
+
+use serde::Serialize;
+
+/// Requested funds of the proposals of a challenge against its budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChallengeBudget {
+    pub challenge_id: u32,
+    pub title: String,
+    pub budget: u64,
+    pub requested_funds: u64,
+    pub proposals: usize,
+    /// Proposals whose requested funds could not be parsed, not included in `requested_funds`
+    pub unparsed_proposals: usize,
+}
+
+impl ChallengeBudget {
+    pub fn is_over_budget(&self) -> bool {
+        self.requested_funds > self.budget
+    }
+}
+
+/// Per challenge summary of requested funds, sorted by ideascale challenge id
+pub fn budget_summary(
+    ideascale_data: &IdeaScaleData,
+    tags: &CustomFieldTags,
+) -> Vec<ChallengeBudget> {
+    let mut summary: Vec<ChallengeBudget> = ideascale_data
+        .challenges
+        .values()
+        .map(|c| ChallengeBudget {
+            challenge_id: c.id,
+            title: c.title.clone(),
+            budget: c.rewards.clone().into(),
+            requested_funds: 0,
+            proposals: 0,
+            unparsed_proposals: 0,
+        })
+        .collect();
+    summary.sort_by_key(|c| c.challenge_id);
+
+    for proposal in ideascale_data.proposals.values() {
+        let challenge =
+            match summary.binary_search_by_key(&proposal.challenge_id, |c| c.challenge_id) {
+                Ok(i) => &mut summary[i],
+                Err(_) => continue,
+            };
+        challenge.proposals += 1;
+        match get_from_extra_fields(&proposal.custom_fields.fields, &tags.proposal_funds)
+            .and_then(|funds| parse_requested_funds(&funds))
+        {
+            Some(funds) => challenge.requested_funds += funds,
+            None => challenge.unparsed_proposals += 1,
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ideascale::models::de::{Fund, Proposal};
+    use serde_json::json;
+
+    fn proposal(id: u32, challenge_id: u32, funds: Option<&str>) -> Proposal {
+        let mut fields = json!({});
+        if let Some(funds) = funds {
+            fields["requested_funds"] = json!(funds);
+        }
+        serde_json::from_value(json!({
+            "id": id,
+            "title": "title",
+            "text": "summary",
+            "url": "url",
+            "customFieldsByKey": fields,
+            "authorInfo": { "name": "name", "email": "email" },
+            "stageId": 1,
+            "stageLabel": "Governance phase",
+            "campaignId": challenge_id,
+            "flag": "approved",
+        }))
+        .unwrap()
+    }
+
+    fn challenge(id: u32, budget: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "name": format!("F7: challenge {}", id),
+            "tagline": budget,
+            "description": "description",
+            "groupId": 1,
+            "funnelId": 1,
+            "campaignUrl": "url",
+        })
+    }
+
+    #[test]
+    fn requested_funds_per_challenge() {
+        let fund: Fund = serde_json::from_value(json!({
+            "id": 1,
+            "name": "Fund7",
+            "campaigns": [challenge(1, "$100,000 in ada"), challenge(2, "$50,000 in ada")],
+        }))
+        .unwrap();
+        let proposals = vec![
+            proposal(1, 1, Some("$60,000")),
+            proposal(2, 1, Some("50000 ADA")),
+            proposal(3, 2, Some("10,000")),
+            proposal(4, 2, Some("a lot")),
+            proposal(5, 2, None),
+        ];
+        let data = IdeaScaleData {
+            funnels: Default::default(),
+            challenges: fund.challenges.iter().map(|c| (c.id, c.clone())).collect(),
+            fund,
+            proposals: proposals.into_iter().map(|p| (p.proposal_id, p)).collect(),
+        };
+
+        let summary = budget_summary(&data, &CustomFieldTags::default());
+        assert_eq!(
+            summary,
+            vec![
+                ChallengeBudget {
+                    challenge_id: 1,
+                    title: "challenge 1".to_string(),
+                    budget: 100_000,
+                    requested_funds: 110_000,
+                    proposals: 2,
+                    unparsed_proposals: 0,
+                },
+                ChallengeBudget {
+                    challenge_id: 2,
+                    title: "challenge 2".to_string(),
+                    budget: 50_000,
+                    requested_funds: 10_000,
+                    proposals: 3,
+                    unparsed_proposals: 2,
+                },
+            ]
+        );
+        assert!(summary[0].is_over_budget());
+        assert!(!summary[1].is_over_budget());
+    }
+}
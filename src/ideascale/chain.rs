@@ -0,0 +1,221 @@
+use crate::ideascale::voteplans::ProposalVotePlanEntry;
+use crate::recovery::tally::{
+    blockdate_to_system_time, timeframe_and_era_from_block0_configuration, voteplans_from_block0,
+};
+
+use chain_impl_mockchain::block::{Block, BlockDate};
+use chain_impl_mockchain::certificate::{ExternalProposalId, VotePlanId};
+use chain_impl_mockchain::vote::PayloadType;
+use jormungandr_lib::interfaces::{Block0Configuration, Block0ConfigurationError};
+
+use std::collections::HashMap;
+use std::time::UNIX_EPOCH;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Block0Configuration(#[from] Block0ConfigurationError),
+
+    #[error("block date {0} cannot be converted into a wall clock time")]
+    InvalidBlockDate(BlockDate),
+
+    #[error("voteplan {voteplan} of proposal {proposal_id} not found in block0")]
+    VotePlanNotFound {
+        proposal_id: String,
+        voteplan: String,
+    },
+
+    #[error("proposal {proposal_id} not found at index {index} of voteplan {voteplan} in block0")]
+    ProposalNotFound {
+        proposal_id: String,
+        voteplan: String,
+        index: u8,
+    },
+}
+
+/// Voteplan data needed by the servicing station, with dates as unix timestamps (seconds)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainVotePlan {
+    pub id: VotePlanId,
+    pub vote_start: i64,
+    pub vote_end: i64,
+    pub committee_end: i64,
+    pub payload_type: PayloadType,
+}
+
+/// Location of a proposal in the block0 voteplans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalChainLink {
+    /// index of the voteplan in [`ChainLinks::voteplans`]
+    pub voteplan: usize,
+    /// index of the proposal inside its voteplan
+    pub index: u8,
+    /// external id of the proposal, as found in the voteplan certificate
+    pub external_id: ExternalProposalId,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChainLinks {
+    pub voteplans: Vec<ChainVotePlan>,
+    /// links by ideascale proposal id
+    pub proposals: HashMap<String, ProposalChainLink>,
+}
+
+impl ChainLinks {
+    /// Load the block0 voteplans and locate in them the proposals of `mapping`, as written by
+    /// `ideascale voteplans` along with the voteplan definitions. Every mapped proposal has to be
+    /// found in block0 at the expected index and with the expected external id.
+    pub fn from_block0(block0: &Block, mapping: &[ProposalVotePlanEntry]) -> Result<Self, Error> {
+        let block0_configuration = Block0Configuration::from_block(block0)?;
+        let (timeframe, era) = timeframe_and_era_from_block0_configuration(&block0_configuration);
+        let to_timestamp = |blockdate: BlockDate| {
+            blockdate_to_system_time(blockdate, &timeframe, &era)
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs() as i64)
+                .ok_or(Error::InvalidBlockDate(blockdate))
+        };
+
+        let mut voteplans: Vec<_> = voteplans_from_block0(block0).into_iter().collect();
+        // keep a stable order, voteplans are returned from a hash map
+        voteplans.sort_by_key(|(id, _)| id.to_string());
+
+        let mut links = ChainLinks::default();
+        let mut external_ids: HashMap<String, (usize, Vec<ExternalProposalId>)> = HashMap::new();
+        for (id, voteplan) in voteplans {
+            external_ids.insert(
+                id.to_string(),
+                (
+                    links.voteplans.len(),
+                    voteplan
+                        .proposals()
+                        .iter()
+                        .map(|proposal| proposal.external_id().clone())
+                        .collect(),
+                ),
+            );
+            links.voteplans.push(ChainVotePlan {
+                id,
+                vote_start: to_timestamp(voteplan.vote_start())?,
+                vote_end: to_timestamp(voteplan.vote_end())?,
+                committee_end: to_timestamp(voteplan.committee_end())?,
+                payload_type: voteplan.payload_type(),
+            });
+        }
+
+        for entry in mapping {
+            let (voteplan, proposals) =
+                external_ids.get(&entry.chain_voteplan_id).ok_or_else(|| {
+                    Error::VotePlanNotFound {
+                        proposal_id: entry.proposal_id.clone(),
+                        voteplan: entry.chain_voteplan_id.clone(),
+                    }
+                })?;
+            let external_id = proposals
+                .get(entry.chain_proposal_index as usize)
+                .filter(|external_id| external_id.to_string() == entry.chain_proposal_id)
+                .ok_or_else(|| Error::ProposalNotFound {
+                    proposal_id: entry.proposal_id.clone(),
+                    voteplan: entry.chain_voteplan_id.clone(),
+                    index: entry.chain_proposal_index,
+                })?;
+            links.proposals.insert(
+                entry.proposal_id.clone(),
+                ProposalChainLink {
+                    voteplan: *voteplan,
+                    index: entry.chain_proposal_index,
+                    external_id: external_id.clone(),
+                },
+            );
+        }
+        Ok(links)
+    }
+
+    pub fn proposal_link(&self, proposal_id: &str) -> Option<(&ChainVotePlan, &ProposalChainLink)> {
+        self.proposals
+            .get(proposal_id)
+            .map(|link| (&self.voteplans[link.voteplan], link))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::Discrimination;
+    use chain_impl_mockchain::certificate::VotePlan;
+    use jormungandr_testing_utils::testing::jormungandr::ConfigurationBuilder;
+    use jormungandr_testing_utils::testing::{vote_plan_cert, VotePlanBuilder};
+    use jormungandr_testing_utils::wallet::Wallet as TestWallet;
+    use rand::rngs::OsRng;
+
+    fn block0_with(vote_plan: &VotePlan) -> Block {
+        let alice =
+            TestWallet::new_account_with_discrimination(&mut OsRng, Discrimination::Production);
+        let vote_plan_cert = vote_plan_cert(
+            &alice,
+            BlockDate {
+                epoch: 1,
+                slot_id: 0,
+            },
+            vote_plan,
+        )
+        .into();
+        ConfigurationBuilder::new()
+            .with_funds(vec![alice.to_initial_fund(1_000_000)])
+            .with_certs(vec![vote_plan_cert])
+            .with_discrimination(Discrimination::Production)
+            .with_committees(&[&alice])
+            .build_block0()
+            .to_block()
+    }
+
+    fn entry(vote_plan: &VotePlan, proposal_id: &str, index: u8) -> ProposalVotePlanEntry {
+        ProposalVotePlanEntry {
+            proposal_id: proposal_id.to_string(),
+            internal_id: index.to_string(),
+            challenge_id: "1".to_string(),
+            chain_proposal_id: vote_plan
+                .proposals()
+                .iter()
+                .nth(index as usize)
+                .unwrap()
+                .external_id()
+                .to_string(),
+            chain_voteplan_id: vote_plan.to_id().to_string(),
+            chain_proposal_index: index,
+        }
+    }
+
+    #[test]
+    fn mapped_proposals_are_linked() {
+        let vote_plan = VotePlanBuilder::new().proposals_count(2).public().build();
+        let block0 = block0_with(&vote_plan);
+        let mapping = vec![entry(&vote_plan, "100", 0), entry(&vote_plan, "101", 1)];
+
+        let links = ChainLinks::from_block0(&block0, &mapping).unwrap();
+        assert_eq!(links.voteplans.len(), 1);
+        let (voteplan, link) = links.proposal_link("101").unwrap();
+        assert_eq!(voteplan.id, vote_plan.to_id());
+        assert_eq!(link.index, 1);
+        assert_eq!(link.external_id.to_string(), mapping[1].chain_proposal_id);
+        assert!(links.proposal_link("102").is_none());
+    }
+
+    #[test]
+    fn mapping_has_to_match_block0() {
+        let vote_plan = VotePlanBuilder::new().proposals_count(2).public().build();
+        let block0 = block0_with(&vote_plan);
+
+        let mut wrong_index = entry(&vote_plan, "100", 0);
+        wrong_index.chain_proposal_index = 1;
+        assert!(matches!(
+            ChainLinks::from_block0(&block0, &[wrong_index]),
+            Err(Error::ProposalNotFound { index: 1, .. })
+        ));
+
+        let other_vote_plan = VotePlanBuilder::new().proposals_count(3).public().build();
+        assert!(matches!(
+            ChainLinks::from_block0(&block0, &[entry(&other_vote_plan, "100", 0)]),
+            Err(Error::VotePlanNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,439 @@
+//! Write an import straight into a vit-servicing-station database
+
+use crate::ideascale::chain::{ChainLinks, ChainVotePlan, ProposalChainLink};
+use crate::ideascale::models::se;
+
+use chain_impl_mockchain::vote::PayloadType;
+use diesel::{Connection, Insertable, QueryDsl, RunQueryDsl, SqliteConnection};
+use vit_servicing_station_lib::db::{
+    self,
+    models::{
+        challenges::{Challenge, ChallengeType},
+        funds::Fund,
+        proposals::{
+            community_choice, simple, Category, Proposal, ProposalChallengeInfo, Proposer,
+        },
+        vote_options::VoteOptions,
+        voteplans::Voteplan,
+    },
+    queries, schema,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("could not open servicing station database: {0}")]
+    Connection(String),
+
+    #[error("could not initialize servicing station database schema: {0}")]
+    Migration(String),
+
+    #[error(transparent)]
+    Query(#[from] diesel::result::Error),
+
+    #[error("invalid {field} '{value}' for proposal {proposal_id}")]
+    InvalidProposalField {
+        proposal_id: String,
+        field: &'static str,
+        value: String,
+    },
+
+    #[error("invalid challenge id '{0}'")]
+    InvalidChallengeId(String),
+}
+
+/// Summary of a database import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbImport {
+    pub challenges: usize,
+    pub proposals: usize,
+    pub voteplans: usize,
+    /// proposals without a matching proposal in the block0 voteplans
+    pub unlinked_proposals: usize,
+}
+
+/// Write fund, challenges and proposals (and voteplans if `chain_links` is available) into the
+/// servicing station database at `db_url`, creating its schema if needed.
+///
+/// Proposals are linked to the block0 voteplans they were mapped to, see
+/// [`ChainLinks::from_block0`].
+pub fn write_to_db(
+    db_url: &str,
+    fund: &se::Fund,
+    challenges: &[&se::Challenge],
+    proposals: &[se::Proposal],
+    chain_links: Option<&ChainLinks>,
+) -> Result<DbImport, Error> {
+    let pool = db::load_db_connection_pool(db_url).map_err(|e| Error::Connection(e.to_string()))?;
+    let db_conn = pool.get().map_err(|e| Error::Connection(e.to_string()))?;
+    db::migrations::initialize_db_with_migration(&db_conn)
+        .map_err(|e| Error::Migration(e.to_string()))?;
+
+    insert_import(&db_conn, fund, challenges, proposals, chain_links)
+}
+
+fn insert_import(
+    db_conn: &SqliteConnection,
+    fund: &se::Fund,
+    challenges: &[&se::Challenge],
+    proposals: &[se::Proposal],
+    chain_links: Option<&ChainLinks>,
+) -> Result<DbImport, Error> {
+    let default_links = ChainLinks::default();
+    let chain_links = chain_links.unwrap_or(&default_links);
+
+    // voteplan ids are not taken from the import, keep clear of the ones already in the database
+    let first_voteplan_id = schema::voteplans::table
+        .select(diesel::dsl::max(schema::voteplans::id))
+        .first::<Option<i32>>(db_conn)?
+        .map_or(0, |id| id + 1);
+    let voteplans: Vec<Voteplan> = chain_links
+        .voteplans
+        .iter()
+        .zip(first_voteplan_id..)
+        .map(|(voteplan, id)| Voteplan {
+            id,
+            chain_voteplan_id: voteplan.id.to_string(),
+            chain_vote_start_time: voteplan.vote_start,
+            chain_vote_end_time: voteplan.vote_end,
+            chain_committee_end_time: voteplan.committee_end,
+            chain_voteplan_payload: payload_type_name(voteplan.payload_type).to_string(),
+            // the election key is not part of the voteplan certificate
+            chain_vote_encryption_key: String::new(),
+            fund_id: fund.id,
+        })
+        .collect();
+
+    let challenges = challenges
+        .iter()
+        .map(|challenge| build_challenge(challenge))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut unlinked_proposals = 0;
+    let mut db_proposals = Vec::with_capacity(proposals.len());
+    for proposal in proposals {
+        let link = chain_links.proposal_link(&proposal.proposal_id);
+        if link.is_none() {
+            unlinked_proposals += 1;
+        }
+        db_proposals.push((
+            build_proposal(fund, proposal, link)?,
+            challenge_info(proposal),
+        ));
+    }
+
+    let vit_fund = Fund {
+        id: fund.id,
+        fund_name: format!("Fund{}", fund.id),
+        fund_goal: fund.goal.clone(),
+        voting_power_threshold: fund.threshold,
+        fund_start_time: voteplans
+            .iter()
+            .map(|v| v.chain_vote_start_time)
+            .min()
+            .unwrap_or_default(),
+        fund_end_time: voteplans
+            .iter()
+            .map(|v| v.chain_vote_end_time)
+            .max()
+            .unwrap_or_default(),
+        next_fund_start_time: 0,
+        registration_snapshot_time: 0,
+        next_registration_snapshot_time: 0,
+        chain_vote_plans: voteplans.clone(),
+        challenges: challenges.clone(),
+    };
+
+    let summary = DbImport {
+        challenges: challenges.len(),
+        proposals: db_proposals.len(),
+        voteplans: voteplans.len(),
+        unlinked_proposals,
+    };
+
+    db_conn.transaction::<_, Error, _>(|| {
+        queries::funds::insert_fund(vit_fund, db_conn)?;
+        queries::voteplans::batch_insert_voteplans(
+            &voteplans
+                .into_iter()
+                .map(|v| v.values())
+                .collect::<Vec<_>>(),
+            db_conn,
+        )?;
+        queries::challenges::batch_insert_challenges(
+            &challenges
+                .into_iter()
+                .map(|c| c.values())
+                .collect::<Vec<_>>(),
+            db_conn,
+        )?;
+
+        let mut simple_data = Vec::new();
+        let mut community_choice_data = Vec::new();
+        for (proposal, info) in &db_proposals {
+            match info {
+                ProposalChallengeInfo::Simple(data) => {
+                    simple_data.push(data.to_sql_values_with_proposal_id(&proposal.proposal_id))
+                }
+                ProposalChallengeInfo::CommunityChoice(data) => community_choice_data
+                    .push(data.to_sql_values_with_proposal_id(&proposal.proposal_id)),
+            }
+        }
+        queries::proposals::batch_insert_proposals(
+            &db_proposals
+                .into_iter()
+                .map(|(proposal, _)| proposal.values())
+                .collect::<Vec<_>>(),
+            db_conn,
+        )?;
+        queries::proposals::batch_insert_simple_challenge_data(&simple_data, db_conn)?;
+        queries::proposals::batch_insert_community_choice_challenge_data(
+            &community_choice_data,
+            db_conn,
+        )?;
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
+
+fn build_challenge(challenge: &se::Challenge) -> Result<Challenge, Error> {
+    Ok(Challenge {
+        id: challenge
+            .id
+            .parse()
+            .map_err(|_| Error::InvalidChallengeId(challenge.id.clone()))?,
+        challenge_type: match challenge.challenge_type.as_str() {
+            "community-choice" => ChallengeType::CommunityChoice,
+            _ => ChallengeType::Simple,
+        },
+        title: challenge.title.clone(),
+        description: challenge.description.clone(),
+        rewards_total: challenge.rewards_total as i64,
+        // ideascale only gives the challenge budget, which is entirely paid out to the funded
+        // proposals: there is no separate amount for proposers
+        proposers_rewards: challenge.rewards_total as i64,
+        fund_id: challenge
+            .fund_id
+            .parse()
+            .map_err(|_| Error::InvalidChallengeId(challenge.fund_id.clone()))?,
+        challenge_url: challenge.challenge_url.clone(),
+    })
+}
+
+fn build_proposal(
+    fund: &se::Fund,
+    proposal: &se::Proposal,
+    link: Option<(&ChainVotePlan, &ProposalChainLink)>,
+) -> Result<Proposal, Error> {
+    let invalid = |field: &'static str, value: &str| Error::InvalidProposalField {
+        proposal_id: proposal.proposal_id.clone(),
+        field,
+        value: value.to_string(),
+    };
+
+    Ok(Proposal {
+        internal_id: proposal
+            .internal_id
+            .parse()
+            .map_err(|_| invalid("internal_id", &proposal.internal_id))?,
+        proposal_id: proposal.proposal_id.clone(),
+        proposal_category: Category {
+            category_id: String::new(),
+            category_name: proposal.category_name.clone(),
+            category_description: String::new(),
+        },
+        proposal_title: proposal.proposal_title.clone(),
+        proposal_summary: proposal.proposal_summary.clone(),
+        proposal_public_key: String::new(),
+        proposal_funds: proposal.proposal_funds as i64,
+        proposal_url: proposal.proposal_url.clone(),
+        proposal_files_url: String::new(),
+        proposal_impact_score: proposal
+            .proposal_impact_score
+            .parse()
+            .map_err(|_| invalid("proposal_impact_score", &proposal.proposal_impact_score))?,
+        proposer: Proposer {
+            proposer_name: proposal.proposer_name.clone(),
+            proposer_email: proposal.proposer_email.clone(),
+            proposer_url: proposal.proposer_url.clone(),
+            proposer_relevant_experience: proposal.proposer_relevant_experience.clone(),
+        },
+        chain_proposal_id: link.map_or_else(Vec::new, |(_, link)| {
+            link.external_id.to_string().into_bytes()
+        }),
+        chain_proposal_index: link.map_or(0, |(_, link)| link.index as i64),
+        chain_vote_options: VoteOptions::parse_coma_separated_value(&proposal.chain_vote_options),
+        chain_voteplan_id: link.map_or_else(String::new, |(voteplan, _)| voteplan.id.to_string()),
+        chain_vote_start_time: link.map_or(0, |(voteplan, _)| voteplan.vote_start),
+        chain_vote_end_time: link.map_or(0, |(voteplan, _)| voteplan.vote_end),
+        chain_committee_end_time: link.map_or(0, |(voteplan, _)| voteplan.committee_end),
+        chain_voteplan_payload: link.map_or_else(
+            || proposal.chain_vote_type.clone(),
+            |(voteplan, _)| payload_type_name(voteplan.payload_type).to_string(),
+        ),
+        chain_vote_encryption_key: String::new(),
+        fund_id: fund.id,
+        challenge_id: proposal
+            .challenge_id
+            .parse()
+            .map_err(|_| invalid("challenge_id", &proposal.challenge_id))?,
+        reviews_count: 0,
+    })
+}
+
+fn challenge_info(proposal: &se::Proposal) -> ProposalChallengeInfo {
+    match proposal.challenge_type.as_str() {
+        "community-choice" => {
+            ProposalChallengeInfo::CommunityChoice(community_choice::ChallengeInfo {
+                proposal_brief: proposal.proposal_brief.clone().unwrap_or_default(),
+                proposal_importance: proposal.proposal_importance.clone().unwrap_or_default(),
+                proposal_goal: proposal.proposal_goal.clone().unwrap_or_default(),
+                proposal_metrics: proposal.proposal_metrics.clone().unwrap_or_default(),
+            })
+        }
+        _ => ProposalChallengeInfo::Simple(simple::ChallengeInfo {
+            proposal_solution: proposal.proposal_solution.clone().unwrap_or_default(),
+        }),
+    }
+}
+
+fn payload_type_name(payload_type: PayloadType) -> &'static str {
+    match payload_type {
+        PayloadType::Public => "public",
+        PayloadType::Private => "private",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ideascale::voteplans::proposal_external_id;
+    use diesel::ExpressionMethods;
+    use serde_json::json;
+
+    fn fund(id: i32) -> se::Fund {
+        se::Fund {
+            id,
+            goal: "goal".to_string(),
+            threshold: 500,
+        }
+    }
+
+    fn challenge(id: u32, fund_id: i32) -> se::Challenge {
+        se::Challenge {
+            challenge_type: "simple".to_string(),
+            challenge_url: "url".to_string(),
+            description: "description".to_string(),
+            fund_id: fund_id.to_string(),
+            id: id.to_string(),
+            rewards_total: 100_000,
+            title: "title".to_string(),
+        }
+    }
+
+    fn proposal(proposal_id: u32, internal_id: u32, challenge_id: u32) -> se::Proposal {
+        serde_json::from_value(json!({
+            "category_name": "Fund7",
+            "challenge_id": challenge_id.to_string(),
+            "challenge_type": "simple",
+            "chain_vote_type": "public",
+            "internal_id": internal_id.to_string(),
+            "proposal_funds": 1000,
+            "proposal_id": proposal_id.to_string(),
+            "proposal_impact_score": "400",
+            "proposal_summary": "summary",
+            "proposal_title": "title",
+            "proposal_url": "url",
+            "proposer_email": "email",
+            "proposer_name": "name",
+            "proposer_relevant_experience": "experience",
+        }))
+        .unwrap()
+    }
+
+    fn chain_links(voteplan: u8, proposal_id: &str) -> ChainLinks {
+        ChainLinks {
+            voteplans: vec![ChainVotePlan {
+                id: format!("{:064x}", voteplan).parse().unwrap(),
+                vote_start: 10,
+                vote_end: 20,
+                committee_end: 30,
+                payload_type: PayloadType::Public,
+            }],
+            proposals: vec![(
+                proposal_id.to_string(),
+                ProposalChainLink {
+                    voteplan: 0,
+                    index: 0,
+                    external_id: proposal_external_id(proposal_id),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn imports_are_written_and_voteplan_ids_do_not_collide() {
+        let pool = db::load_db_connection_pool(":memory:").unwrap();
+        let db_conn = pool.get().unwrap();
+        db::migrations::initialize_db_with_migration(&db_conn).unwrap();
+
+        let import = insert_import(
+            &db_conn,
+            &fund(1),
+            &[&challenge(1, 1)],
+            &[proposal(10, 0, 1), proposal(11, 1, 1)],
+            Some(&chain_links(1, "10")),
+        )
+        .unwrap();
+        assert_eq!(
+            import,
+            DbImport {
+                challenges: 1,
+                proposals: 2,
+                voteplans: 1,
+                unlinked_proposals: 1,
+            }
+        );
+        insert_import(
+            &db_conn,
+            &fund(2),
+            &[&challenge(2, 2)],
+            &[proposal(20, 2, 2)],
+            Some(&chain_links(2, "20")),
+        )
+        .unwrap();
+
+        let voteplans = schema::voteplans::table
+            .select((schema::voteplans::id, schema::voteplans::fund_id))
+            .order(schema::voteplans::id)
+            .load::<(i32, i32)>(&db_conn)
+            .unwrap();
+        assert_eq!(voteplans, vec![(0, 1), (1, 2)]);
+
+        let proposals = schema::proposals::table
+            .select((
+                schema::proposals::proposal_id,
+                schema::proposals::chain_voteplan_id,
+                schema::proposals::chain_vote_start_time,
+            ))
+            .order(schema::proposals::proposal_id)
+            .load::<(String, String, i64)>(&db_conn)
+            .unwrap();
+        assert_eq!(
+            proposals,
+            vec![
+                ("10".to_string(), format!("{:064x}", 1), 10),
+                ("11".to_string(), String::new(), 0),
+                ("20".to_string(), format!("{:064x}", 2), 10),
+            ]
+        );
+
+        let challenges: i64 = schema::challenges::table
+            .count()
+            .get_result(&db_conn)
+            .unwrap();
+        assert_eq!(challenges, 2);
+    }
+}
@@ -1,72 +1,330 @@
 use crate::ideascale::models::de::{Fund, Funnel, Proposal, Stage};
 
 use once_cell::sync::Lazy;
+use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use url::Url;
 
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::time::Duration;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
     RequestError(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    UrlError(#[from] url::ParseError),
+
     #[error("Could not get value from json, missing attribute {attribute_name}")]
     MissingAttribute { attribute_name: &'static str },
+
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Request to {url} failed with status {status}")]
+    UnsuccessfulRequest { url: Url, status: StatusCode },
 }
 
 pub type Scores = HashMap<u32, f32>;
 
-static BASE_IDEASCALE_URL: Lazy<url::Url> = Lazy::new(|| {
+pub static BASE_IDEASCALE_URL: Lazy<url::Url> = Lazy::new(|| {
     "https://cardano.ideascale.com/a/rest/v1/"
         .try_into()
         .unwrap()
 });
 
-static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+pub const FUNDS_PATH: &str = "campaigns/groups";
+pub const FUNNELS_PATH: &str = "funnels";
+pub const STAGES_PATH: &str = "stages";
 
-async fn request_data<T: DeserializeOwned>(api_token: String, url: Url) -> Result<T, Error> {
-    CLIENT
-        .get(url)
-        .header("api_token", api_token)
-        .send()
-        .await?
-        .json()
-        .await
-        .map_err(Error::RequestError)
-}
+pub const DEFAULT_PAGE_SIZE: usize = 1000;
+pub const DEFAULT_CONCURRENCY: usize = 8;
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
 
-pub async fn get_funds_data(api_token: String) -> Result<Vec<Fund>, Error> {
-    request_data(
-        api_token,
-        BASE_IDEASCALE_URL.join("campaigns/groups").unwrap(),
-    )
-    .await
+/// Ideascale REST API client
+#[derive(Clone, Debug)]
+pub struct IdeascaleClient {
+    client: reqwest::Client,
+    base_url: Url,
+    api_token: String,
+    page_size: usize,
+    concurrency: usize,
+    max_retries: u32,
+    retry_backoff: Duration,
 }
 
-pub async fn get_stages(api_token: String) -> Result<Vec<Stage>, Error> {
-    request_data(api_token, BASE_IDEASCALE_URL.join("stages").unwrap()).await
-}
+impl IdeascaleClient {
+    pub fn new(api_token: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: BASE_IDEASCALE_URL.clone(),
+            api_token,
+            page_size: DEFAULT_PAGE_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+        }
+    }
 
-pub async fn get_proposals_data(
-    challenge_id: u32,
-    api_token: String,
-) -> Result<Vec<Proposal>, Error> {
-    request_data(
-        api_token,
-        BASE_IDEASCALE_URL
-            // ideascale API have some pager system which is not easy to find in the documentation
-            // https://a.ideascale.com/api-docs/index.html#/rest-api-controller-v-1/ideasByCampaignUsingGET_2
-            // in this case we want all of them, easiest way is to max out the page size.
-            .join(&format!("campaigns/{}/ideas/0/100000", challenge_id))
-            .unwrap(),
-    )
-    .await
+    /// Use a different API base url, for example a local mock of the API
+    pub fn with_base_url(mut self, mut base_url: Url) -> Self {
+        // paths are joined to the base url, which would replace its last segment otherwise
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Max number of requests running at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Max number of retries for rate limited (429) or server errors (5xx)
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Wait before the first retry, doubled on every following one
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    async fn request_data<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let raw = self.request_raw(path).await?;
+        serde_json::from_value(raw).map_err(Error::from)
+    }
+
+    /// Request the raw json response for an API path relative to the base url
+    pub async fn request_raw(&self, path: &str) -> Result<serde_json::Value, Error> {
+        let url = self.base_url.join(path)?;
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .client
+                .get(url.clone())
+                .header("api_token", &self.api_token)
+                .send()
+                .await?;
+            let status = response.status();
+            if status.is_success() {
+                return response.json().await.map_err(Error::RequestError);
+            }
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt >= self.max_retries {
+                return Err(Error::UnsuccessfulRequest { url, status });
+            }
+            let backoff = self.retry_backoff * 2u32.saturating_pow(attempt);
+            log::warn!(
+                "Request to {} failed with status {}, retrying in {:?}",
+                url,
+                status,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    pub async fn get_funds_data(&self) -> Result<Vec<Fund>, Error> {
+        self.request_data(FUNDS_PATH).await
+    }
+
+    pub async fn get_stages(&self) -> Result<Vec<Stage>, Error> {
+        self.request_data(STAGES_PATH).await
+    }
+
+    pub async fn get_funnels_data_for_fund(&self) -> Result<Vec<Funnel>, Error> {
+        self.request_data(FUNNELS_PATH).await
+    }
+
+    /// Fetch a single page of proposals of a challenge
+    pub async fn get_proposals_page(
+        &self,
+        challenge_id: u32,
+        page: usize,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        // ideascale API have some pager system which is not easy to find in the documentation
+        // https://a.ideascale.com/api-docs/index.html#/rest-api-controller-v-1/ideasByCampaignUsingGET_2
+        self.request_data(&format!(
+            "campaigns/{}/ideas/{}/{}",
+            challenge_id, page, self.page_size
+        ))
+        .await
+    }
+
+    /// Fetch all raw proposals of a challenge, page by page, until an empty page is returned.
+    /// The server may cap the page size, so a non full page does not mean it is the last one
+    pub async fn get_raw_proposals_data(
+        &self,
+        challenge_id: u32,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        let mut proposals = Vec::new();
+        for page in 0.. {
+            let page_proposals = self.get_proposals_page(challenge_id, page).await?;
+            if page_proposals.is_empty() {
+                break;
+            }
+            proposals.extend(page_proposals);
+        }
+        Ok(proposals)
+    }
+
+    pub async fn get_proposals_data(&self, challenge_id: u32) -> Result<Vec<Proposal>, Error> {
+        self.get_raw_proposals_data(challenge_id)
+            .await?
+            .into_iter()
+            .map(|proposal| serde_json::from_value(proposal).map_err(Error::from))
+            .collect()
+    }
 }
 
-pub async fn get_funnels_data_for_fund(api_token: String) -> Result<Vec<Funnel>, Error> {
-    let challenges: Vec<Funnel> =
-        request_data(api_token, BASE_IDEASCALE_URL.join("funnels").unwrap()).await?;
-    Ok(challenges)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+
+    /// Minimal http server answering the given responses in order. Returns its base url (without
+    /// trailing slash) and the paths of the requests it received.
+    fn mock_server(responses: Vec<(u16, String)>) -> (Url, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/a/rest/v1", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let (paths, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap();
+                paths.send(path.to_string()).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn page(ids: std::ops::Range<u32>) -> (u16, String) {
+        let proposals: Vec<serde_json::Value> =
+            ids.map(|id| serde_json::json!({ "id": id })).collect();
+        (200, serde_json::to_string(&proposals).unwrap())
+    }
+
+    #[test]
+    fn base_url_without_trailing_slash_is_kept() {
+        let client = IdeascaleClient::new(String::new())
+            .with_base_url("http://localhost/a/rest/v1".parse().unwrap());
+        assert_eq!(
+            client.base_url.join(FUNDS_PATH).unwrap().as_str(),
+            "http://localhost/a/rest/v1/campaigns/groups"
+        );
+    }
+
+    fn test_client(url: Url) -> IdeascaleClient {
+        IdeascaleClient::new(String::new())
+            .with_base_url(url)
+            .with_retry_backoff(Duration::from_millis(0))
+    }
+
+    #[tokio::test]
+    async fn proposals_are_fetched_until_an_empty_page() {
+        let (url, paths) = mock_server(vec![page(0..2), page(2..4), page(4..5), page(5..5)]);
+        let client = test_client(url).with_page_size(2);
+
+        let proposals = client.get_raw_proposals_data(7).await.unwrap();
+        assert_eq!(proposals.len(), 5);
+        assert_eq!(proposals[4]["id"], 4);
+        assert_eq!(
+            paths.try_iter().collect::<Vec<_>>(),
+            vec![
+                "/a/rest/v1/campaigns/7/ideas/0/2",
+                "/a/rest/v1/campaigns/7/ideas/1/2",
+                "/a/rest/v1/campaigns/7/ideas/2/2",
+                "/a/rest/v1/campaigns/7/ideas/3/2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_capped_by_the_server_are_all_fetched() {
+        let (url, paths) = mock_server(vec![page(0..2), page(2..4), page(4..4)]);
+        let client = test_client(url).with_page_size(1000);
+
+        let proposals = client.get_raw_proposals_data(7).await.unwrap();
+        assert_eq!(proposals.len(), 4);
+        assert_eq!(paths.try_iter().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_and_server_errors_are_retried() {
+        let (url, paths) = mock_server(vec![
+            (429, String::new()),
+            (503, String::new()),
+            (200, "[]".to_string()),
+        ]);
+        let client = test_client(url);
+
+        assert!(client.get_funds_data().await.unwrap().is_empty());
+        assert_eq!(paths.try_iter().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_are_limited() {
+        let (url, paths) = mock_server(vec![(500, String::new()), (500, String::new())]);
+        let client = test_client(url).with_max_retries(1);
+
+        assert!(matches!(
+            client.get_funds_data().await,
+            Err(Error::UnsuccessfulRequest { status, .. }) if status == StatusCode::INTERNAL_SERVER_ERROR
+        ));
+        assert_eq!(paths.try_iter().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let (url, paths) = mock_server(vec![(404, String::new())]);
+        let client = test_client(url);
+
+        assert!(matches!(
+            client.get_stages().await,
+            Err(Error::UnsuccessfulRequest { status, .. }) if status == StatusCode::NOT_FOUND
+        ));
+        assert_eq!(paths.try_iter().count(), 1);
+    }
 }
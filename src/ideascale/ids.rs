@@ -0,0 +1,83 @@
+use crate::ideascale::IdeaScaleData;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+// Initial ids, kept as they were when ids were assigned sequentially
+const FIRST_CHALLENGE_ID: u32 = 1;
+const FIRST_PROPOSAL_ID: u32 = 0;
+
+/// Mapping from ideascale ids to the ids assigned on import (challenge `id` and proposal
+/// `internal_id`).
+///
+/// New ids are assigned in ascending ideascale id order, after any id already present in the
+/// mapping, so loading a previously stored mapping keeps ids stable across re-imports.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdsMapping {
+    #[serde(default)]
+    pub challenges: BTreeMap<u32, u32>,
+    #[serde(default)]
+    pub proposals: BTreeMap<u32, u32>,
+}
+
+impl IdsMapping {
+    /// Assign ids to every challenge and proposal in `data` not already present in the mapping
+    pub fn update(&mut self, data: &IdeaScaleData) {
+        assign_ids(
+            &mut self.challenges,
+            data.challenges.keys().copied(),
+            FIRST_CHALLENGE_ID,
+        );
+        assign_ids(
+            &mut self.proposals,
+            data.proposals.keys().copied(),
+            FIRST_PROPOSAL_ID,
+        );
+    }
+
+    pub fn challenge_id(&self, ideascale_id: u32) -> Option<u32> {
+        self.challenges.get(&ideascale_id).copied()
+    }
+
+    pub fn proposal_id(&self, ideascale_id: u32) -> Option<u32> {
+        self.proposals.get(&ideascale_id).copied()
+    }
+}
+
+fn assign_ids(mapping: &mut BTreeMap<u32, u32>, ids: impl Iterator<Item = u32>, first: u32) {
+    let mut new_ids: Vec<u32> = ids.filter(|id| !mapping.contains_key(id)).collect();
+    new_ids.sort_unstable();
+    let mut next = mapping.values().max().map_or(first, |max| max + 1);
+    for id in new_ids {
+        mapping.insert(id, next);
+        next += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_sorted_by_ideascale_id() {
+        let mut mapping = BTreeMap::new();
+        assign_ids(&mut mapping, vec![30, 10, 20].into_iter(), 1);
+        assert_eq!(
+            mapping,
+            vec![(10, 1), (20, 2), (30, 3)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn existing_ids_are_kept() {
+        let mut mapping: BTreeMap<u32, u32> = vec![(20, 1), (30, 2)].into_iter().collect();
+        assign_ids(&mut mapping, vec![30, 5, 20, 40].into_iter(), 1);
+        assert_eq!(
+            mapping,
+            vec![(5, 3), (20, 1), (30, 2), (40, 4)]
+                .into_iter()
+                .collect()
+        );
+    }
+}
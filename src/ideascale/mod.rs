@@ -1,14 +1,28 @@
+mod budget;
+pub mod chain;
+pub mod db;
 mod fetch;
-mod models;
+mod ids;
+pub mod models;
+pub mod snapshot;
+mod validation;
+pub mod voteplans;
 
-use crate::ideascale::models::de::{clean_str, Challenge, Fund, Funnel, Proposal, Stage};
+use crate::ideascale::models::de::{
+    clean_str, parse_requested_funds, Challenge, Fund, Funnel, Proposal, Stage,
+};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use futures::{StreamExt, TryStreamExt};
 use regex::Regex;
 
-pub use crate::ideascale::fetch::Scores;
+pub use crate::ideascale::budget::{budget_summary, ChallengeBudget};
+pub use crate::ideascale::fetch::{IdeascaleClient, Scores, BASE_IDEASCALE_URL};
+use crate::ideascale::fetch::{FUNDS_PATH, FUNNELS_PATH, STAGES_PATH};
+pub use crate::ideascale::ids::IdsMapping;
 pub use crate::ideascale::models::custom_fields::CustomFieldTags;
+pub use crate::ideascale::validation::{validate, IssueKind, ValidationIssue, ValidationReport};
 
 // Id of funnel that do have rewards and should not count when importing funnels. It is static and
 // should not change
@@ -19,14 +33,35 @@ pub enum Error {
     #[error(transparent)]
     Fetch(#[from] fetch::Error),
 
-    #[error(transparent)]
-    Join(#[from] tokio::task::JoinError),
-
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
 
     #[error(transparent)]
     Regex(#[from] regex::Error),
+
+    #[error(transparent)]
+    Snapshot(#[from] snapshot::Error),
+
+    #[error(transparent)]
+    Chain(#[from] chain::Error),
+
+    #[error(transparent)]
+    Db(#[from] db::Error),
+
+    #[error("Missing proposals for challenge with id {0}")]
+    MissingChallengeProposals(u32),
+
+    #[error("Selected fund {0}, wasn't among the available funds")]
+    FundNotFound(usize),
+
+    #[error("A funnel with id {funnel_id} wasn't found for challenge with id {challenge_id}")]
+    FunnelNotFound { funnel_id: u32, challenge_id: u32 },
+
+    #[error("No id was assigned for {kind} with ideascale id {id}")]
+    MissingAssignedId { kind: &'static str, id: u32 },
+
+    #[error("Expected a challenge with id {challenge_id} for proposal with id {proposal_id}")]
+    ChallengeNotFound { challenge_id: u32, proposal_id: u32 },
 }
 
 #[derive(Debug)]
@@ -37,61 +72,133 @@ pub struct IdeaScaleData {
     pub proposals: HashMap<u32, Proposal>,
 }
 
+impl IdeaScaleData {
+    /// Remove the challenges whose funnel was not found, along with their proposals, so the
+    /// rest of the fund can still be imported. Returns the ids of the removed challenges.
+    pub fn remove_challenges_without_funnel(&mut self) -> Vec<u32> {
+        let funnels = &self.funnels;
+        let mut removed: Vec<u32> = self
+            .challenges
+            .values()
+            .filter(|c| !funnels.contains_key(&c.funnel_id))
+            .map(|c| c.id)
+            .collect();
+        removed.sort_unstable();
+        for id in &removed {
+            self.challenges.remove(id);
+        }
+        self.proposals
+            .retain(|_, p| !removed.contains(&p.challenge_id));
+        removed
+    }
+}
+
+/// Raw, unprocessed, responses of the ideascale API needed to build an import
+#[derive(Debug, Clone)]
+pub struct RawIdeascaleData {
+    pub funds: serde_json::Value,
+    pub funnels: serde_json::Value,
+    pub stages: serde_json::Value,
+    /// Proposals of every rewarded challenge, indexed by challenge id
+    pub proposals: BTreeMap<u32, serde_json::Value>,
+}
+
 pub async fn fetch_all(
     fund: usize,
     stage_label: &str,
     stages_filters: &[&str],
     excluded_proposals: &HashSet<u32>,
-    api_token: String,
+    client: &IdeascaleClient,
 ) -> Result<IdeaScaleData, Error> {
-    let funnels_task = tokio::spawn(fetch::get_funnels_data_for_fund(api_token.clone()));
-    let funds_task = tokio::spawn(fetch::get_funds_data(api_token.clone()));
-    let funnels = funnels_task
-        .await??
-        .into_iter()
-        .map(|f| (f.id, f))
-        .collect();
+    let raw = fetch_raw(client).await?;
+    process_raw(&raw, fund, stage_label, stages_filters, excluded_proposals)
+}
 
-    let funds = funds_task.await??;
+/// Fetch the raw responses of the ideascale API, without any filtering, so they can be stored
+/// and processed later on
+pub async fn fetch_raw(client: &IdeascaleClient) -> Result<RawIdeascaleData, Error> {
+    let (funnels, funds, stages) = futures::try_join!(
+        client.request_raw(FUNNELS_PATH),
+        client.request_raw(FUNDS_PATH),
+        client.request_raw(STAGES_PATH)
+    )?;
 
-    let challenges: Vec<Challenge> = funds
-        .iter()
-        .filter(|f| f.id != PROCESS_IMPROVEMENTS_ID)
-        .flat_map(|f| f.challenges.iter().cloned())
-        .filter(|c| c.rewards > 0.into())
-        .collect();
+    let parsed_funds: Vec<Fund> = serde_json::from_value(funds.clone())?;
+    let proposals: BTreeMap<u32, serde_json::Value> =
+        futures::stream::iter(rewarded_challenges(&parsed_funds).map(|c| async move {
+            client
+                .get_raw_proposals_data(c.id)
+                .await
+                .map(|proposals| (c.id, serde_json::Value::Array(proposals)))
+        }))
+        .buffer_unordered(client.concurrency())
+        .try_collect()
+        .await?;
+
+    Ok(RawIdeascaleData {
+        funds,
+        funnels,
+        stages,
+        proposals,
+    })
+}
 
-    let proposals_tasks: Vec<_> = challenges
+/// Build the import data for `fund` out of raw ideascale responses
+pub fn process_raw(
+    raw: &RawIdeascaleData,
+    fund: usize,
+    stage_label: &str,
+    stages_filters: &[&str],
+    excluded_proposals: &HashSet<u32>,
+) -> Result<IdeaScaleData, Error> {
+    let funnels: Vec<Funnel> = serde_json::from_value(raw.funnels.clone())?;
+    let funnels: HashMap<u32, Funnel> = funnels.into_iter().map(|f| (f.id, f)).collect();
+    let funds: Vec<Fund> = serde_json::from_value(raw.funds.clone())?;
+
+    let fund_data = funds
         .iter()
-        .map(|c| tokio::spawn(fetch::get_proposals_data(c.id, api_token.clone())))
-        .collect();
+        .find(|f| f.name.as_ref().contains(&format!("Fund{}", fund)))
+        .cloned()
+        .ok_or(Error::FundNotFound(fund))?;
+
+    let challenges: Vec<Challenge> = rewarded_challenges(&funds).cloned().collect();
 
     let matches = regex::Regex::new(&stages_filters.join("|"))?;
-    let proposals = futures::future::try_join_all(proposals_tasks)
-        .await?
-        .into_iter()
-        // forcefully unwrap to pop errors directly
-        // TODO: Handle error better here
-        .map(Result::unwrap)
-        .flatten()
-        // filter out non approved or staged proposals
-        .filter(|p| p.approved && filter_proposal_by_stage_type(&p.stage_type, &matches))
-        .filter(|p| !excluded_proposals.contains(&p.proposal_id));
-
-    let mut stages: Vec<_> = fetch::get_stages(api_token.clone()).await?;
+    let mut proposals = Vec::new();
+    for challenge in &challenges {
+        let raw_proposals = raw
+            .proposals
+            .get(&challenge.id)
+            .ok_or(Error::MissingChallengeProposals(challenge.id))?;
+        let challenge_proposals: Vec<Proposal> = serde_json::from_value(raw_proposals.clone())?;
+        proposals.extend(
+            challenge_proposals
+                .into_iter()
+                // filter out non approved or staged proposals
+                .filter(|p| p.approved && filter_proposal_by_stage_type(&p.stage_type, &matches))
+                .filter(|p| !excluded_proposals.contains(&p.proposal_id)),
+        );
+    }
+
+    let mut stages: Vec<Stage> = serde_json::from_value(raw.stages.clone())?;
     stages.retain(|stage| filter_stages(stage, stage_label, &funnels));
 
     Ok(IdeaScaleData {
         funnels,
-        fund: funds
-            .into_iter()
-            .find(|f| f.name.as_ref().contains(&format!("Fund{}", fund)))
-            .unwrap_or_else(|| panic!("Selected fund {}, wasn't among the available funds", fund)),
+        fund: fund_data,
         challenges: challenges.into_iter().map(|c| (c.id, c)).collect(),
-        proposals: proposals.map(|p| (p.proposal_id, p)).collect(),
+        proposals: proposals.into_iter().map(|p| (p.proposal_id, p)).collect(),
     })
 }
 
+fn rewarded_challenges(funds: &[Fund]) -> impl Iterator<Item = &Challenge> {
+    funds
+        .iter()
+        .filter(|f| f.id != PROCESS_IMPROVEMENTS_ID)
+        .flat_map(|f| f.challenges.iter())
+        .filter(|c| c.rewards > 0.into())
+}
+
 pub fn build_fund(fund: i32, goal: String, threshold: i64) -> Vec<models::se::Fund> {
     vec![models::se::Fund {
         id: fund,
@@ -103,17 +210,25 @@ pub fn build_fund(fund: i32, goal: String, threshold: i64) -> Vec<models::se::Fu
 pub fn build_challenges(
     fund: i32,
     ideascale_data: &IdeaScaleData,
-) -> HashMap<u32, models::se::Challenge> {
+    ids: &IdsMapping,
+) -> Result<HashMap<u32, models::se::Challenge>, Error> {
     let funnels = &ideascale_data.funnels;
-    (1..)
-        .zip(ideascale_data.challenges.values())
-        .map(|(i, c)| {
-            (
+    ideascale_data
+        .challenges
+        .values()
+        .map(|c| {
+            let id = ids.challenge_id(c.id).ok_or(Error::MissingAssignedId {
+                kind: "challenge",
+                id: c.id,
+            })?;
+            let funnel = funnels.get(&c.funnel_id).ok_or(Error::FunnelNotFound {
+                funnel_id: c.funnel_id,
+                challenge_id: c.id,
+            })?;
+            Ok((
                 c.id,
                 models::se::Challenge {
-                    challenge_type: funnels
-                        .get(&c.funnel_id)
-                        .unwrap_or_else(|| panic!("A funnel with id {} wasn't found", c.funnel_id))
+                    challenge_type: funnel
                         .is_community()
                         .then(|| "community-choice")
                         .unwrap_or("simple")
@@ -121,11 +236,11 @@ pub fn build_challenges(
                     challenge_url: c.challenge_url.clone(),
                     description: c.description.to_string(),
                     fund_id: fund.to_string(),
-                    id: i.to_string(),
-                    rewards_total: c.rewards.to_string(),
+                    id: id.to_string(),
+                    rewards_total: c.rewards.clone().into(),
                     title: c.title.clone(),
                 },
-            )
+            ))
         })
         .collect()
 }
@@ -137,31 +252,34 @@ pub fn build_proposals(
     chain_vote_type: &str,
     fund: usize,
     tags: &CustomFieldTags,
-) -> Vec<models::se::Proposal> {
-    ideascale_data
-        .proposals
-        .values()
-        .enumerate()
-        .map(|(i, p)| {
-            let challenge = &built_challenges.get(&p.challenge_id).unwrap_or_else(|| {
-                panic!(
-                    "Expected a challenge with id {} for proposal with id {}",
-                    p.challenge_id, p.proposal_id
-                )
-            });
-            models::se::Proposal {
+    ids: &IdsMapping,
+) -> Result<Vec<models::se::Proposal>, Error> {
+    let mut proposals: Vec<_> = ideascale_data.proposals.values().collect();
+    proposals.sort_by_key(|p| p.proposal_id);
+    proposals
+        .into_iter()
+        .map(|p| {
+            let internal_id = ids
+                .proposal_id(p.proposal_id)
+                .ok_or(Error::MissingAssignedId {
+                    kind: "proposal",
+                    id: p.proposal_id,
+                })?;
+            let challenge =
+                built_challenges
+                    .get(&p.challenge_id)
+                    .ok_or(Error::ChallengeNotFound {
+                        challenge_id: p.challenge_id,
+                        proposal_id: p.proposal_id,
+                    })?;
+            Ok(models::se::Proposal {
                 category_name: format!("Fund{}", fund),
                 chain_vote_options: "blank,yes,no".to_string(),
                 challenge_id: challenge.id.clone(),
                 challenge_type: challenge.challenge_type.clone(),
                 chain_vote_type: chain_vote_type.to_string(),
-                internal_id: i.to_string(),
-                // this may change to an integer type in the future, would have to get from json value as so
-                proposal_funds: get_from_extra_fields(
-                    &p.custom_fields.fields,
-                    &tags.proposal_funds,
-                )
-                .unwrap_or_default(),
+                internal_id: internal_id.to_string(),
+                proposal_funds: requested_funds(p, tags),
                 proposal_id: p.proposal_id.to_string(),
                 proposal_impact_score: scores
                     .get(&p.proposal_id)
@@ -201,11 +319,19 @@ pub fn build_proposals(
                     &p.custom_fields.fields,
                     &tags.proposal_metrics,
                 ),
-            }
+            })
         })
         .collect()
 }
 
+/// Missing or unparsable requested funds are published as 0. They are listed in the validation
+/// report, which makes `--strict` imports fail before the proposals are built
+fn requested_funds(proposal: &Proposal, tags: &CustomFieldTags) -> u64 {
+    get_from_extra_fields(&proposal.custom_fields.fields, &tags.proposal_funds)
+        .and_then(|funds| parse_requested_funds(&funds))
+        .unwrap_or_default()
+}
+
 fn filter_proposal_by_stage_type(stage: &str, re: &Regex) -> bool {
     re.is_match(stage)
 }
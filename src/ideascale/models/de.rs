@@ -133,6 +133,34 @@ impl Display for AdaRewards {
     }
 }
 
+/// Parse the requested funds custom field of a proposal. Input is not standarized, so currency
+/// markers ("$", "ADA", "₳") and thousands separators are ignored, "$10,000", "10.000 ADA" or
+/// "10000" all parse to 10000. Decimals, if any, are truncated.
+pub fn parse_requested_funds(funds: &str) -> Option<u64> {
+    let mut funds = funds.trim().to_ascii_lowercase();
+    for marker in &["$", "₳", "ada"] {
+        funds = funds.replace(marker, "");
+    }
+    funds.retain(|c| !c.is_whitespace() && !matches!(c, ',' | '_' | '\''));
+
+    // a single `.` followed by less than 3 digits is a decimal separator, otherwise it is
+    // a thousands separator
+    let integer_part = match funds.rfind('.') {
+        Some(i) if funds.matches('.').count() == 1 && funds.len() - i - 1 < 3 => {
+            let (integer, decimals) = funds.split_at(i);
+            if !decimals[1..].chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            integer.to_string()
+        }
+        _ => funds.replace('.', ""),
+    };
+    if integer_part.is_empty() || !integer_part.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    integer_part.parse().ok()
+}
+
 fn deserialize_approved<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
     let approved = String::deserialize(deserializer)?;
     Ok(matches!(approved.as_str(), "approved"))
@@ -183,3 +211,31 @@ fn deserialize_rewards<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64
             D::Error::custom(&format!("Unable to read malformed value: {}", rewards_str))
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_requested_funds;
+
+    #[test]
+    fn requested_funds_variants() {
+        for (input, expected) in &[
+            ("10000", Some(10000)),
+            ("$10,000", Some(10000)),
+            (" 10.000 ADA", Some(10000)),
+            ("10 000 ada", Some(10000)),
+            ("₳25,500.50", Some(25500)),
+            ("1,234,567", Some(1234567)),
+            ("", None),
+            ("ADA", None),
+            ("a lot of ada", None),
+            ("-100", None),
+        ] {
+            assert_eq!(
+                parse_requested_funds(input),
+                *expected,
+                "input: {:?}",
+                input
+            );
+        }
+    }
+}
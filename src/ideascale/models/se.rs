@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
 pub struct Challenge {
@@ -7,7 +7,7 @@ pub struct Challenge {
     pub description: String,
     pub fund_id: String,
     pub id: String,
-    pub rewards_total: String,
+    pub rewards_total: u64,
     pub title: String,
 }
 
@@ -18,7 +18,7 @@ pub struct Fund {
     pub threshold: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Proposal {
     pub category_name: String,
     #[serde(default = "default_vote_options")]
@@ -27,7 +27,7 @@ pub struct Proposal {
     pub challenge_type: String,
     pub chain_vote_type: String,
     pub internal_id: String,
-    pub proposal_funds: String,
+    pub proposal_funds: u64,
     pub proposal_id: String,
     pub proposal_impact_score: String,
     pub proposal_summary: String,
@@ -50,7 +50,6 @@ pub struct Proposal {
     pub proposal_metrics: Option<String>,
 }
 
-#[allow(dead_code)]
-fn default_vote_options() -> &'static str {
-    "blank,yes,no"
+fn default_vote_options() -> String {
+    "blank,yes,no".to_string()
 }
@@ -0,0 +1,222 @@
+//! Offline snapshots of the raw ideascale API responses.
+//!
+//! A snapshot is a directory with the following layout:
+//!
+//! ```text
+//! funds.json
+//! funnels.json
+//! stages.json
+//! proposals/<challenge_id>.json
+//! checksums.json
+//! ```
+//!
+//! where `checksums.json` maps every other file path (relative to the snapshot directory) to
+//! its blake2b256 hash, so a snapshot can be checked for modifications before being imported.
+
+use crate::ideascale::RawIdeascaleData;
+
+use chain_crypto::hash::Blake2b256;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const FUNDS_FILE: &str = "funds.json";
+const FUNNELS_FILE: &str = "funnels.json";
+const STAGES_FILE: &str = "stages.json";
+const PROPOSALS_DIR: &str = "proposals";
+const CHECKSUMS_FILE: &str = "checksums.json";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Could not access snapshot file {path}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Malformed json in snapshot file {path}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Snapshot file {0} is not listed in the checksums file")]
+    MissingChecksum(String),
+
+    #[error("Checksum mismatch for snapshot file {file}, expected {expected} but found {found}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        found: String,
+    },
+
+    #[error("Invalid proposals file name {0}, expected <challenge_id>.json")]
+    InvalidProposalsFile(String),
+}
+
+/// Relative path of file name → blake2b256 hash of its content
+pub type Checksums = BTreeMap<String, String>;
+
+/// Write `data` into the snapshot directory `dir`, creating it if needed
+pub fn save(data: &RawIdeascaleData, dir: &Path) -> Result<Checksums, Error> {
+    create_dir(&dir.join(PROPOSALS_DIR))?;
+
+    let mut checksums = Checksums::new();
+    let mut files = vec![
+        (FUNDS_FILE.to_string(), &data.funds),
+        (FUNNELS_FILE.to_string(), &data.funnels),
+        (STAGES_FILE.to_string(), &data.stages),
+    ];
+    files.extend(
+        data.proposals
+            .iter()
+            .map(|(challenge_id, proposals)| (proposals_file(*challenge_id), proposals)),
+    );
+
+    for (file, content) in files {
+        let path = dir.join(&file);
+        let bytes = serde_json::to_vec_pretty(content).map_err(|source| Error::Json {
+            path: path.clone(),
+            source,
+        })?;
+        write_file(&path, &bytes)?;
+        checksums.insert(file, checksum(&bytes));
+    }
+
+    let path = dir.join(CHECKSUMS_FILE);
+    let bytes = serde_json::to_vec_pretty(&checksums).map_err(|source| Error::Json {
+        path: path.clone(),
+        source,
+    })?;
+    write_file(&path, &bytes)?;
+
+    Ok(checksums)
+}
+
+/// Load a snapshot directory, verifying every file against the stored checksums
+pub fn load(dir: &Path) -> Result<RawIdeascaleData, Error> {
+    let checksums: Checksums = parse_json(
+        &dir.join(CHECKSUMS_FILE),
+        &read_file(&dir.join(CHECKSUMS_FILE))?,
+    )?;
+    let load_file = |file: &str| -> Result<serde_json::Value, Error> {
+        let path = dir.join(file);
+        let bytes = read_file(&path)?;
+        let expected = checksums
+            .get(file)
+            .ok_or_else(|| Error::MissingChecksum(file.to_string()))?;
+        let found = checksum(&bytes);
+        if &found != expected {
+            return Err(Error::ChecksumMismatch {
+                file: file.to_string(),
+                expected: expected.clone(),
+                found,
+            });
+        }
+        parse_json(&path, &bytes)
+    };
+
+    let mut proposals = BTreeMap::new();
+    for file in checksums
+        .keys()
+        .filter(|file| file.starts_with(&format!("{}/", PROPOSALS_DIR)))
+    {
+        let challenge_id = file
+            .strip_prefix(&format!("{}/", PROPOSALS_DIR))
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| Error::InvalidProposalsFile(file.clone()))?;
+        proposals.insert(challenge_id, load_file(file)?);
+    }
+
+    Ok(RawIdeascaleData {
+        funds: load_file(FUNDS_FILE)?,
+        funnels: load_file(FUNNELS_FILE)?,
+        stages: load_file(STAGES_FILE)?,
+        proposals,
+    })
+}
+
+fn proposals_file(challenge_id: u32) -> String {
+    format!("{}/{}.json", PROPOSALS_DIR, challenge_id)
+}
+
+fn checksum(bytes: &[u8]) -> String {
+    Blake2b256::hash(bytes).to_string()
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(path: &Path, bytes: &[u8]) -> Result<T, Error> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Json {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn create_dir(path: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    std::fs::write(path, bytes).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use serde_json::json;
+
+    fn raw_data() -> RawIdeascaleData {
+        RawIdeascaleData {
+            funds: json!([{ "id": 1 }]),
+            funnels: json!([{ "id": 2 }]),
+            stages: json!([{ "id": 3 }]),
+            proposals: vec![(10, json!([{ "id": 4 }])), (11, json!([]))]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let data = raw_data();
+        let checksums = save(&data, dir).unwrap();
+        assert_eq!(checksums.len(), 5);
+
+        let loaded = load(dir).unwrap();
+        assert_eq!(loaded.funds, data.funds);
+        assert_eq!(loaded.funnels, data.funnels);
+        assert_eq!(loaded.stages, data.stages);
+        assert_eq!(loaded.proposals, data.proposals);
+    }
+
+    #[test]
+    fn modified_file_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        save(&raw_data(), dir).unwrap();
+        std::fs::write(dir.join(proposals_file(10)), b"[]").unwrap();
+
+        assert!(matches!(
+            load(dir),
+            Err(Error::ChecksumMismatch { file, .. }) if file == proposals_file(10)
+        ));
+    }
+}
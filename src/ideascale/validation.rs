@@ -0,0 +1,193 @@
+use crate::ideascale::models::custom_fields::CustomFieldTags;
+use crate::ideascale::models::de::parse_requested_funds;
+use crate::ideascale::{get_from_extra_fields, IdeaScaleData, Scores};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// requested funds are published as 0 when it is the requested funds field
+    MissingCustomField,
+    /// requested funds are published as 0
+    NonNumericRequestedFunds,
+    EmptySummary,
+    MissingImpactScore,
+    /// the challenge and its proposals are left out of the import
+    MissingFunnel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub challenge_id: u32,
+    /// None for issues of the challenge itself
+    pub proposal_id: Option<u32>,
+    pub details: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues.iter().filter(|i| i.kind == kind).count()
+    }
+}
+
+/// Check the imported data for values that would otherwise be silently replaced by defaults
+/// when building the import output
+pub fn validate(
+    ideascale_data: &IdeaScaleData,
+    scores: &Scores,
+    tags: &CustomFieldTags,
+) -> ValidationReport {
+    let mut issues = Vec::new();
+
+    let mut challenges: Vec<_> = ideascale_data.challenges.values().collect();
+    challenges.sort_by_key(|c| c.id);
+    for challenge in challenges {
+        if !ideascale_data.funnels.contains_key(&challenge.funnel_id) {
+            issues.push(ValidationIssue {
+                kind: IssueKind::MissingFunnel,
+                challenge_id: challenge.id,
+                proposal_id: None,
+                details: format!(
+                    "funnel {} not found, the challenge is skipped",
+                    challenge.funnel_id
+                ),
+            });
+        }
+    }
+
+    let mut proposals: Vec<_> = ideascale_data.proposals.values().collect();
+    proposals.sort_by_key(|p| p.proposal_id);
+    for proposal in proposals {
+        let mut push = |kind, details: String| {
+            issues.push(ValidationIssue {
+                kind,
+                challenge_id: proposal.challenge_id,
+                proposal_id: Some(proposal.proposal_id),
+                details,
+            })
+        };
+        let fields = &proposal.custom_fields.fields;
+
+        for tag in [&tags.proposal_funds, &tags.proposal_relevant_experience] {
+            if get_from_extra_fields(fields, tag).map_or(true, |v| v.trim().is_empty()) {
+                push(IssueKind::MissingCustomField, tag.clone());
+            }
+        }
+
+        if let Some(funds) = get_from_extra_fields(fields, &tags.proposal_funds) {
+            if !funds.trim().is_empty() && parse_requested_funds(&funds).is_none() {
+                push(IssueKind::NonNumericRequestedFunds, funds);
+            }
+        }
+
+        if proposal.proposal_summary.as_ref().trim().is_empty() {
+            push(IssueKind::EmptySummary, String::new());
+        }
+
+        if !scores.contains_key(&proposal.proposal_id) {
+            push(IssueKind::MissingImpactScore, String::new());
+        }
+    }
+
+    ValidationReport { issues }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ideascale::models::de::{Fund, Funnel, Proposal};
+    use crate::ideascale::{build_challenges, IdsMapping};
+    use serde_json::json;
+
+    fn proposal(id: u32, summary: &str, funds: Option<&str>) -> Proposal {
+        let mut fields = json!({ "relevant_experience": "a lot" });
+        if let Some(funds) = funds {
+            fields["requested_funds"] = json!(funds);
+        }
+        serde_json::from_value(json!({
+            "id": id,
+            "title": "title",
+            "text": summary,
+            "url": "url",
+            "customFieldsByKey": fields,
+            "authorInfo": { "name": "name", "email": "email" },
+            "stageId": 1,
+            "stageLabel": "Governance phase",
+            "campaignId": 1,
+            "flag": "approved",
+        }))
+        .unwrap()
+    }
+
+    fn data(proposals: Vec<Proposal>) -> IdeaScaleData {
+        let fund: Fund = serde_json::from_value(json!({
+            "id": 1,
+            "name": "Fund7",
+            "campaigns": [{
+                "id": 1,
+                "name": "F7: challenge",
+                "tagline": "$100,000 in ada",
+                "description": "description",
+                "groupId": 1,
+                "funnelId": 2,
+                "campaignUrl": "url",
+            }],
+        }))
+        .unwrap();
+        let funnel: Funnel = serde_json::from_value(json!({
+            "id": 3,
+            "name": "funnel",
+            "description": "description",
+        }))
+        .unwrap();
+        IdeaScaleData {
+            funnels: vec![(funnel.id, funnel)].into_iter().collect(),
+            challenges: fund.challenges.iter().map(|c| (c.id, c.clone())).collect(),
+            fund,
+            proposals: proposals.into_iter().map(|p| (p.proposal_id, p)).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_every_issue_kind() {
+        let data = data(vec![
+            proposal(1, "summary", Some("1000")),
+            proposal(2, "", Some("a lot of ada")),
+            proposal(3, "summary", None),
+        ]);
+        let scores: Scores = vec![(1, 4.5), (2, 3.0)].into_iter().collect();
+        let report = validate(&data, &scores, &CustomFieldTags::default());
+
+        assert_eq!(report.count(IssueKind::MissingFunnel), 1);
+        assert_eq!(report.count(IssueKind::EmptySummary), 1);
+        assert_eq!(report.count(IssueKind::NonNumericRequestedFunds), 1);
+        assert_eq!(report.count(IssueKind::MissingCustomField), 1);
+        assert_eq!(report.count(IssueKind::MissingImpactScore), 1);
+        assert!(!report.issues.iter().any(|i| i.proposal_id == Some(1)));
+    }
+
+    #[test]
+    fn challenges_without_funnel_are_skipped() {
+        let mut data = data(vec![proposal(1, "summary", Some("1000"))]);
+        assert_eq!(data.remove_challenges_without_funnel(), vec![1]);
+        assert!(data.challenges.is_empty());
+        assert!(data.proposals.is_empty());
+
+        let report = validate(&data, &Scores::new(), &CustomFieldTags::default());
+        assert!(report.is_empty());
+        assert!(build_challenges(7, &data, &IdsMapping::default())
+            .unwrap()
+            .is_empty());
+    }
+}
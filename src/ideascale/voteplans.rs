@@ -0,0 +1,408 @@
+//! Build block0 voteplans out of imported proposals
+
+use crate::ideascale::models::se::Proposal;
+
+use chain_impl_mockchain::block::BlockDate;
+use chain_impl_mockchain::certificate::{
+    ExternalProposalId, Proposal as VotePlanProposal, Proposals, PushProposal, VoteAction,
+    VotePlan, VotePlanId,
+};
+use chain_impl_mockchain::vote::{Options, PayloadType};
+use chain_vote::MemberPublicKey;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Max number of proposals a voteplan certificate can hold
+pub const MAX_PROPOSALS_PER_VOTEPLAN: usize = 255;
+
+const MEMBER_PUBLIC_KEY_HRP: &str = "p256k1_memberpk";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("max proposals per voteplan should be in range [1, 255], got {0}")]
+    InvalidMaxProposals(usize),
+
+    #[error("private voteplans need at least one committee member public key")]
+    MissingCommitteeKeys,
+
+    #[error("invalid committee member public key {0}")]
+    InvalidCommitteeKey(String),
+
+    #[error("invalid proposal {proposal_id}: {reason}")]
+    InvalidProposal { proposal_id: String, reason: String },
+
+    #[error("unknown split strategy '{0}', expected one of: per-challenge, sequential")]
+    UnknownSplitStrategy(String),
+}
+
+/// How proposals are distributed among voteplans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// every voteplan only holds proposals of a single challenge
+    PerChallenge,
+    /// voteplans are filled up, challenge after challenge
+    Sequential,
+}
+
+#[derive(Debug, Clone)]
+pub struct VotePlanSettings {
+    pub vote_start: BlockDate,
+    pub vote_end: BlockDate,
+    pub committee_end: BlockDate,
+    pub payload_type: PayloadType,
+    /// bech32 encoded committee member public keys, only used for private voteplans
+    pub committee_member_public_keys: Vec<String>,
+    pub max_proposals_per_voteplan: usize,
+    pub split: SplitStrategy,
+}
+
+/// Voteplan definition, in the format accepted by `jcli certificate new vote-plan`
+#[derive(Debug, Clone, Serialize)]
+pub struct VotePlanDefinition {
+    pub payload_type: &'static str,
+    pub vote_start: BlockDateDefinition,
+    pub vote_end: BlockDateDefinition,
+    pub committee_end: BlockDateDefinition,
+    pub proposals: Vec<ProposalDefinition>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub committee_member_public_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BlockDateDefinition {
+    pub epoch: u32,
+    pub slot_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalDefinition {
+    pub external_id: String,
+    pub options: u8,
+    pub action: &'static str,
+}
+
+/// External id given to an imported proposal in the generated voteplans.
+///
+/// Voteplan certificates only hold a 32 bytes external id per proposal, which the servicing
+/// station stores as `chain_proposal_id`. Ideascale ids do not fit in it as they are, so they are
+/// hashed with blake2b256, which also keeps ids unique across challenges. Nothing else has to
+/// recompute it: the ids are written to the proposals to voteplans mapping, and the database
+/// import reads them back from block0.
+pub fn proposal_external_id(proposal_id: &str) -> ExternalProposalId {
+    ExternalProposalId::digest(&proposal_id.as_bytes().to_vec())
+}
+
+/// Location of an imported proposal in the generated voteplans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposalVotePlanEntry {
+    pub proposal_id: String,
+    pub internal_id: String,
+    pub challenge_id: String,
+    pub chain_proposal_id: String,
+    pub chain_voteplan_id: String,
+    pub chain_proposal_index: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedVotePlans {
+    pub voteplans: Vec<(VotePlanId, VotePlanDefinition)>,
+    pub mapping: Vec<ProposalVotePlanEntry>,
+}
+
+impl FromStr for SplitStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "per-challenge" => Ok(Self::PerChallenge),
+            "sequential" => Ok(Self::Sequential),
+            other => Err(Error::UnknownSplitStrategy(other.to_string())),
+        }
+    }
+}
+
+impl From<BlockDate> for BlockDateDefinition {
+    fn from(date: BlockDate) -> Self {
+        Self {
+            epoch: date.epoch,
+            slot_id: date.slot_id,
+        }
+    }
+}
+
+/// Distribute `proposals` among voteplans following `settings`. Proposals are ordered by
+/// challenge id and then by internal id, and their external ids are computed with
+/// [`proposal_external_id`]. The returned mapping is what links the proposals to block0 when
+/// importing them into the servicing station.
+pub fn generate_voteplans(
+    proposals: &[Proposal],
+    settings: &VotePlanSettings,
+) -> Result<GeneratedVotePlans, Error> {
+    if settings.max_proposals_per_voteplan == 0
+        || settings.max_proposals_per_voteplan > MAX_PROPOSALS_PER_VOTEPLAN
+    {
+        return Err(Error::InvalidMaxProposals(
+            settings.max_proposals_per_voteplan,
+        ));
+    }
+
+    let committee_keys = match settings.payload_type {
+        PayloadType::Public => Vec::new(),
+        PayloadType::Private => {
+            if settings.committee_member_public_keys.is_empty() {
+                return Err(Error::MissingCommitteeKeys);
+            }
+            settings
+                .committee_member_public_keys
+                .iter()
+                .map(|key| parse_member_public_key(key))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    // proposals are ordered by internal id, which sets their index in the voteplans
+    let mut by_challenge: BTreeMap<u32, Vec<(u32, &Proposal)>> = BTreeMap::new();
+    for proposal in proposals {
+        by_challenge
+            .entry(parse_id(proposal, "challenge_id", &proposal.challenge_id)?)
+            .or_default()
+            .push((
+                parse_id(proposal, "internal_id", &proposal.internal_id)?,
+                proposal,
+            ));
+    }
+    let by_challenge: BTreeMap<u32, Vec<&Proposal>> = by_challenge
+        .into_iter()
+        .map(|(challenge_id, mut proposals)| {
+            proposals.sort_by_key(|(internal_id, _)| *internal_id);
+            (
+                challenge_id,
+                proposals.into_iter().map(|(_, p)| p).collect(),
+            )
+        })
+        .collect();
+
+    let groups: Vec<Vec<&Proposal>> = match settings.split {
+        SplitStrategy::PerChallenge => by_challenge
+            .values()
+            .flat_map(|proposals| proposals.chunks(settings.max_proposals_per_voteplan))
+            .map(<[_]>::to_vec)
+            .collect(),
+        SplitStrategy::Sequential => by_challenge
+            .values()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>()
+            .chunks(settings.max_proposals_per_voteplan)
+            .map(<[_]>::to_vec)
+            .collect(),
+    };
+
+    let mut generated = GeneratedVotePlans {
+        voteplans: Vec::with_capacity(groups.len()),
+        mapping: Vec::with_capacity(proposals.len()),
+    };
+    for group in groups {
+        let mut voteplan_proposals = Proposals::new();
+        let mut definitions = Vec::with_capacity(group.len());
+        for proposal in &group {
+            let options = proposal.chain_vote_options.split(',').count();
+            let external_id = proposal_external_id(&proposal.proposal_id);
+            let vote_options =
+                Options::new_length(options as u8).map_err(|e| Error::InvalidProposal {
+                    proposal_id: proposal.proposal_id.clone(),
+                    reason: format!("{:?}", e),
+                })?;
+            if let PushProposal::Full { .. } = voteplan_proposals.push(VotePlanProposal::new(
+                external_id.clone(),
+                vote_options,
+                VoteAction::OffChain,
+            )) {
+                // groups are never larger than the max amount of proposals
+                unreachable!("voteplan proposals are full");
+            }
+            definitions.push(ProposalDefinition {
+                external_id: external_id.to_string(),
+                options: options as u8,
+                action: "off_chain",
+            });
+        }
+
+        let voteplan = VotePlan::new(
+            settings.vote_start,
+            settings.vote_end,
+            settings.committee_end,
+            voteplan_proposals,
+            settings.payload_type,
+            committee_keys.clone(),
+        );
+        let voteplan_id = voteplan.to_id();
+
+        generated
+            .mapping
+            .extend(group.iter().zip(definitions.iter()).enumerate().map(
+                |(index, (proposal, definition))| ProposalVotePlanEntry {
+                    proposal_id: proposal.proposal_id.clone(),
+                    internal_id: proposal.internal_id.clone(),
+                    challenge_id: proposal.challenge_id.clone(),
+                    chain_proposal_id: definition.external_id.clone(),
+                    chain_voteplan_id: voteplan_id.to_string(),
+                    chain_proposal_index: index as u8,
+                },
+            ));
+        generated.voteplans.push((
+            voteplan_id,
+            VotePlanDefinition {
+                payload_type: match settings.payload_type {
+                    PayloadType::Public => "public",
+                    PayloadType::Private => "private",
+                },
+                vote_start: settings.vote_start.into(),
+                vote_end: settings.vote_end.into(),
+                committee_end: settings.committee_end.into(),
+                proposals: definitions,
+                committee_member_public_keys: match settings.payload_type {
+                    PayloadType::Public => Vec::new(),
+                    PayloadType::Private => settings.committee_member_public_keys.clone(),
+                },
+            },
+        ));
+    }
+
+    Ok(generated)
+}
+
+fn parse_id(proposal: &Proposal, field: &str, value: &str) -> Result<u32, Error> {
+    value.parse().map_err(|_| Error::InvalidProposal {
+        proposal_id: proposal.proposal_id.clone(),
+        reason: format!("non numeric {} '{}'", field, value),
+    })
+}
+
+fn parse_member_public_key(key: &str) -> Result<MemberPublicKey, Error> {
+    use bech32::FromBase32;
+
+    let invalid = || Error::InvalidCommitteeKey(key.to_string());
+    let (hrp, data, _) = bech32::decode(key).map_err(|_| invalid())?;
+    if hrp != MEMBER_PUBLIC_KEY_HRP {
+        return Err(invalid());
+    }
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|_| invalid())?;
+    MemberPublicKey::from_bytes(&bytes).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(proposal_id: u32, internal_id: u32, challenge_id: u32) -> Proposal {
+        serde_json::from_value(serde_json::json!({
+            "category_name": "Fund7",
+            "challenge_id": challenge_id.to_string(),
+            "challenge_type": "simple",
+            "chain_vote_type": "public",
+            "internal_id": internal_id.to_string(),
+            "proposal_funds": 1000,
+            "proposal_id": proposal_id.to_string(),
+            "proposal_impact_score": "400",
+            "proposal_summary": "summary",
+            "proposal_title": "title",
+            "proposal_url": "url",
+            "proposer_email": "email",
+            "proposer_name": "name",
+            "proposer_relevant_experience": "experience",
+        }))
+        .unwrap()
+    }
+
+    fn settings(max_proposals_per_voteplan: usize, split: SplitStrategy) -> VotePlanSettings {
+        VotePlanSettings {
+            vote_start: BlockDate {
+                epoch: 1,
+                slot_id: 0,
+            },
+            vote_end: BlockDate {
+                epoch: 2,
+                slot_id: 0,
+            },
+            committee_end: BlockDate {
+                epoch: 3,
+                slot_id: 0,
+            },
+            payload_type: PayloadType::Public,
+            committee_member_public_keys: Vec::new(),
+            max_proposals_per_voteplan,
+            split,
+        }
+    }
+
+    fn proposals() -> Vec<Proposal> {
+        vec![
+            proposal(100, 2, 2),
+            proposal(101, 0, 1),
+            proposal(102, 1, 1),
+            proposal(103, 3, 2),
+            proposal(104, 4, 2),
+        ]
+    }
+
+    #[test]
+    fn per_challenge_split() {
+        let generated =
+            generate_voteplans(&proposals(), &settings(2, SplitStrategy::PerChallenge)).unwrap();
+        let sizes: Vec<_> = generated
+            .voteplans
+            .iter()
+            .map(|(_, v)| v.proposals.len())
+            .collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+
+        let order: Vec<_> = generated
+            .mapping
+            .iter()
+            .map(|m| (m.proposal_id.as_str(), m.chain_proposal_index))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("101", 0), ("102", 1), ("100", 0), ("103", 1), ("104", 0)]
+        );
+        assert_eq!(
+            generated.mapping[0].chain_proposal_id,
+            proposal_external_id("101").to_string()
+        );
+    }
+
+    #[test]
+    fn sequential_split() {
+        let generated =
+            generate_voteplans(&proposals(), &settings(3, SplitStrategy::Sequential)).unwrap();
+        let sizes: Vec<_> = generated
+            .voteplans
+            .iter()
+            .map(|(_, v)| v.proposals.len())
+            .collect();
+        assert_eq!(sizes, vec![3, 2]);
+    }
+
+    #[test]
+    fn non_numeric_internal_ids_are_rejected() {
+        let mut proposals = proposals();
+        proposals[1].internal_id = "two".to_string();
+        assert!(matches!(
+            generate_voteplans(&proposals, &settings(10, SplitStrategy::Sequential)),
+            Err(Error::InvalidProposal { proposal_id, .. }) if proposal_id == proposals[1].proposal_id
+        ));
+    }
+
+    #[test]
+    fn private_voteplans_need_committee_keys() {
+        let mut settings = settings(10, SplitStrategy::Sequential);
+        settings.payload_type = PayloadType::Private;
+        assert!(matches!(
+            generate_voteplans(&proposals(), &settings),
+            Err(Error::MissingCommitteeKeys)
+        ));
+    }
+}
@@ -1,14 +1,15 @@
 use crate::logs::sentry::{Error, SentryFragmentLog};
-use crate::recovery::tally::{deconstruct_account_transaction, ValidationError};
+use crate::recovery::tally::{deconstruct_account_transaction, ValidatedFragment, ValidationError};
 
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::fragment::Fragment;
 use chain_impl_mockchain::vote::Payload;
 use jormungandr_lib::interfaces::PersistentFragmentLog;
+use serde::Serialize;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct LogCmpFields {
     pub public_key: String,
     pub chain_proposal_index: u8,
@@ -51,9 +52,133 @@ pub struct LogCmpStats {
     pub duplicated_sentry_logs: usize,
     pub duplicated_fragment_logs: usize,
     pub fragment_ids_differ: HashSet<String>,
+    pub fragment_ids_missing_in_sentry: HashSet<String>,
     pub unhandled_fragment_logs: Vec<(Fragment, Error)>,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// Fragment reported by the app (sentry) that the node never received
+    MissingInFragmentLogs,
+    /// Fragment received by the node that the app never reported
+    MissingInSentryLogs,
+    /// Same fragment id but different choice, proposal or voteplan
+    FieldsMismatch,
+}
+
+/// Single fragment discrepancy between sentry and persistent fragment logs.
+/// Fields are kept flat so the entry can be dumped both as csv and json.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogDiscrepancy {
+    pub kind: DiscrepancyKind,
+    pub fragment_id: String,
+    pub public_key: String,
+    pub sentry_voteplan_id: Option<String>,
+    pub sentry_chain_proposal_index: Option<u8>,
+    pub sentry_choice: Option<u8>,
+    pub fragment_voteplan_id: Option<String>,
+    pub fragment_chain_proposal_index: Option<u8>,
+    pub fragment_choice: Option<u8>,
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+struct DiscrepancyCounts {
+    pub missing_in_fragment_logs: usize,
+    pub missing_in_sentry_logs: usize,
+    pub fields_mismatch: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoterDiscrepancies {
+    pub public_key: String,
+    pub missing_in_fragment_logs: usize,
+    pub missing_in_sentry_logs: usize,
+    pub fields_mismatch: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalDiscrepancies {
+    pub voteplan_id: String,
+    pub chain_proposal_index: u8,
+    pub missing_in_fragment_logs: usize,
+    pub missing_in_sentry_logs: usize,
+    pub fields_mismatch: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogDiscrepancyReport {
+    pub discrepancies: Vec<LogDiscrepancy>,
+    pub per_voter: Vec<VoterDiscrepancies>,
+    pub per_proposal: Vec<ProposalDiscrepancies>,
+}
+
+impl DiscrepancyCounts {
+    fn add(&mut self, kind: DiscrepancyKind) {
+        match kind {
+            DiscrepancyKind::MissingInFragmentLogs => self.missing_in_fragment_logs += 1,
+            DiscrepancyKind::MissingInSentryLogs => self.missing_in_sentry_logs += 1,
+            DiscrepancyKind::FieldsMismatch => self.fields_mismatch += 1,
+        }
+    }
+}
+
+impl LogDiscrepancy {
+    fn new(
+        kind: DiscrepancyKind,
+        sentry: Option<&LogCmpFields>,
+        fragment: Option<&LogCmpFields>,
+    ) -> Self {
+        // one of both is always present, persistent logs are the source of truth when available
+        let reference = fragment
+            .or(sentry)
+            .expect("at least one log entry is needed");
+        Self {
+            kind,
+            fragment_id: reference.fragment_id.clone(),
+            public_key: reference.public_key.clone(),
+            sentry_voteplan_id: sentry.map(|log| log.voteplan_id.clone()),
+            sentry_chain_proposal_index: sentry.map(|log| log.chain_proposal_index),
+            sentry_choice: sentry.map(|log| log.choice),
+            fragment_voteplan_id: fragment.map(|log| log.voteplan_id.clone()),
+            fragment_chain_proposal_index: fragment.map(|log| log.chain_proposal_index),
+            fragment_choice: fragment.map(|log| log.choice),
+        }
+    }
+
+    /// Proposals involved in the discrepancy, both sides are reported if they differ
+    fn proposals(&self) -> Vec<(String, u8)> {
+        let sentry = self
+            .sentry_voteplan_id
+            .clone()
+            .zip(self.sentry_chain_proposal_index);
+        let fragment = self
+            .fragment_voteplan_id
+            .clone()
+            .zip(self.fragment_chain_proposal_index);
+        let mut proposals: Vec<(String, u8)> = fragment.into_iter().chain(sentry).collect();
+        proposals.dedup();
+        proposals
+    }
+}
+
+fn fragment_logs_to_log_cmp_fields(
+    fragment_logs: &[PersistentFragmentLog],
+) -> (Vec<LogCmpFields>, Vec<(Fragment, Error)>) {
+    fragment_logs.iter().fold(
+        (Vec::new(), Vec::new()),
+        |(mut success, mut errored), log| {
+            match persistent_fragment_log_to_log_cmp_fields(log) {
+                Ok(log) => {
+                    success.push(log);
+                }
+                Err(e) => errored.push((log.fragment.clone(), e)),
+            };
+            (success, errored)
+        },
+    )
+}
+
 pub fn compare_logs(
     sentry_logs: &[SentryFragmentLog],
     fragment_logs: &[PersistentFragmentLog],
@@ -62,19 +187,7 @@ pub fn compare_logs(
     let fragment_logs_size = fragment_logs.len();
     let sentry_cmp: Vec<LogCmpFields> = sentry_logs.iter().cloned().map(Into::into).collect();
 
-    let (fragments_cmp, unhandled_fragment_logs): (Vec<LogCmpFields>, Vec<(Fragment, Error)>) =
-        fragment_logs.iter().fold(
-            (Vec::new(), Vec::new()),
-            |(mut success, mut errored), log| {
-                match persistent_fragment_log_to_log_cmp_fields(log) {
-                    Ok(log) => {
-                        success.push(log);
-                    }
-                    Err(e) => errored.push((log.fragment.clone(), e)),
-                };
-                (success, errored)
-            },
-        );
+    let (fragments_cmp, unhandled_fragment_logs) = fragment_logs_to_log_cmp_fields(fragment_logs);
 
     let sentry_fragments_ids: HashSet<String> = sentry_cmp
         .iter()
@@ -88,6 +201,10 @@ pub fn compare_logs(
         .difference(&fragment_logs_ids)
         .cloned()
         .collect();
+    let fragment_ids_missing_in_sentry: HashSet<String> = fragment_logs_ids
+        .difference(&sentry_fragments_ids)
+        .cloned()
+        .collect();
     let duplicated_sentry_logs = sentry_logs_size - sentry_fragments_ids.len();
     let duplicated_fragment_logs = fragment_logs_size - fragment_logs_ids.len();
 
@@ -97,6 +214,473 @@ pub fn compare_logs(
         duplicated_sentry_logs,
         duplicated_fragment_logs,
         fragment_ids_differ,
+        fragment_ids_missing_in_sentry,
         unhandled_fragment_logs,
     }
 }
+
+/// Bidirectional diff between sentry and persistent fragment logs, grouped by voter and proposal.
+/// Fragments that could not be decoded from the persistent logs are not part of the report,
+/// use [`compare_logs`] to get those.
+pub fn logs_discrepancies(
+    sentry_logs: &[SentryFragmentLog],
+    fragment_logs: &[PersistentFragmentLog],
+) -> LogDiscrepancyReport {
+    let sentry_cmp: HashMap<String, LogCmpFields> = sentry_logs
+        .iter()
+        .cloned()
+        .map(LogCmpFields::from)
+        .map(|log| (log.fragment_id.clone(), log))
+        .collect();
+    let fragments_cmp: HashMap<String, LogCmpFields> =
+        fragment_logs_to_log_cmp_fields(fragment_logs)
+            .0
+            .into_iter()
+            .map(|log| (log.fragment_id.clone(), log))
+            .collect();
+
+    let mut discrepancies = Vec::new();
+    for (fragment_id, sentry_log) in &sentry_cmp {
+        match fragments_cmp.get(fragment_id) {
+            None => discrepancies.push(LogDiscrepancy::new(
+                DiscrepancyKind::MissingInFragmentLogs,
+                Some(sentry_log),
+                None,
+            )),
+            Some(fragment_log) if fragment_log != sentry_log => {
+                discrepancies.push(LogDiscrepancy::new(
+                    DiscrepancyKind::FieldsMismatch,
+                    Some(sentry_log),
+                    Some(fragment_log),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    for (fragment_id, fragment_log) in &fragments_cmp {
+        if !sentry_cmp.contains_key(fragment_id) {
+            discrepancies.push(LogDiscrepancy::new(
+                DiscrepancyKind::MissingInSentryLogs,
+                None,
+                Some(fragment_log),
+            ));
+        }
+    }
+    discrepancies
+        .sort_by(|a, b| (&a.public_key, &a.fragment_id).cmp(&(&b.public_key, &b.fragment_id)));
+
+    let mut per_voter: BTreeMap<String, DiscrepancyCounts> = BTreeMap::new();
+    let mut per_proposal: BTreeMap<(String, u8), DiscrepancyCounts> = BTreeMap::new();
+    for discrepancy in &discrepancies {
+        per_voter
+            .entry(discrepancy.public_key.clone())
+            .or_default()
+            .add(discrepancy.kind);
+        for proposal in discrepancy.proposals() {
+            per_proposal
+                .entry(proposal)
+                .or_default()
+                .add(discrepancy.kind);
+        }
+    }
+
+    LogDiscrepancyReport {
+        discrepancies,
+        per_voter: per_voter
+            .into_iter()
+            .map(|(public_key, counts)| VoterDiscrepancies {
+                public_key,
+                missing_in_fragment_logs: counts.missing_in_fragment_logs,
+                missing_in_sentry_logs: counts.missing_in_sentry_logs,
+                fields_mismatch: counts.fields_mismatch,
+            })
+            .collect(),
+        per_proposal: per_proposal
+            .into_iter()
+            .map(
+                |((voteplan_id, chain_proposal_index), counts)| ProposalDiscrepancies {
+                    voteplan_id,
+                    chain_proposal_index,
+                    missing_in_fragment_logs: counts.missing_in_fragment_logs,
+                    missing_in_sentry_logs: counts.missing_in_sentry_logs,
+                    fields_mismatch: counts.fields_mismatch,
+                },
+            )
+            .collect(),
+    }
+}
+
+/// Ballot for which the spending counter reported by the app differs from the one
+/// that actually verified the transaction signature
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendingCounterMismatch {
+    pub fragment_id: String,
+    pub public_key: String,
+    pub voteplan_id: String,
+    pub chain_proposal_index: u8,
+    pub sentry_spending_counter: u64,
+    pub verified_spending_counter: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoterSpendingCounters {
+    pub public_key: String,
+    pub matched_ballots: usize,
+    pub out_of_order_counters: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendingCounterReport {
+    /// Sentry logs whose fragment id was not found among the validated fragments
+    pub unmatched_sentry_logs: usize,
+    pub mismatches: Vec<SpendingCounterMismatch>,
+    pub per_voter: Vec<VoterSpendingCounters>,
+}
+
+/// Join sentry logs with the fragments validated by [`VoteFragmentFilter`](crate::recovery::tally::VoteFragmentFilter)
+/// by fragment id and flag every ballot where the app spending counter is not the one that verified.
+pub fn compare_spending_counters(
+    sentry_logs: &[SentryFragmentLog],
+    validated_fragments: &[ValidatedFragment],
+) -> SpendingCounterReport {
+    let verified_counters: HashMap<String, u32> = validated_fragments
+        .iter()
+        .map(|validated| {
+            (
+                validated.fragment.id().to_string(),
+                u32::from(validated.spending_counter),
+            )
+        })
+        .collect();
+
+    let mut unmatched_sentry_logs = 0;
+    let mut mismatches = Vec::new();
+    let mut per_voter: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut seen_fragments = HashSet::new();
+    for log in sentry_logs {
+        if !seen_fragments.insert(&log.fragment_id) {
+            continue;
+        }
+        let verified_spending_counter = match verified_counters.get(&log.fragment_id) {
+            Some(counter) => *counter,
+            None => {
+                unmatched_sentry_logs += 1;
+                continue;
+            }
+        };
+        let (matched_ballots, out_of_order_counters) =
+            per_voter.entry(log.public_key.clone()).or_default();
+        *matched_ballots += 1;
+        if log.spending_counter != verified_spending_counter as u64 {
+            *out_of_order_counters += 1;
+            mismatches.push(SpendingCounterMismatch {
+                fragment_id: log.fragment_id.clone(),
+                public_key: log.public_key.clone(),
+                voteplan_id: log.voteplan_id.clone(),
+                chain_proposal_index: log.chain_proposal_index,
+                sentry_spending_counter: log.spending_counter,
+                verified_spending_counter,
+            });
+        }
+    }
+
+    SpendingCounterReport {
+        unmatched_sentry_logs,
+        mismatches,
+        per_voter: per_voter
+            .into_iter()
+            .map(
+                |(public_key, (matched_ballots, out_of_order_counters))| VoterSpendingCounters {
+                    public_key,
+                    matched_ballots,
+                    out_of_order_counters,
+                },
+            )
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        compare_spending_counters, logs_discrepancies, persistent_fragment_log_to_log_cmp_fields,
+        DiscrepancyKind, LogCmpFields,
+    };
+    use crate::logs::sentry::SentryFragmentLog;
+    use crate::recovery::tally::ValidatedFragment;
+    use chain_addr::Discrimination;
+    use chain_core::property::Block as _;
+    use chain_impl_mockchain::account::SpendingCounter;
+    use chain_impl_mockchain::block::BlockDate;
+    use chain_impl_mockchain::vote::Choice;
+    use jormungandr_lib::interfaces::PersistentFragmentLog;
+    use jormungandr_testing_utils::testing::jormungandr::ConfigurationBuilder;
+    use jormungandr_testing_utils::testing::VotePlanBuilder;
+    use jormungandr_testing_utils::wallet::Wallet as TestWallet;
+    use rand::rngs::OsRng;
+
+    fn sentry_log(
+        public_key: &str,
+        fragment_id: &str,
+        chain_proposal_index: u8,
+    ) -> SentryFragmentLog {
+        SentryFragmentLog {
+            public_key: public_key.to_string(),
+            chain_proposal_index,
+            proposal_index: chain_proposal_index as u32,
+            voteplan_id: "voteplan".to_string(),
+            choice: 1,
+            spending_counter: 0,
+            fragment_id: fragment_id.to_string(),
+        }
+    }
+
+    /// Public vote casts of a single voter, one per proposal
+    fn vote_cast_logs(proposals: u8) -> Vec<PersistentFragmentLog> {
+        let mut voter =
+            TestWallet::new_account_with_discrimination(&mut OsRng, Discrimination::Production);
+        let vote_plan = VotePlanBuilder::new()
+            .proposals_count(proposals as usize)
+            .public()
+            .build();
+        let block0_configuration = ConfigurationBuilder::new()
+            .with_funds(vec![voter.to_initial_fund(1_000_000)])
+            .with_discrimination(Discrimination::Production)
+            .build_block0();
+        let block0 = block0_configuration.to_block();
+        let valid_until = BlockDate {
+            epoch: 1,
+            slot_id: 0,
+        };
+        (0..proposals)
+            .map(|proposal_index| PersistentFragmentLog {
+                time: block0_configuration.blockchain_configuration.block0_date,
+                fragment: voter
+                    .issue_vote_cast_cert(
+                        &block0.id().into(),
+                        &block0_configuration.blockchain_configuration.linear_fees,
+                        valid_until,
+                        &vote_plan,
+                        proposal_index,
+                        &Choice::new(1),
+                    )
+                    .unwrap(),
+            })
+            .collect()
+    }
+
+    fn sentry_log_of(fields: &LogCmpFields, spending_counter: u64) -> SentryFragmentLog {
+        SentryFragmentLog {
+            public_key: fields.public_key.clone(),
+            chain_proposal_index: fields.chain_proposal_index,
+            proposal_index: fields.chain_proposal_index as u32,
+            voteplan_id: fields.voteplan_id.clone(),
+            choice: fields.choice,
+            spending_counter,
+            fragment_id: fields.fragment_id.clone(),
+        }
+    }
+
+    fn sentry_logs_of(fragment_logs: &[PersistentFragmentLog]) -> Vec<SentryFragmentLog> {
+        fragment_logs
+            .iter()
+            .map(|log| sentry_log_of(&persistent_fragment_log_to_log_cmp_fields(log).unwrap(), 0))
+            .collect()
+    }
+
+    fn validated(log: &PersistentFragmentLog, spending_counter: u32) -> ValidatedFragment {
+        ValidatedFragment {
+            fragment: log.fragment.clone(),
+            recorded_date: BlockDate {
+                epoch: 0,
+                slot_id: 0,
+            },
+            spending_counter: SpendingCounter::from(spending_counter),
+        }
+    }
+
+    #[test]
+    fn sentry_logs_missing_in_fragment_logs() {
+        let sentry_logs = vec![
+            sentry_log("a", "1", 0),
+            sentry_log("a", "2", 1),
+            sentry_log("b", "3", 1),
+        ];
+        let report = logs_discrepancies(&sentry_logs, &[]);
+
+        assert_eq!(report.discrepancies.len(), 3);
+        assert!(report
+            .discrepancies
+            .iter()
+            .all(|d| d.kind == DiscrepancyKind::MissingInFragmentLogs));
+
+        assert_eq!(report.per_voter.len(), 2);
+        assert_eq!(report.per_voter[0].public_key, "a");
+        assert_eq!(report.per_voter[0].missing_in_fragment_logs, 2);
+
+        assert_eq!(report.per_proposal.len(), 2);
+        assert_eq!(report.per_proposal[1].chain_proposal_index, 1);
+        assert_eq!(report.per_proposal[1].missing_in_fragment_logs, 2);
+    }
+
+    #[test]
+    fn fragment_logs_missing_in_sentry_logs() {
+        let fragment_logs = vote_cast_logs(3);
+        let sentry_logs = sentry_logs_of(&fragment_logs[..1]);
+        let report = logs_discrepancies(&sentry_logs, &fragment_logs);
+
+        assert_eq!(report.discrepancies.len(), 2);
+        assert!(report
+            .discrepancies
+            .iter()
+            .all(|d| d.kind == DiscrepancyKind::MissingInSentryLogs
+                && d.sentry_choice.is_none()
+                && d.fragment_choice == Some(1)));
+
+        assert_eq!(report.per_voter.len(), 1);
+        assert_eq!(report.per_voter[0].missing_in_sentry_logs, 2);
+        assert_eq!(report.per_voter[0].missing_in_fragment_logs, 0);
+
+        assert_eq!(report.per_proposal.len(), 2);
+        assert!(report
+            .per_proposal
+            .iter()
+            .all(|p| p.chain_proposal_index > 0 && p.missing_in_sentry_logs == 1));
+    }
+
+    #[test]
+    fn matching_logs_have_no_discrepancies() {
+        let fragment_logs = vote_cast_logs(3);
+        let sentry_logs = sentry_logs_of(&fragment_logs);
+        let report = logs_discrepancies(&sentry_logs, &fragment_logs);
+
+        assert!(report.discrepancies.is_empty());
+        assert!(report.per_voter.is_empty());
+        assert!(report.per_proposal.is_empty());
+    }
+
+    #[test]
+    fn different_choice_is_a_fields_mismatch() {
+        let fragment_logs = vote_cast_logs(2);
+        let mut sentry_logs = sentry_logs_of(&fragment_logs);
+        sentry_logs[1].choice = 0;
+        let report = logs_discrepancies(&sentry_logs, &fragment_logs);
+
+        assert_eq!(report.discrepancies.len(), 1);
+        assert_eq!(
+            report.discrepancies[0].kind,
+            DiscrepancyKind::FieldsMismatch
+        );
+        assert_eq!(report.discrepancies[0].sentry_choice, Some(0));
+        assert_eq!(report.discrepancies[0].fragment_choice, Some(1));
+        assert_eq!(report.per_voter[0].fields_mismatch, 1);
+    }
+
+    #[test]
+    fn spending_counters_in_order() {
+        let fragment_logs = vote_cast_logs(3);
+        let sentry_logs: Vec<SentryFragmentLog> = fragment_logs
+            .iter()
+            .zip(0..)
+            .map(|(log, counter)| {
+                sentry_log_of(
+                    &persistent_fragment_log_to_log_cmp_fields(log).unwrap(),
+                    counter,
+                )
+            })
+            .collect();
+        let validated: Vec<ValidatedFragment> = fragment_logs
+            .iter()
+            .zip(0..)
+            .map(|(log, counter)| validated(log, counter))
+            .collect();
+
+        let report = compare_spending_counters(&sentry_logs, &validated);
+        assert_eq!(report.unmatched_sentry_logs, 0);
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.per_voter.len(), 1);
+        assert_eq!(report.per_voter[0].matched_ballots, 3);
+        assert_eq!(report.per_voter[0].out_of_order_counters, 0);
+    }
+
+    #[test]
+    fn spending_counters_with_gaps() {
+        let fragment_logs = vote_cast_logs(3);
+        let fields: Vec<LogCmpFields> = fragment_logs
+            .iter()
+            .map(|log| persistent_fragment_log_to_log_cmp_fields(log).unwrap())
+            .collect();
+        // the app skipped counter 1, while the node verified 0, 1 and 2
+        let sentry_logs = vec![
+            sentry_log_of(&fields[0], 0),
+            sentry_log_of(&fields[1], 2),
+            sentry_log_of(&fields[2], 3),
+        ];
+        let validated: Vec<ValidatedFragment> = fragment_logs
+            .iter()
+            .zip(0..)
+            .map(|(log, counter)| validated(log, counter))
+            .collect();
+
+        let report = compare_spending_counters(&sentry_logs, &validated);
+        assert_eq!(report.mismatches.len(), 2);
+        assert_eq!(report.mismatches[0].fragment_id, fields[1].fragment_id);
+        assert_eq!(report.mismatches[0].sentry_spending_counter, 2);
+        assert_eq!(report.mismatches[0].verified_spending_counter, 1);
+        assert_eq!(report.per_voter[0].matched_ballots, 3);
+        assert_eq!(report.per_voter[0].out_of_order_counters, 2);
+    }
+
+    #[test]
+    fn duplicated_sentry_logs_are_counted_once() {
+        let fragment_logs = vote_cast_logs(2);
+        let fields: Vec<LogCmpFields> = fragment_logs
+            .iter()
+            .map(|log| persistent_fragment_log_to_log_cmp_fields(log).unwrap())
+            .collect();
+        let sentry_logs = vec![
+            sentry_log_of(&fields[0], 0),
+            sentry_log_of(&fields[0], 0),
+            sentry_log_of(&fields[1], 1),
+            sentry_log_of(&fields[1], 1),
+        ];
+        let validated = vec![
+            validated(&fragment_logs[0], 0),
+            validated(&fragment_logs[1], 1),
+        ];
+
+        let report = compare_spending_counters(&sentry_logs, &validated);
+        assert_eq!(report.unmatched_sentry_logs, 0);
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.per_voter[0].matched_ballots, 2);
+    }
+
+    #[test]
+    fn out_of_order_spending_counters() {
+        let fragment_logs = vote_cast_logs(3);
+        let fields: Vec<LogCmpFields> = fragment_logs
+            .iter()
+            .map(|log| persistent_fragment_log_to_log_cmp_fields(log).unwrap())
+            .collect();
+        // the app sent the last two ballots in reverse order, and one log has no validated
+        // fragment at all
+        let sentry_logs = vec![
+            sentry_log_of(&fields[0], 0),
+            sentry_log_of(&fields[1], 2),
+            sentry_log_of(&fields[2], 1),
+            sentry_log("a", "unknown", 0),
+        ];
+        let validated: Vec<ValidatedFragment> = fragment_logs
+            .iter()
+            .zip(0..)
+            .map(|(log, counter)| validated(log, counter))
+            .collect();
+
+        let report = compare_spending_counters(&sentry_logs, &validated);
+        assert_eq!(report.unmatched_sentry_logs, 1);
+        assert_eq!(report.mismatches.len(), 2);
+        assert_eq!(report.per_voter.len(), 1);
+        assert_eq!(report.per_voter[0].matched_ballots, 3);
+        assert_eq!(report.per_voter[0].out_of_order_counters, 2);
+    }
+}
@@ -3,7 +3,10 @@ use crate::recovery::tally::ValidationError;
 
 use regex::Regex;
 use reqwest::{blocking::Client, Method, Url};
+use serde::Deserialize;
 
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
@@ -28,6 +31,9 @@ pub enum Error {
 
     #[error(transparent)]
     ValidationError(#[from] ValidationError),
+
+    #[error("invalid regex for checker '{name}'")]
+    InvalidCheckerRegex { name: String, source: regex::Error },
 }
 
 pub struct SentryLogClient {
@@ -133,10 +139,62 @@ pub struct RegexMatch {
     pub matches: usize,
 }
 
+pub struct MessagePrefixMatch {
+    prefix: String,
+    total_checked: usize,
+    pub matches: usize,
+}
+
+pub struct DistinctValues {
+    key: String,
+    pub values: HashSet<String>,
+}
+
+pub struct GroupByCount {
+    key: String,
+    pub groups: BTreeMap<String, usize>,
+}
+
+/// Wraps a checker so its report is labeled with a user provided name
+pub struct NamedChecker {
+    pub name: String,
+    pub checker: Box<SentryLogsStatChecker>,
+}
+
 pub enum SentryLogsStatChecker {
     SuccessfulScans(SuccessfulScan),
     MalformedQr(MalformedQr),
     RegexMatch(RegexMatch),
+    MessagePrefix(MessagePrefixMatch),
+    DistinctValues(DistinctValues),
+    GroupByCount(GroupByCount),
+    Named(NamedChecker),
+}
+
+/// Checker definition as loaded from a user provided configuration file (yaml or json)
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatCheckerConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: StatCheckerKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StatCheckerKind {
+    /// Match a regex against the string value found at `path`
+    RegexMatch { path: String, re: String },
+    /// Match the log message against a fixed prefix
+    MessagePrefix { prefix: String },
+    /// Count distinct values found at `path`
+    DistinctValues { path: String },
+    /// Count logs grouped by the value found at `path`
+    GroupBy { path: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatCheckersConfig {
+    pub checkers: Vec<StatCheckerConfig>,
 }
 
 pub struct SentryLogsStatsExecutor(Vec<SentryLogsStatChecker>);
@@ -158,6 +216,31 @@ fn raw_log_message_starts_with(log: &RawLog, pattern: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// Get the value at `path` in a log. Paths starting with `/` are treated as json pointers
+/// (e.g `/tags/os`), any other path is looked up as a top level key as is
+fn raw_log_get_path<'a>(log: &'a RawLog, path: &str) -> Option<&'a RawLog> {
+    if path.starts_with('/') {
+        log.pointer(path)
+    } else {
+        log.get(path)
+    }
+}
+
+fn raw_log_value_to_string(value: &RawLog) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn percentage(part: usize, total: usize) -> usize {
+    if total == 0 {
+        0
+    } else {
+        (part * 100) / total
+    }
+}
+
 impl Stat for SuccessfulScan {
     fn check_raw_log(&mut self, log: &RawLog) {
         if raw_log_message_starts_with(log, REGISTERED_MESSAGE) {
@@ -185,7 +268,7 @@ impl Stat for MalformedQr {
 impl Stat for RegexMatch {
     fn check_raw_log(&mut self, log: &RawLog) {
         self.total_checked += 1;
-        if let Some(entry) = log.get(&self.key).and_then(|value| value.as_str()) {
+        if let Some(entry) = raw_log_get_path(log, &self.key).and_then(|value| value.as_str()) {
             if self.re.is_match(entry) {
                 self.matches += 1;
             }
@@ -199,11 +282,78 @@ impl Stat for RegexMatch {
             self.re.as_str(),
             self.matches,
             self.total_checked,
-            (self.matches * 100) / self.total_checked,
+            percentage(self.matches, self.total_checked),
         )
     }
 }
 
+impl Stat for MessagePrefixMatch {
+    fn check_raw_log(&mut self, log: &RawLog) {
+        self.total_checked += 1;
+        if raw_log_message_starts_with(log, &self.prefix) {
+            self.matches += 1;
+        }
+    }
+
+    fn report(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "Total messages starting with [{}]: {}/{}, {}%",
+            self.prefix,
+            self.matches,
+            self.total_checked,
+            percentage(self.matches, self.total_checked),
+        )
+    }
+}
+
+impl Stat for DistinctValues {
+    fn check_raw_log(&mut self, log: &RawLog) {
+        if let Some(value) = raw_log_get_path(log, &self.key) {
+            self.values.insert(raw_log_value_to_string(value));
+        }
+    }
+
+    fn report(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "Total distinct values for [{}]: {}",
+            self.key,
+            self.values.len()
+        )
+    }
+}
+
+impl Stat for GroupByCount {
+    fn check_raw_log(&mut self, log: &RawLog) {
+        if let Some(value) = raw_log_get_path(log, &self.key) {
+            *self
+                .groups
+                .entry(raw_log_value_to_string(value))
+                .or_default() += 1;
+        }
+    }
+
+    fn report(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Counts grouped by [{}]:", self.key)?;
+        for (value, count) in &self.groups {
+            write!(formatter, "\n\t{}: {}", value, count)?;
+        }
+        Ok(())
+    }
+}
+
+impl Stat for NamedChecker {
+    fn check_raw_log(&mut self, log: &RawLog) {
+        self.checker.check_raw_log(log);
+    }
+
+    fn report(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: ", self.name)?;
+        self.checker.report(formatter)
+    }
+}
+
 impl SuccessfulScan {
     pub fn new() -> Self {
         Default::default()
@@ -227,12 +377,91 @@ impl RegexMatch {
     }
 }
 
+impl MessagePrefixMatch {
+    pub fn new(prefix: String) -> Self {
+        Self {
+            prefix,
+            total_checked: 0,
+            matches: 0,
+        }
+    }
+}
+
+impl DistinctValues {
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            values: HashSet::new(),
+        }
+    }
+}
+
+impl GroupByCount {
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+impl NamedChecker {
+    pub fn new(name: String, checker: SentryLogsStatChecker) -> Self {
+        Self {
+            name,
+            checker: Box::new(checker),
+        }
+    }
+}
+
+impl TryFrom<StatCheckerConfig> for SentryLogsStatChecker {
+    type Error = Error;
+
+    fn try_from(config: StatCheckerConfig) -> Result<Self, Self::Error> {
+        let StatCheckerConfig { name, kind } = config;
+        let checker = match kind {
+            StatCheckerKind::RegexMatch { path, re } => {
+                let re = Regex::new(&re).map_err(|source| Error::InvalidCheckerRegex {
+                    name: name.clone(),
+                    source,
+                })?;
+                SentryLogsStatChecker::RegexMatch(RegexMatch::new(re, path))
+            }
+            StatCheckerKind::MessagePrefix { prefix } => {
+                SentryLogsStatChecker::MessagePrefix(MessagePrefixMatch::new(prefix))
+            }
+            StatCheckerKind::DistinctValues { path } => {
+                SentryLogsStatChecker::DistinctValues(DistinctValues::new(path))
+            }
+            StatCheckerKind::GroupBy { path } => {
+                SentryLogsStatChecker::GroupByCount(GroupByCount::new(path))
+            }
+        };
+        Ok(SentryLogsStatChecker::Named(NamedChecker::new(
+            name, checker,
+        )))
+    }
+}
+
+impl StatCheckersConfig {
+    pub fn build_checkers(self) -> Result<Vec<SentryLogsStatChecker>, Error> {
+        self.checkers
+            .into_iter()
+            .map(SentryLogsStatChecker::try_from)
+            .collect()
+    }
+}
+
 impl Stat for SentryLogsStatChecker {
     fn check_raw_log(&mut self, log: &RawLog) {
         match self {
             SentryLogsStatChecker::SuccessfulScans(scan) => scan.check_raw_log(log),
             SentryLogsStatChecker::MalformedQr(qr) => qr.check_raw_log(log),
             SentryLogsStatChecker::RegexMatch(re) => re.check_raw_log(log),
+            SentryLogsStatChecker::MessagePrefix(prefix) => prefix.check_raw_log(log),
+            SentryLogsStatChecker::DistinctValues(distinct) => distinct.check_raw_log(log),
+            SentryLogsStatChecker::GroupByCount(group) => group.check_raw_log(log),
+            SentryLogsStatChecker::Named(named) => named.check_raw_log(log),
         };
     }
 
@@ -241,6 +470,10 @@ impl Stat for SentryLogsStatChecker {
             SentryLogsStatChecker::SuccessfulScans(scan) => scan.report(formatter),
             SentryLogsStatChecker::MalformedQr(qr) => qr.report(formatter),
             SentryLogsStatChecker::RegexMatch(re) => re.report(formatter),
+            SentryLogsStatChecker::MessagePrefix(prefix) => prefix.report(formatter),
+            SentryLogsStatChecker::DistinctValues(distinct) => distinct.report(formatter),
+            SentryLogsStatChecker::GroupByCount(group) => group.report(formatter),
+            SentryLogsStatChecker::Named(named) => named.report(formatter),
         }
     }
 }
@@ -328,8 +561,9 @@ impl FromStr for SentryFragmentLog {
 mod tests {
     use super::SentryFragmentLog;
     use crate::logs::sentry::{
-        MalformedQr, RawLog, RegexMatch, SentryLogsStatChecker, SentryLogsStatsExecutor, Stat,
-        SuccessfulScan, MALFORMED_QR_MESSAGE, REGISTERED_MESSAGE,
+        DistinctValues, GroupByCount, MalformedQr, RawLog, RegexMatch, SentryLogsStatChecker,
+        SentryLogsStatsExecutor, Stat, StatCheckersConfig, SuccessfulScan, MALFORMED_QR_MESSAGE,
+        REGISTERED_MESSAGE,
     };
 
     use std::str::FromStr;
@@ -411,7 +645,76 @@ mod tests {
                     assert_eq!(re.total_checked, total_malformed + total_success);
                     assert_eq!(re.matches, total_success);
                 }
+                _ => unreachable!(),
             }
         }
     }
+
+    #[test]
+    fn test_nested_path_scans() {
+        let logs: Vec<RawLog> = vec![
+            serde_json::json!({ "tags": { "os": "android 11" }, "user": { "id": "a" } }),
+            serde_json::json!({ "tags": { "os": "android 10" }, "user": { "id": "a" } }),
+            serde_json::json!({ "tags": { "os": "ios" }, "user": { "id": "b" } }),
+            serde_json::json!({ "message": REGISTERED_MESSAGE }),
+        ];
+
+        let mut re = RegexMatch::new(Regex::from_str("^android").unwrap(), "/tags/os".to_string());
+        let mut distinct = DistinctValues::new("/user/id".to_string());
+        let mut group = GroupByCount::new("/tags/os".to_string());
+        re.process_raw_logs(logs.iter());
+        distinct.process_raw_logs(logs.iter());
+        group.process_raw_logs(logs.iter());
+
+        assert_eq!(re.matches, 2);
+        assert_eq!(distinct.values.len(), 2);
+        assert_eq!(group.groups.len(), 3);
+        assert_eq!(group.groups["ios"], 1);
+    }
+
+    #[test]
+    fn test_dotted_keys_are_literal() {
+        let logs: Vec<RawLog> = vec![
+            serde_json::json!({ "tags.os": "android", "tags": { "os": "ios" } }),
+            serde_json::json!({ "tags": { "os": "ios" } }),
+        ];
+
+        let mut group = GroupByCount::new("tags.os".to_string());
+        group.process_raw_logs(logs.iter());
+
+        assert_eq!(group.groups.len(), 1);
+        assert_eq!(group.groups["android"], 1);
+    }
+
+    #[test]
+    fn test_checkers_from_config() {
+        let config: StatCheckersConfig = serde_yaml::from_str(
+            r#"
+checkers:
+  - name: registrations
+    type: message_prefix
+    prefix: "User registered"
+  - name: android
+    type: regex_match
+    path: /tags/os
+    re: "^android"
+  - name: users
+    type: distinct_values
+    path: /user/id
+  - name: by os
+    type: group_by
+    path: /tags/os
+"#,
+        )
+        .unwrap();
+        let checkers = config.build_checkers().unwrap();
+        assert_eq!(checkers.len(), 4);
+
+        let mut executor = SentryLogsStatsExecutor::new(checkers);
+        executor.process_raw_logs(generate_test_raw_log_set(3, 2).iter());
+        let report = executor.to_string();
+        assert!(report
+            .contains("registrations: Total messages starting with [User registered]: 3/5, 60%"));
+        assert!(report.contains("users: Total distinct values for [/user/id]: 0"));
+    }
 }
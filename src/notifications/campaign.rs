@@ -0,0 +1,342 @@
+use crate::notifications::requests::create_message::{
+    self, ContentSettings, ContentSettingsBuilder, ContentType, CreateMessage,
+    CreateMessageBuilder, MultiLanguageContent, DATETIME_FMT,
+};
+use crate::notifications::schedule::{SendAt, VotePlanEvent, VotePlansSchedule};
+
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use thiserror::Error;
+
+use std::collections::{HashMap, HashSet};
+
+pub type Variables = HashMap<String, String>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unknown variable '{variable}' used in notification '{notification}'")]
+    UnknownVariable {
+        notification: String,
+        variable: String,
+    },
+
+    #[error("unclosed variable placeholder in notification '{notification}'")]
+    UnclosedPlaceholder { notification: String },
+
+    #[error("invalid send date '{send_date}' in notification '{notification}', expected format \"Y-m-d H:M\"")]
+    InvalidSendDate {
+        notification: String,
+        send_date: String,
+        source: chrono::ParseError,
+    },
+
+    #[error("notification '{0}' should have either a send_date or a send_at entry")]
+    InvalidSendDateEntries(String),
+
+    #[error("notification '{0}' uses send_at but no block0 schedule was provided")]
+    MissingSchedule(String),
+
+    #[error("notification '{0}' has empty content")]
+    EmptyContent(String),
+
+    #[error("notification name '{0}' is used more than once")]
+    DuplicatedNotification(String),
+
+    #[error(transparent)]
+    CreateMessage(#[from] create_message::Error),
+}
+
+/// Campaign plan, a sequence of notifications sent every fund
+/// (registration opens, voting starts, voting ends soon, results...)
+#[derive(Debug, Clone, Deserialize)]
+pub struct CampaignPlan {
+    /// Pushwoosh application code where messages will be sent
+    pub application: String,
+    /// Values available to templates as `{{ name }}`, for example the fund number
+    #[serde(default)]
+    pub variables: Variables,
+    pub notifications: Vec<PlannedNotification>,
+    /// Voteplans dates used to resolve `send_at` entries
+    #[serde(skip)]
+    pub schedule: Option<VotePlansSchedule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlannedNotification {
+    /// Unique name of the notification within the plan
+    pub name: String,
+    /// Send date of format "Y-m-d H:M", it can be templated
+    pub send_date: Option<String>,
+    /// Send date relative to block0 voteplans events, for example `vote_end-24h`.
+    /// A notification is scheduled for every distinct voteplan date
+    pub send_at: Option<SendAt>,
+    /// Timezone of send date, for example "America/New_York"
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub ignore_user_timezones: bool,
+    pub campaign: Option<String>,
+    pub filter: Option<String>,
+    /// Plain or multi language (language code to text) templated content
+    pub content: ContentType,
+}
+
+/// Notification of a plan with all its templates rendered
+#[derive(Debug)]
+pub struct ExpandedNotification {
+    pub name: String,
+    pub message: CreateMessage,
+}
+
+/// Replace every `{{ name }}` placeholder in `template` with its variable value
+fn render(template: &str, variables: &Variables, notification: &str) -> Result<String, Error> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| Error::UnclosedPlaceholder {
+                notification: notification.to_string(),
+            })?;
+        let variable = rest[start + 2..start + end].trim();
+        let value = variables
+            .get(variable)
+            .ok_or_else(|| Error::UnknownVariable {
+                notification: notification.to_string(),
+                variable: variable.to_string(),
+            })?;
+        rendered.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn render_content(
+    content: &ContentType,
+    variables: &Variables,
+    notification: &str,
+) -> Result<ContentType, Error> {
+    let rendered = match content {
+        ContentType::Plain(text) => ContentType::Plain(render(text, variables, notification)?),
+        ContentType::MultiLanguage(texts) => ContentType::MultiLanguage(
+            texts
+                .iter()
+                .map(|(language, text)| {
+                    render(text, variables, notification).map(|text| (language.clone(), text))
+                })
+                .collect::<Result<MultiLanguageContent, _>>()?,
+        ),
+    };
+    let is_empty = match &rendered {
+        ContentType::Plain(text) => text.trim().is_empty(),
+        ContentType::MultiLanguage(texts) => {
+            texts.is_empty() || texts.values().any(|text| text.trim().is_empty())
+        }
+    };
+    if is_empty {
+        return Err(Error::EmptyContent(notification.to_string()));
+    }
+    Ok(rendered)
+}
+
+impl PlannedNotification {
+    pub fn render_send_date(&self, variables: &Variables) -> Result<NaiveDateTime, Error> {
+        let send_date = self
+            .send_date
+            .as_ref()
+            .ok_or_else(|| Error::InvalidSendDateEntries(self.name.clone()))?;
+        let send_date = render(send_date, variables, &self.name)?;
+        NaiveDateTime::parse_from_str(&send_date, DATETIME_FMT).map_err(|source| {
+            Error::InvalidSendDate {
+                notification: self.name.clone(),
+                send_date,
+                source,
+            }
+        })
+    }
+
+    fn content_settings(
+        &self,
+        variables: &Variables,
+        schedule: Option<&VotePlansSchedule>,
+    ) -> Result<Vec<ContentSettings>, Error> {
+        let content = render_content(&self.content, variables, &self.name)?;
+        let builder = || {
+            ContentSettingsBuilder::new()
+                .with_campaign(self.campaign.clone())
+                .with_filter(self.filter.clone())
+                .with_ignore_user_timezones(self.ignore_user_timezones)
+                .with_content(content.clone())
+        };
+        match (&self.send_date, &self.send_at) {
+            (Some(_), None) => Ok(vec![builder()
+                .with_naive_send_date(self.render_send_date(variables)?)
+                .with_timezone(self.timezone.clone())
+                .build()?]),
+            (None, Some(send_at)) => {
+                let schedule = schedule.ok_or_else(|| Error::MissingSchedule(self.name.clone()))?;
+                schedule
+                    .send_dates(send_at)
+                    .into_iter()
+                    .map(|send_date| {
+                        // block0 dates are computed in UTC
+                        builder()
+                            .with_send_date(send_date)
+                            .with_timezone(Some("UTC".to_string()))
+                            .build()
+                            .map_err(Into::into)
+                    })
+                    .collect()
+            }
+            _ => Err(Error::InvalidSendDateEntries(self.name.clone())),
+        }
+    }
+
+    fn expand(
+        &self,
+        auth: &str,
+        application: &str,
+        variables: &Variables,
+        schedule: Option<&VotePlansSchedule>,
+    ) -> Result<ExpandedNotification, Error> {
+        let message = self
+            .content_settings(variables, schedule)?
+            .into_iter()
+            .fold(
+                CreateMessageBuilder::new()
+                    .with_auth(auth.to_string())
+                    .with_application(application.to_string()),
+                |builder, content_settings| builder.add_content_settings(content_settings),
+            )
+            .build()?;
+        Ok(ExpandedNotification {
+            name: self.name.clone(),
+            message,
+        })
+    }
+}
+
+impl CampaignPlan {
+    /// Add or override plan variables
+    pub fn extend_variables(&mut self, variables: impl IntoIterator<Item = (String, String)>) {
+        self.variables.extend(variables);
+    }
+
+    /// Use block0 voteplans dates for `send_at` entries, the earliest voteplans dates are also
+    /// available to templates as `vote_start`, `vote_end` and `committee_end` (UTC) variables
+    pub fn with_schedule(mut self, schedule: VotePlansSchedule) -> Self {
+        for event in &[
+            VotePlanEvent::VoteStart,
+            VotePlanEvent::VoteEnd,
+            VotePlanEvent::CommitteeEnd,
+        ] {
+            self.variables
+                .entry(event.to_string())
+                .or_insert_with(|| schedule.first(*event).format(DATETIME_FMT).to_string());
+        }
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Check that every notification of the plan can be rendered
+    pub fn validate(&self) -> Result<(), Error> {
+        self.expand("").map(|_| ())
+    }
+
+    /// Render every notification of the plan into a `CreateMessage` request
+    pub fn expand(&self, auth: &str) -> Result<Vec<ExpandedNotification>, Error> {
+        let mut names = HashSet::new();
+        self.notifications
+            .iter()
+            .map(|notification| {
+                if !names.insert(&notification.name) {
+                    return Err(Error::DuplicatedNotification(notification.name.clone()));
+                }
+                notification.expand(
+                    auth,
+                    &self.application,
+                    &self.variables,
+                    self.schedule.as_ref(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, CampaignPlan, Error, Variables};
+
+    const PLAN: &str = r#"
+application: APP-CODE
+variables:
+  fund: "8"
+notifications:
+  - name: voting-starts
+    send_date: "{{ vote_start }}"
+    timezone: UTC
+    content:
+      en: "Fund {{fund}} voting is open!"
+      es: "¡La votación del Fund {{ fund }} está abierta!"
+  - name: results
+    send_date: "2022-03-01 12:00"
+    content: "Fund {{ fund }} results are out"
+"#;
+
+    #[test]
+    fn render_template() {
+        let variables: Variables = vec![("fund".to_string(), "8".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            render("Fund {{fund}}, {{ fund }}!", &variables, "test").unwrap(),
+            "Fund 8, 8!"
+        );
+        assert!(matches!(
+            render("Fund {{ number }}", &variables, "test"),
+            Err(Error::UnknownVariable { .. })
+        ));
+        assert!(matches!(
+            render("Fund {{ fund", &variables, "test"),
+            Err(Error::UnclosedPlaceholder { .. })
+        ));
+    }
+
+    #[test]
+    fn expand_plan() {
+        let mut plan: CampaignPlan = serde_yaml::from_str(PLAN).unwrap();
+        assert!(matches!(
+            plan.validate(),
+            Err(Error::UnknownVariable { .. })
+        ));
+
+        plan.extend_variables(vec![(
+            "vote_start".to_string(),
+            "2022-02-10 11:00".to_string(),
+        )]);
+        let expanded = plan.expand("token").unwrap();
+        assert_eq!(expanded.len(), 2);
+
+        let json = serde_json::to_value(&expanded[0].message).unwrap();
+        assert_eq!(json["notifications"][0]["send_date"], "2022-02-10 11:00");
+        assert_eq!(
+            json["notifications"][0]["content"]["en"],
+            "Fund 8 voting is open!"
+        );
+    }
+
+    #[test]
+    fn send_at_requires_schedule() {
+        let mut plan: CampaignPlan = serde_yaml::from_str(PLAN).unwrap();
+        plan.notifications[0].send_date = None;
+        plan.notifications[0].send_at = Some("vote_end-24h".parse().unwrap());
+        assert!(matches!(plan.validate(), Err(Error::MissingSchedule(_))));
+
+        plan.notifications[0].send_date = Some("2022-02-10 11:00".to_string());
+        assert!(matches!(
+            plan.validate(),
+            Err(Error::InvalidSendDateEntries(_))
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::notifications::requests::Request;
+
+use chain_crypto::hash::Blake2b256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("could not access ledger file {path}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("malformed ledger file {path}")]
+    Malformed {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentEntry {
+    /// Hash of the json payload sent to pushwoosh
+    pub hash: String,
+    pub endpoint: String,
+    /// Message codes returned by pushwoosh
+    pub message_codes: Vec<String>,
+    /// RFC 3339 date of when the request was sent
+    pub sent_at: String,
+}
+
+/// Local record of already sent requests, used so re-running a failed script does not
+/// send the same notifications twice
+#[derive(Debug)]
+pub struct SentLedger {
+    path: PathBuf,
+    entries: Vec<SentEntry>,
+}
+
+pub fn request_hash(request: &Request) -> Result<String, Error> {
+    let payload = serde_json::to_vec(request)?;
+    Ok(Blake2b256::hash(&payload).to_string())
+}
+
+impl SentLedger {
+    /// Load the ledger from `path`, an empty ledger is used if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let entries = if path.exists() {
+            let content = std::fs::read(path).map_err(|source| Error::Io {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            serde_json::from_slice(&content).map_err(|source| Error::Malformed {
+                path: path.to_path_buf(),
+                source,
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn find(&self, hash: &str) -> Option<&SentEntry> {
+        self.entries.iter().find(|entry| entry.hash == hash)
+    }
+
+    /// Add a sent request to the ledger and persist it right away
+    pub fn record(
+        &mut self,
+        hash: String,
+        request: &Request,
+        message_codes: Vec<String>,
+    ) -> Result<(), Error> {
+        self.entries.push(SentEntry {
+            hash,
+            endpoint: request.endpoint().to_string(),
+            message_codes,
+            sent_at: chrono::Utc::now().to_rfc3339(),
+        });
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let content = serde_json::to_vec_pretty(&self.entries)?;
+        std::fs::write(&self.path, content).map_err(|source| Error::Io {
+            path: self.path.clone(),
+            source,
+        })
+    }
+}
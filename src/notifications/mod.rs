@@ -1,5 +1,8 @@
+pub mod campaign;
+pub mod ledger;
 pub mod requests;
 pub mod responses;
+pub mod schedule;
 pub mod send;
 
 use thiserror::Error;
@@ -10,12 +13,21 @@ pub enum Error {
     #[error(transparent)]
     CreateMessageError(#[from] requests::create_message::Error),
 
+    #[error(transparent)]
+    CampaignError(#[from] campaign::Error),
+
+    #[error(transparent)]
+    ScheduleError(#[from] schedule::Error),
+
     #[error(transparent)]
     SerdeError(#[from] serde_json::Error),
 
     #[error(transparent)]
     RequestError(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    UrlParseError(#[from] url::ParseError),
+
     #[error("error reading file, source: {0}")]
     FileError(#[from] std::io::Error),
 
@@ -24,4 +36,21 @@ pub enum Error {
 
     #[error("request was unsuccessful, feedback:\n {response}")]
     UnsuccessfulRequest { response: String },
+
+    #[error("server error {status}, feedback:\n {response}")]
+    ServerError {
+        status: reqwest::StatusCode,
+        response: String,
+    },
+
+    #[error(
+        "request was rejected by pushwoosh with status code {status_code:?}: {status_message}"
+    )]
+    RejectedRequest {
+        status_code: Option<u64>,
+        status_message: String,
+    },
+
+    #[error(transparent)]
+    LedgerError(#[from] ledger::Error),
 }
@@ -1,8 +1,8 @@
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
-use std::fmt::Display;
+use std::fmt::{self, Display};
 
 use thiserror::Error;
 
@@ -23,14 +23,14 @@ pub enum Error {
 
 pub type MultiLanguageContent = HashMap<String, String>;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
 pub enum ContentType {
     Plain(String),
     MultiLanguage(MultiLanguageContent),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ContentSettings {
     send_date: String,
     content: ContentType,
@@ -53,6 +53,17 @@ pub struct CreateMessage {
     notifications: Vec<ContentSettings>,
 }
 
+// written by hand so the access token does not end up in logs
+impl fmt::Debug for CreateMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateMessage")
+            .field("auth", &"<redacted>")
+            .field("application", &self.application)
+            .field("notifications", &self.notifications)
+            .finish()
+    }
+}
+
 pub struct ContentSettingsBuilder {
     send_date: String,
     content: Option<ContentType>,
@@ -89,6 +100,12 @@ impl ContentSettingsBuilder {
         self
     }
 
+    /// Set a send date without offset, it is interpreted in the configured timezone (if any)
+    pub fn with_naive_send_date(mut self, datetime: NaiveDateTime) -> Self {
+        self.send_date = datetime.format(DATETIME_FMT).to_string();
+        self
+    }
+
     pub fn with_plain_content(mut self, content: String) -> Self {
         self.content = Some(ContentType::Plain(content));
         self
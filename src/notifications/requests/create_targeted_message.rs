@@ -0,0 +1,109 @@
+use crate::notifications::requests::create_message::{ContentType, Error, DATETIME_FMT};
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+
+/// Message sent to the devices matching a filter expression
+#[derive(Serialize, Deserialize)]
+pub struct CreateTargetedMessage {
+    /// API access token from Pushwoosh Control Panel
+    auth: String,
+    send_date: String,
+    content: ContentType,
+    /// Filter expression as described by pushwoosh API, for example `A("APP_CODE") * T("FundParticipation", EQ, "fund7")`
+    devices_filter: String,
+    ignore_user_timezones: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+}
+
+pub struct CreateTargetedMessageBuilder {
+    auth: Option<String>,
+    send_date: String,
+    content: Option<ContentType>,
+    devices_filter: Option<String>,
+    ignore_user_timezones: bool,
+    timezone: Option<String>,
+    campaign: Option<String>,
+}
+
+impl Default for CreateTargetedMessageBuilder {
+    fn default() -> Self {
+        Self {
+            auth: None,
+            send_date: "now".to_string(),
+            content: None,
+            devices_filter: None,
+            ignore_user_timezones: false,
+            timezone: None,
+            campaign: None,
+        }
+    }
+}
+
+impl CreateTargetedMessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_auth(mut self, auth: String) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn with_send_date<Tz>(mut self, datetime: DateTime<Tz>) -> Self
+    where
+        Tz: chrono::TimeZone,
+        Tz::Offset: Display,
+    {
+        self.send_date = datetime.format(DATETIME_FMT).to_string();
+        self
+    }
+
+    pub fn with_content(mut self, content: ContentType) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    pub fn with_devices_filter(mut self, devices_filter: String) -> Self {
+        self.devices_filter = Some(devices_filter);
+        self
+    }
+
+    pub fn with_ignore_user_timezones(mut self, ignore: bool) -> Self {
+        self.ignore_user_timezones = ignore;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<String>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn with_campaign(mut self, campaign: Option<String>) -> Self {
+        self.campaign = campaign;
+        self
+    }
+
+    pub fn build(self) -> Result<CreateTargetedMessage, Error> {
+        let missing_field = |field_name: &str| Error::MissingFieldOnBuilderError {
+            object_name: "CreateTargetedMessage".to_string(),
+            field_name: field_name.to_string(),
+        };
+        Ok(CreateTargetedMessage {
+            auth: self.auth.ok_or_else(|| missing_field("auth"))?,
+            send_date: self.send_date,
+            content: self.content.ok_or_else(|| missing_field("content"))?,
+            devices_filter: self
+                .devices_filter
+                .ok_or_else(|| missing_field("devices_filter"))?,
+            ignore_user_timezones: self.ignore_user_timezones,
+            timezone: self.timezone,
+            campaign: self.campaign,
+        })
+    }
+}
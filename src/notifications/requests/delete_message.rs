@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Delete a scheduled message, only messages not yet sent can be deleted
+#[derive(Serialize, Deserialize)]
+pub struct DeleteMessage {
+    /// API access token from Pushwoosh Control Panel
+    auth: String,
+    /// Message code obtained in createMessage
+    message: String,
+}
+
+impl DeleteMessage {
+    pub fn new(auth: String, message: String) -> Self {
+        Self { auth, message }
+    }
+}
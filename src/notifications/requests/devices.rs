@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+/// Pushwoosh device types, see Pushwoosh registerDevice API documentation
+pub const DEVICE_TYPE_IOS: u8 = 1;
+pub const DEVICE_TYPE_ANDROID: u8 = 3;
+
+/// Tag values accepted by Pushwoosh, lists are used for multi value tags
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TagValue {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    List(Vec<String>),
+}
+
+pub type Tags = HashMap<String, TagValue>;
+
+/// Register a device for the application
+#[derive(Serialize, Deserialize)]
+pub struct RegisterDevice {
+    /// Pushwoosh application code
+    application: String,
+    /// Push token of the device
+    push_token: String,
+    /// Unique identifier of the device
+    hwid: String,
+    /// Device type, check `DEVICE_TYPE_*` constants
+    device_type: u8,
+    /// Language locale of the device, for example "en"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// Timezone offset in seconds for the device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timezone: Option<i64>,
+}
+
+/// Set tags for a device, used to segment notifications receivers (for example by fund participation)
+#[derive(Serialize, Deserialize)]
+pub struct SetTags {
+    /// Pushwoosh application code
+    application: String,
+    /// Unique identifier of the device
+    hwid: String,
+    /// Tags to set for the device
+    tags: Tags,
+}
+
+impl RegisterDevice {
+    pub fn new(application: String, push_token: String, hwid: String, device_type: u8) -> Self {
+        Self {
+            application,
+            push_token,
+            hwid,
+            device_type,
+            language: None,
+            timezone: None,
+        }
+    }
+
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<i64>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+}
+
+impl SetTags {
+    pub fn new(application: String, hwid: String, tags: Tags) -> Self {
+        Self {
+            application,
+            hwid,
+            tags,
+        }
+    }
+}
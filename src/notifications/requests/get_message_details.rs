@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Retrieve the details of a message
+#[derive(Serialize, Deserialize)]
+pub struct GetMessageDetails {
+    /// API access token from Pushwoosh Control Panel
+    auth: String,
+    /// Message code or message id
+    message: String,
+}
+
+impl GetMessageDetails {
+    pub fn new(auth: String, message: String) -> Self {
+        Self { auth, message }
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Retrieve the history of sent messages
+#[derive(Serialize, Deserialize, Default)]
+pub struct GetPushHistory {
+    /// API access token from Pushwoosh Control Panel
+    auth: String,
+    /// Messages source filter, for example "API" or "CP"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// Field to search by, for example "applicationCode" or "notificationCode"
+    #[serde(rename = "searchBy", skip_serializing_if = "Option::is_none")]
+    search_by: Option<String>,
+    /// Value to search for in the `search_by` field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    /// Used for pagination, last message id from the previous request
+    #[serde(rename = "lastNotificationID", skip_serializing_if = "Option::is_none")]
+    last_notification_id: Option<String>,
+}
+
+impl GetPushHistory {
+    pub fn new(auth: String) -> Self {
+        Self {
+            auth,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_source(mut self, source: Option<String>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_search(mut self, search_by: Option<String>, value: Option<String>) -> Self {
+        self.search_by = search_by;
+        self.value = value;
+        self
+    }
+
+    pub fn with_last_notification_id(mut self, last_notification_id: Option<String>) -> Self {
+        self.last_notification_id = last_notification_id;
+        self
+    }
+}
@@ -1,10 +1,24 @@
 pub mod create_message;
-use serde::{Deserialize, Serialize};
+pub mod create_targeted_message;
+pub mod delete_message;
+pub mod devices;
+pub mod get_message_details;
+pub mod get_push_history;
+use serde::Serialize;
 
-#[derive(Serialize, Deserialize)]
+/// Body of a request, serialized as is under the `request` key. Pushwoosh knows which method
+/// is called from the endpoint, so there is no tag to deserialize it back: parse the data of
+/// the expected method instead (e.g. [`create_message::CreateMessage`]).
+#[derive(Serialize)]
 #[serde(untagged)]
 pub enum RequestData {
     CreateMessageRequest(create_message::CreateMessage),
+    CreateTargetedMessageRequest(create_targeted_message::CreateTargetedMessage),
+    DeleteMessageRequest(delete_message::DeleteMessage),
+    GetMessageDetailsRequest(get_message_details::GetMessageDetails),
+    GetPushHistoryRequest(get_push_history::GetPushHistory),
+    RegisterDeviceRequest(devices::RegisterDevice),
+    SetTagsRequest(devices::SetTags),
 }
 
 #[derive(Serialize)]
@@ -12,8 +26,176 @@ pub struct Request {
     request: RequestData,
 }
 
+impl RequestData {
+    /// Pushwoosh API method name for the request
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            RequestData::CreateMessageRequest(_) => "createMessage",
+            RequestData::CreateTargetedMessageRequest(_) => "createTargetedMessage",
+            RequestData::DeleteMessageRequest(_) => "deleteMessage",
+            RequestData::GetMessageDetailsRequest(_) => "getMessageDetails",
+            RequestData::GetPushHistoryRequest(_) => "getPushHistory",
+            RequestData::RegisterDeviceRequest(_) => "registerDevice",
+            RequestData::SetTagsRequest(_) => "setTags",
+        }
+    }
+}
+
 impl Request {
     pub fn new(data: RequestData) -> Self {
         Self { request: data }
     }
+
+    pub fn endpoint(&self) -> &'static str {
+        self.request.endpoint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::create_message::{ContentSettingsBuilder, CreateMessage, CreateMessageBuilder};
+    use super::create_targeted_message::CreateTargetedMessageBuilder;
+    use super::delete_message::DeleteMessage;
+    use super::devices::{RegisterDevice, SetTags, TagValue, DEVICE_TYPE_ANDROID};
+    use super::get_message_details::GetMessageDetails;
+    use super::get_push_history::GetPushHistory;
+    use super::{Request, RequestData};
+    use serde_json::json;
+
+    fn to_json(data: RequestData) -> (&'static str, serde_json::Value) {
+        let request = Request::new(data);
+        (request.endpoint(), serde_json::to_value(&request).unwrap())
+    }
+
+    #[test]
+    fn create_targeted_message() {
+        let message = CreateTargetedMessageBuilder::new()
+            .with_auth("token".to_string())
+            .with_content(serde_json::from_str(r#""hello""#).unwrap())
+            .with_devices_filter(r#"A("APP")"#.to_string())
+            .build()
+            .unwrap();
+        assert_eq!(
+            to_json(RequestData::CreateTargetedMessageRequest(message)),
+            (
+                "createTargetedMessage",
+                json!({"request": {
+                    "auth": "token",
+                    "send_date": "now",
+                    "content": "hello",
+                    "devices_filter": "A(\"APP\")",
+                    "ignore_user_timezones": false,
+                }})
+            )
+        );
+    }
+
+    #[test]
+    fn message_requests() {
+        assert_eq!(
+            to_json(RequestData::DeleteMessageRequest(DeleteMessage::new(
+                "token".to_string(),
+                "code".to_string()
+            ))),
+            (
+                "deleteMessage",
+                json!({"request": {"auth": "token", "message": "code"}})
+            )
+        );
+        assert_eq!(
+            to_json(RequestData::GetMessageDetailsRequest(
+                GetMessageDetails::new("token".to_string(), "code".to_string())
+            )),
+            (
+                "getMessageDetails",
+                json!({"request": {"auth": "token", "message": "code"}})
+            )
+        );
+        assert_eq!(
+            to_json(RequestData::GetPushHistoryRequest(
+                GetPushHistory::new("token".to_string())
+                    .with_search(Some("applicationCode".to_string()), Some("APP".to_string()))
+                    .with_last_notification_id(Some("42".to_string()))
+            )),
+            (
+                "getPushHistory",
+                json!({"request": {
+                    "auth": "token",
+                    "searchBy": "applicationCode",
+                    "value": "APP",
+                    "lastNotificationID": "42",
+                }})
+            )
+        );
+    }
+
+    #[test]
+    fn device_requests() {
+        assert_eq!(
+            to_json(RequestData::RegisterDeviceRequest(
+                RegisterDevice::new(
+                    "APP".to_string(),
+                    "push".to_string(),
+                    "hwid".to_string(),
+                    DEVICE_TYPE_ANDROID
+                )
+                .with_language(Some("en".to_string()))
+            )),
+            (
+                "registerDevice",
+                json!({"request": {
+                    "application": "APP",
+                    "push_token": "push",
+                    "hwid": "hwid",
+                    "device_type": 3,
+                    "language": "en",
+                }})
+            )
+        );
+        let tags = vec![
+            ("Fund".to_string(), TagValue::String("fund7".to_string())),
+            ("Voted".to_string(), TagValue::Boolean(true)),
+            (
+                "Proposals".to_string(),
+                TagValue::List(vec!["1".to_string()]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            to_json(RequestData::SetTagsRequest(SetTags::new(
+                "APP".to_string(),
+                "hwid".to_string(),
+                tags
+            ))),
+            (
+                "setTags",
+                json!({"request": {
+                    "application": "APP",
+                    "hwid": "hwid",
+                    "tags": {"Fund": "fund7", "Voted": true, "Proposals": ["1"]},
+                }})
+            )
+        );
+    }
+
+    #[test]
+    fn create_message_does_not_accept_other_requests() {
+        let message = CreateMessageBuilder::new()
+            .with_auth("token".to_string())
+            .with_application("APP".to_string())
+            .add_content_settings(
+                ContentSettingsBuilder::new()
+                    .with_plain_content("hello".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(serde_json::from_str::<CreateMessage>(&json).is_ok());
+
+        let history = serde_json::to_string(&GetPushHistory::new("token".to_string())).unwrap();
+        assert!(serde_json::from_str::<CreateMessage>(&history).is_err());
+    }
 }
@@ -1,6 +1,7 @@
+use crate::notifications::responses::{deserialize_status_code, serialize_status_code};
+
 use reqwest::StatusCode;
-use serde::de::Error;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct InnerResponse {
@@ -19,21 +20,6 @@ pub struct CreateMessageResponse {
     pub response: InnerResponse,
 }
 
-fn deserialize_status_code<'de, D>(deserializer: D) -> Result<StatusCode, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    StatusCode::from_u16(u16::deserialize(deserializer)?)
-        .map_err(|_| D::Error::custom("Invalid StatusCode"))
-}
-
-fn serialize_status_code<S>(status_code: &StatusCode, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    serializer.serialize_u16(status_code.as_u16())
-}
-
 #[cfg(test)]
 mod test {
     use super::CreateMessageResponse;
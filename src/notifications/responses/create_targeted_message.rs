@@ -0,0 +1,21 @@
+use crate::notifications::responses::{deserialize_status_code, serialize_status_code};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InnerResponse {
+    #[serde(rename = "messageCode")]
+    pub message_code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateTargetedMessageResponse {
+    #[serde(
+        deserialize_with = "deserialize_status_code",
+        serialize_with = "serialize_status_code"
+    )]
+    pub status_code: StatusCode,
+    pub status_message: String,
+    pub response: InnerResponse,
+}
@@ -1 +1,76 @@
 pub mod create_message;
+pub mod create_targeted_message;
+
+use reqwest::StatusCode;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Response for the API methods which content is not typed (deleteMessage, getMessageDetails,
+/// getPushHistory, registerDevice, setTags), the inner response is kept as raw json
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GenericResponse {
+    #[serde(
+        deserialize_with = "deserialize_status_code",
+        serialize_with = "serialize_status_code"
+    )]
+    pub status_code: StatusCode,
+    pub status_message: String,
+    #[serde(default)]
+    pub response: Option<serde_json::Value>,
+}
+
+/// Responses from which the codes of the created messages can be retrieved
+pub trait MessageCodes {
+    fn message_codes(&self) -> Vec<String>;
+}
+
+impl MessageCodes for create_message::CreateMessageResponse {
+    fn message_codes(&self) -> Vec<String> {
+        self.response.messages.clone()
+    }
+}
+
+impl MessageCodes for create_targeted_message::CreateTargetedMessageResponse {
+    fn message_codes(&self) -> Vec<String> {
+        vec![self.response.message_code.clone()]
+    }
+}
+
+impl MessageCodes for GenericResponse {
+    fn message_codes(&self) -> Vec<String> {
+        let response = match &self.response {
+            Some(response) => response,
+            None => return Vec::new(),
+        };
+        ["Messages", "messages", "messageCode"]
+            .iter()
+            .filter_map(|key| response.get(key))
+            .flat_map(|value| match value {
+                serde_json::Value::Array(codes) => codes
+                    .iter()
+                    .filter_map(|code| code.as_str().map(str::to_string))
+                    .collect(),
+                serde_json::Value::String(code) => vec![code.clone()],
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn deserialize_status_code<'de, D>(deserializer: D) -> Result<StatusCode, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    StatusCode::from_u16(u16::deserialize(deserializer)?)
+        .map_err(|_| D::Error::custom("Invalid StatusCode"))
+}
+
+pub(crate) fn serialize_status_code<S>(
+    status_code: &StatusCode,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u16(status_code.as_u16())
+}
@@ -0,0 +1,279 @@
+use crate::recovery::tally::{
+    blockdate_to_system_time, timeframe_and_era_from_block0_configuration, voteplans_from_block0,
+};
+
+use chain_impl_mockchain::block::{Block, BlockDate};
+use chrono::{DateTime, Duration, Utc};
+use jormungandr_lib::interfaces::{Block0Configuration, Block0ConfigurationError};
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Block0Configuration(#[from] Block0ConfigurationError),
+
+    #[error("block0 does not contain any voteplan")]
+    NoVotePlans,
+
+    #[error("block date {0} cannot be converted into a wall clock time")]
+    InvalidBlockDate(BlockDate),
+
+    #[error("unknown voteplan event '{0}', expected one of: vote_start, vote_end, committee_end")]
+    UnknownEvent(String),
+
+    #[error("invalid offset '{0}', expected a signed amount of minutes, hours or days (e.g -24h)")]
+    InvalidOffset(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VotePlanEvent {
+    VoteStart,
+    VoteEnd,
+    CommitteeEnd,
+}
+
+/// Point in time relative to a voteplan event, parsed from strings like `vote_end-24h`,
+/// `vote_start+30m` or `committee_end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendAt {
+    pub event: VotePlanEvent,
+    pub offset: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VotePlanDates {
+    pub vote_start: DateTime<Utc>,
+    pub vote_end: DateTime<Utc>,
+    pub committee_end: DateTime<Utc>,
+}
+
+/// Wall clock dates of the voteplans in block0, voteplans sharing the same dates are merged
+#[derive(Debug, Clone)]
+pub struct VotePlansSchedule(Vec<VotePlanDates>);
+
+impl VotePlanDates {
+    pub fn event_date(&self, event: VotePlanEvent) -> DateTime<Utc> {
+        match event {
+            VotePlanEvent::VoteStart => self.vote_start,
+            VotePlanEvent::VoteEnd => self.vote_end,
+            VotePlanEvent::CommitteeEnd => self.committee_end,
+        }
+    }
+}
+
+impl VotePlansSchedule {
+    pub fn from_block0(block0: &Block) -> Result<Self, Error> {
+        let block0_configuration = Block0Configuration::from_block(block0)?;
+        let (timeframe, era) = timeframe_and_era_from_block0_configuration(&block0_configuration);
+        let to_datetime = |blockdate: BlockDate| {
+            blockdate_to_system_time(blockdate, &timeframe, &era)
+                .map(DateTime::<Utc>::from)
+                .ok_or(Error::InvalidBlockDate(blockdate))
+        };
+
+        let dates = voteplans_from_block0(block0)
+            .values()
+            .map(|voteplan| {
+                Ok(VotePlanDates {
+                    vote_start: to_datetime(voteplan.vote_start())?,
+                    vote_end: to_datetime(voteplan.vote_end())?,
+                    committee_end: to_datetime(voteplan.committee_end())?,
+                })
+            })
+            .collect::<Result<BTreeSet<_>, Error>>()?;
+
+        if dates.is_empty() {
+            return Err(Error::NoVotePlans);
+        }
+        Ok(Self(dates.into_iter().collect()))
+    }
+
+    pub fn voteplan_dates(&self) -> &[VotePlanDates] {
+        &self.0
+    }
+
+    /// Earliest date of the event among all voteplans
+    pub fn first(&self, event: VotePlanEvent) -> DateTime<Utc> {
+        self.0
+            .iter()
+            .map(|dates| dates.event_date(event))
+            .min()
+            .expect("schedule contains at least one voteplan")
+    }
+
+    /// Distinct send dates, one for each voteplan event date, sorted in time
+    pub fn send_dates(&self, send_at: &SendAt) -> Vec<DateTime<Utc>> {
+        self.0
+            .iter()
+            .map(|dates| dates.event_date(send_at.event) + send_at.offset)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+impl FromStr for VotePlanEvent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "vote_start" => Ok(Self::VoteStart),
+            "vote_end" => Ok(Self::VoteEnd),
+            "committee_end" => Ok(Self::CommitteeEnd),
+            other => Err(Error::UnknownEvent(other.to_string())),
+        }
+    }
+}
+
+impl Display for VotePlanEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VoteStart => write!(f, "vote_start"),
+            Self::VoteEnd => write!(f, "vote_end"),
+            Self::CommitteeEnd => write!(f, "committee_end"),
+        }
+    }
+}
+
+fn parse_offset(offset: &str) -> Result<Duration, Error> {
+    let invalid = || Error::InvalidOffset(offset.to_string());
+    let (sign, rest) = match offset.chars().next() {
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return Err(invalid()),
+    };
+    if rest.len() < 2 {
+        return Err(invalid());
+    }
+    let (amount, unit) = rest.split_at(rest.len() - 1);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(duration * sign)
+}
+
+impl FromStr for SendAt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.find(|c| c == '+' || c == '-') {
+            Some(index) => Ok(Self {
+                event: s[..index].parse()?,
+                offset: parse_offset(&s[index..])?,
+            }),
+            None => Ok(Self {
+                event: s.parse()?,
+                offset: Duration::zero(),
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SendAt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SendAt, VotePlanEvent, VotePlansSchedule};
+    use chain_addr::Discrimination;
+    use chain_impl_mockchain::block::BlockDate;
+    use chrono::{Duration, TimeZone, Utc};
+    use jormungandr_lib::time::SecondsSinceUnixEpoch;
+    use jormungandr_testing_utils::testing::jormungandr::ConfigurationBuilder;
+    use jormungandr_testing_utils::testing::{vote_plan_cert, VotePlanBuilder};
+    use jormungandr_testing_utils::wallet::Wallet as TestWallet;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn schedule_dates_are_utc_wall_clock_times() {
+        // 2021-09-01 12:00:00 UTC
+        let block0_date = 1_630_497_600;
+        let slot_duration = 4;
+        let slots_per_epoch = 10;
+
+        let alice =
+            TestWallet::new_account_with_discrimination(&mut OsRng, Discrimination::Production);
+        let vote_plan = VotePlanBuilder::new().proposals_count(1).public().build();
+        let vote_plan_cert = vote_plan_cert(
+            &alice,
+            BlockDate {
+                epoch: 1,
+                slot_id: 0,
+            },
+            &vote_plan,
+        )
+        .into();
+        let mut block0_configuration = ConfigurationBuilder::new()
+            .with_funds(vec![alice.to_initial_fund(1_000_000)])
+            .with_certs(vec![vote_plan_cert])
+            .with_discrimination(Discrimination::Production)
+            .with_committees(&[&alice])
+            .with_slot_duration(slot_duration)
+            .with_slots_per_epoch(slots_per_epoch)
+            .build_block0();
+        block0_configuration.blockchain_configuration.block0_date =
+            SecondsSinceUnixEpoch::from_secs(block0_date);
+
+        let schedule = VotePlansSchedule::from_block0(&block0_configuration.to_block()).unwrap();
+
+        let expected = |blockdate: BlockDate| {
+            let slots = blockdate.epoch * slots_per_epoch + blockdate.slot_id;
+            Utc.timestamp(
+                block0_date as i64 + (slots * slot_duration as u32) as i64,
+                0,
+            )
+        };
+        assert_eq!(schedule.voteplan_dates().len(), 1);
+        let dates = schedule.voteplan_dates()[0];
+        assert_eq!(dates.vote_start, expected(vote_plan.vote_start()));
+        assert_eq!(dates.vote_end, expected(vote_plan.vote_end()));
+        assert_eq!(dates.committee_end, expected(vote_plan.committee_end()));
+
+        let send_at: SendAt = "vote_end-1m".parse().unwrap();
+        assert_eq!(
+            schedule.send_dates(&send_at),
+            vec![expected(vote_plan.vote_end()) - Duration::minutes(1)]
+        );
+    }
+
+    #[test]
+    fn parse_send_at() {
+        assert_eq!(
+            "vote_end-24h".parse::<SendAt>().unwrap(),
+            SendAt {
+                event: VotePlanEvent::VoteEnd,
+                offset: Duration::hours(-24)
+            }
+        );
+        assert_eq!(
+            "vote_start+30m".parse::<SendAt>().unwrap(),
+            SendAt {
+                event: VotePlanEvent::VoteStart,
+                offset: Duration::minutes(30)
+            }
+        );
+        assert_eq!(
+            "committee_end".parse::<SendAt>().unwrap().offset,
+            Duration::zero()
+        );
+        assert!("vote_end-24".parse::<SendAt>().is_err());
+        assert!("voting_end-1d".parse::<SendAt>().is_err());
+    }
+}
@@ -1,14 +1,63 @@
 use reqwest::{blocking::Client, StatusCode, Url};
+use serde::de::DeserializeOwned;
 
 use crate::notifications::{
-    requests::Request, responses::create_message::CreateMessageResponse, Error,
+    ledger::{request_hash, SentEntry, SentLedger},
+    requests::Request,
+    responses::{
+        create_message::CreateMessageResponse,
+        create_targeted_message::CreateTargetedMessageResponse, MessageCodes,
+    },
+    Error,
 };
 
-pub fn send_create_message(
+use std::time::Duration;
+
+/// Pushwoosh returns this `status_code` in the response body when the request was accepted
+const PUSHWOOSH_OK_STATUS: u64 = 200;
+
+/// Retries with exponential backoff for network and server (5xx) errors
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn no_retries() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::from_millis(0),
+        }
+    }
+
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(attempt)
+    }
+}
+
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::RequestError(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+        Error::ServerError { .. } => true,
+        _ => false,
+    }
+}
+
+fn send_request_once<R: DeserializeOwned>(
+    client: &Client,
     url: Url,
     notification: &Request,
-) -> Result<CreateMessageResponse, Error> {
-    let client = Client::new();
+) -> Result<R, Error> {
     let response = client
         .post(url)
         .body(serde_json::to_string(&notification)?)
@@ -20,12 +69,123 @@ pub fn send_create_message(
                 request: serde_json::to_string_pretty(&notification)?,
             })
         }
+        status if status.is_server_error() => {
+            return Err(Error::ServerError {
+                status,
+                response: response.text()?,
+            })
+        }
         _ => {
             return Err(Error::UnsuccessfulRequest {
                 response: response.text()?,
             })
         }
     };
-    let response_message = response.json()?;
-    Ok(response_message)
+    let response_message: serde_json::Value = response.json()?;
+    // pushwoosh may answer with an http OK but report the actual error in the body
+    match response_message.get("status_code").and_then(|v| v.as_u64()) {
+        Some(PUSHWOOSH_OK_STATUS) => {}
+        status_code => {
+            return Err(Error::RejectedRequest {
+                status_code,
+                status_message: response_message
+                    .get("status_message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        }
+    }
+    serde_json::from_value(response_message).map_err(Into::into)
+}
+
+/// Send a request to the Pushwoosh API, `url` should point to the API method endpoint.
+/// Network and server errors are retried following the `retry_policy`.
+pub fn send_request_with_retries<R: DeserializeOwned>(
+    client: &Client,
+    url: Url,
+    notification: &Request,
+    retry_policy: &RetryPolicy,
+) -> Result<R, Error> {
+    let mut attempt = 0;
+    loop {
+        match send_request_once(client, url.clone(), notification) {
+            Err(e) if attempt < retry_policy.max_retries && is_retryable(&e) => {
+                let backoff = retry_policy.backoff_for(attempt);
+                log::warn!(
+                    "request to {} failed, retrying in {:?}: {}",
+                    url,
+                    backoff,
+                    e
+                );
+                std::thread::sleep(backoff);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Send a request to the Pushwoosh API, `url` should point to the API method endpoint
+pub fn send_request<R: DeserializeOwned>(url: Url, notification: &Request) -> Result<R, Error> {
+    send_request_with_retries(
+        &Client::new(),
+        url,
+        notification,
+        &RetryPolicy::no_retries(),
+    )
+}
+
+pub fn send_create_message(
+    url: Url,
+    notification: &Request,
+) -> Result<CreateMessageResponse, Error> {
+    send_request(url, notification)
+}
+
+pub fn send_create_targeted_message(
+    url: Url,
+    notification: &Request,
+) -> Result<CreateTargetedMessageResponse, Error> {
+    send_request(url, notification)
+}
+
+pub enum SendOutcome<R> {
+    Sent(R),
+    /// The request was found in the ledger, so it was not sent again
+    AlreadySent(SentEntry),
+}
+
+/// Reusable sender, retrying failed requests and recording sent ones in an optional ledger
+pub struct Sender {
+    client: Client,
+    retry_policy: RetryPolicy,
+    ledger: Option<SentLedger>,
+}
+
+impl Sender {
+    pub fn new(retry_policy: RetryPolicy, ledger: Option<SentLedger>) -> Self {
+        Self {
+            client: Client::new(),
+            retry_policy,
+            ledger,
+        }
+    }
+
+    pub fn send<R: DeserializeOwned + MessageCodes>(
+        &mut self,
+        url: Url,
+        notification: &Request,
+    ) -> Result<SendOutcome<R>, Error> {
+        let hash = request_hash(notification)?;
+        if let Some(entry) = self.ledger.as_ref().and_then(|ledger| ledger.find(&hash)) {
+            return Ok(SendOutcome::AlreadySent(entry.clone()));
+        }
+        let response: R =
+            send_request_with_retries(&self.client, url, notification, &self.retry_policy)?;
+        if let Some(ledger) = self.ledger.as_mut() {
+            ledger.record(hash, notification, response.message_codes())?;
+        }
+        Ok(SendOutcome::Sent(response))
+    }
 }
@@ -16,7 +16,10 @@ use chain_impl_mockchain::{
     value::ValueError,
     vote::CommitteeId,
 };
-use chain_time::{Epoch, Slot, SlotDuration, TimeEra, TimeFrame, Timeline};
+use chain_time::{
+    era::{EpochPosition, EpochSlotOffset},
+    Epoch, Slot, SlotDuration, TimeEra, TimeFrame, Timeline,
+};
 use jormungandr_lib::{
     crypto::{account::Identifier, hash::Hash},
     interfaces::{
@@ -79,13 +82,44 @@ fn timeframe_from_block0_start_and_slot_duration(
     )
 }
 
+/// Build the blockchain timeframe and era from the block0 configuration, so block dates
+/// can be converted from and into wall clock times
+pub fn timeframe_and_era_from_block0_configuration(
+    block0_configuration: &Block0Configuration,
+) -> (TimeFrame, TimeEra) {
+    let block0_start = block0_configuration.blockchain_configuration.block0_date;
+    let slot_duration = block0_configuration.blockchain_configuration.slot_duration;
+    let timeframe = timeframe_from_block0_start_and_slot_duration(block0_start, slot_duration);
+    let era = TimeEra::new(
+        Slot::from(0),
+        Epoch(0),
+        block0_configuration
+            .blockchain_configuration
+            .slots_per_epoch
+            .into(),
+    );
+    (timeframe, era)
+}
+
+pub fn blockdate_to_system_time(
+    blockdate: BlockDate,
+    timeframe: &TimeFrame,
+    era: &TimeEra,
+) -> Option<SystemTime> {
+    let slot = era.from_era_to_slot(EpochPosition {
+        epoch: Epoch(blockdate.epoch),
+        slot: EpochSlotOffset(blockdate.slot_id),
+    });
+    timeframe.slot_to_systemtime(slot)
+}
+
 fn committee_id_to_address(id: CommitteeIdDef) -> Address {
     let id = CommitteeId::from(id);
     let pk = id.public_key();
     chain_addr::Address(Discrimination::Production, Kind::Account(pk)).into()
 }
 
-fn voteplans_from_block0(block0: &Block) -> HashMap<VotePlanId, VotePlan> {
+pub fn voteplans_from_block0(block0: &Block) -> HashMap<VotePlanId, VotePlan> {
     block0
         .fragments()
         .filter_map(|fragment| {
@@ -254,17 +288,7 @@ impl<I: Iterator<Item = PersistentFragmentLog>> VoteFragmentFilter<I> {
     pub fn new(block0: Block, range_check: Range<u32>, fragments: I) -> Result<Self, Error> {
         let block0_configuration = Block0Configuration::from_block(&block0)?;
         let fees = block0_configuration.blockchain_configuration.linear_fees;
-        let block0_start = block0_configuration.blockchain_configuration.block0_date;
-        let slot_duration = block0_configuration.blockchain_configuration.slot_duration;
-        let timeframe = timeframe_from_block0_start_and_slot_duration(block0_start, slot_duration);
-        let era = TimeEra::new(
-            Slot::from(0),
-            Epoch(0),
-            block0_configuration
-                .blockchain_configuration
-                .slots_per_epoch
-                .into(),
-        );
+        let (timeframe, era) = timeframe_and_era_from_block0_configuration(&block0_configuration);
         Ok(Self {
             block0: block0.header().hash().into(),
             range_check,
@@ -1,15 +1,21 @@
 mod funding;
 mod lottery;
+pub mod scheme;
+pub mod seed;
+pub mod validation;
 
 use crate::community_advisors::models::{AdvisorReviewRow, ReviewScore};
-use lottery::{CasWinnings, TicketsDistribution};
+use lottery::CasWinnings;
 use rand::{Rng, SeedableRng};
 use rand_chacha::{ChaCha8Rng, ChaChaRng};
+use scheme::{LotteryStage, ProposalTickets, RewardScheme, TicketKind};
+use serde::Serialize;
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 
 pub use crate::rewards::community_advisors::funding::ProposalRewardSlots;
 pub use funding::{FundSetting, Funds};
+pub use lottery::TicketsDistribution;
 
 pub type Seed = <ChaChaRng as SeedableRng>::Seed;
 pub type CommunityAdvisor = String;
@@ -21,63 +27,83 @@ pub type CaRewards = BTreeMap<CommunityAdvisor, Rewards>;
 pub type ProposalsReviews = BTreeMap<ProposalId, Vec<AdvisorReviewRow>>;
 pub type ApprovedProposals = BTreeMap<ProposalId, Funds>;
 
-const LEGACY_MAX_WINNING_TICKETS: u64 = 3;
-
 #[derive(Debug)]
 struct ProposalRewards {
+    id: ProposalId,
     per_ticket_reward: Rewards,
+    bonus_reward: Rewards,
     tickets: ProposalTickets,
 }
 
-#[derive(Debug)]
-enum ProposalTickets {
-    Legacy {
-        eligible_assessors: BTreeSet<CommunityAdvisor>,
-        winning_tkts: u64,
-    },
-    Fund7 {
-        excellent_tkts: TicketsDistribution,
-        good_tkts: TicketsDistribution,
-        excellent_winning_tkts: u64,
-        good_winning_tkts: u64,
-    },
+/// How the rewards of a proposal were drawn, so that anyone with the seed can check the lottery
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProposalLotteryAudit {
+    pub proposal_id: ProposalId,
+    /// reward scheme the proposal tickets were made by
+    pub scheme: &'static str,
+    pub per_ticket_reward: Rewards,
+    /// share of the bonus funds assigned to the proposal, included in the per ticket reward
+    pub bonus_reward: Rewards,
+    /// tickets drawn in each lottery stage, losing tickets of a stage take part in the next one
+    pub winning_tickets_per_stage: Vec<u64>,
+    pub assessors: Vec<AssessorLotteryAudit>,
+}
+
+/// Tickets of an eligible assessor in a proposal lottery, and the ones it won
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssessorLotteryAudit {
+    pub assessor: CommunityAdvisor,
+    pub excellent_tickets: u64,
+    pub good_tickets: u64,
+    pub legacy_tickets: u64,
+    pub won_per_stage: Vec<u64>,
+    pub rewards: Rewards,
+}
+
+impl AssessorLotteryAudit {
+    fn new(assessor: CommunityAdvisor, stages: usize) -> Self {
+        Self {
+            assessor,
+            excellent_tickets: 0,
+            good_tickets: 0,
+            legacy_tickets: 0,
+            won_per_stage: vec![0; stages],
+            rewards: Rewards::ZERO,
+        }
+    }
 }
 
 fn get_tickets_per_proposal(
     proposal_reviews: ProposalsReviews,
     rewards_slots: &ProposalRewardSlots,
+    scheme: &dyn RewardScheme,
 ) -> (u64, BTreeMap<ProposalId, ProposalTickets>) {
-    let (winning_tickets, proposals_tickets): (Vec<_>, _) = proposal_reviews
+    let (base_tickets, proposals_tickets): (Vec<_>, _) = proposal_reviews
         .into_iter()
         .map(|(id, reviews)| {
             let filtered = reviews
                 .into_iter()
-                .filter(|review| !matches!(review.score(), ReviewScore::FilteredOut))
+                .filter(|review| {
+                    !matches!(
+                        review.score(),
+                        ReviewScore::FilteredOut | ReviewScore::Conflicting
+                    )
+                })
                 .collect::<Vec<_>>();
-            let tickets = load_tickets_from_reviews(&filtered, rewards_slots);
-            let winning_tickets = match tickets {
-                ProposalTickets::Legacy { winning_tkts, .. } => {
-                    // it would be a bit harder to track it otherwise, and we don't need this additional
-                    // complexity now
-                    assert_eq!(
-                        0,
-                        rewards_slots.max_winning_tickets() % LEGACY_MAX_WINNING_TICKETS
-                    );
-                    winning_tkts
-                        * (rewards_slots.max_winning_tickets() / LEGACY_MAX_WINNING_TICKETS)
-                }
-                ProposalTickets::Fund7 {
-                    excellent_winning_tkts,
-                    good_winning_tkts,
-                    ..
-                } => excellent_winning_tkts + good_winning_tkts,
-            };
-
-            (winning_tickets, (id, tickets))
+            let tickets = scheme.proposal_tickets(&filtered, rewards_slots);
+            (tickets.base_tickets, (id, tickets))
         })
         .unzip();
 
-    (winning_tickets.into_iter().sum(), proposals_tickets)
+    (base_tickets.into_iter().sum(), proposals_tickets)
+}
+
+/// Split `funds` among `tickets`, nothing is awarded if there are no tickets to win
+fn per_ticket(funds: Funds, tickets: u64) -> Rewards {
+    if tickets == 0 {
+        return Rewards::ZERO;
+    }
+    funds / Rewards::from(tickets)
 }
 
 fn calculate_rewards_per_proposal(
@@ -85,14 +111,15 @@ fn calculate_rewards_per_proposal(
     approved_proposals: &ApprovedProposals,
     funding: &FundSetting,
     rewards_slots: &ProposalRewardSlots,
+    scheme: &dyn RewardScheme,
 ) -> Vec<ProposalRewards> {
     let bonus_funds = funding.bonus_funds();
 
     let total_approved_budget = approved_proposals.values().sum::<Funds>();
     let (total_tickets, proposals_tickets) =
-        get_tickets_per_proposal(proposal_reviews, rewards_slots);
+        get_tickets_per_proposal(proposal_reviews, rewards_slots, scheme);
 
-    let base_ticket_reward = funding.proposal_funds() / Rewards::from(total_tickets);
+    let base_ticket_reward = per_ticket(funding.proposal_funds(), total_tickets);
 
     proposals_tickets
         .into_iter()
@@ -101,138 +128,108 @@ fn calculate_rewards_per_proposal(
                 .get(&id)
                 .map(|budget| bonus_funds * budget / total_approved_budget)
                 .unwrap_or_default();
-            let per_ticket_reward = match tickets {
-                ProposalTickets::Legacy { winning_tkts, .. } => {
-                    base_ticket_reward * Rewards::from(rewards_slots.max_winning_tickets())
-                        / Rewards::from(LEGACY_MAX_WINNING_TICKETS)
-                        + bonus_reward / Rewards::from(winning_tkts)
-                }
-                ProposalTickets::Fund7 {
-                    excellent_winning_tkts,
-                    good_winning_tkts,
-                    ..
-                } => {
-                    base_ticket_reward
-                        + bonus_reward / Rewards::from(excellent_winning_tkts + good_winning_tkts)
-                }
-            };
+            let (numerator, denominator) = tickets.base_reward_ratio;
+            let base_reward =
+                base_ticket_reward * Rewards::from(numerator) / Rewards::from(denominator);
+            let per_ticket_reward =
+                base_reward + per_ticket(bonus_reward, tickets.winning_tickets());
             ProposalRewards {
+                id,
                 tickets,
                 per_ticket_reward,
+                bonus_reward,
             }
         })
         .collect()
 }
 
-fn load_tickets_from_reviews(
-    proposal_reviews: &[AdvisorReviewRow],
-    rewards_slots: &ProposalRewardSlots,
-) -> ProposalTickets {
-    let is_legacy = proposal_reviews
-        .iter()
-        .any(|rev| matches!(rev.score(), ReviewScore::NA));
-
-    if is_legacy {
-        return ProposalTickets::Legacy {
-            eligible_assessors: proposal_reviews
-                .iter()
-                .map(|rev| rev.assessor.clone())
-                .collect(),
-            winning_tkts: std::cmp::min(proposal_reviews.len() as u64, LEGACY_MAX_WINNING_TICKETS),
-        };
-    }
-
-    // assuming only one review per assessor in a single proposal
-    let (excellent_tkts, good_tkts): (TicketsDistribution, TicketsDistribution) =
-        // a full match is used so that we don't forget to consider new review types which may be added in the future
-        proposal_reviews.iter().map(|rev| match rev.score() {
-            ReviewScore::Excellent => (rev.assessor.clone(), rewards_slots.excellent_slots),
-            ReviewScore::Good => (rev.assessor.clone(), rewards_slots.good_slots),
-            _ => unreachable!("we've already filtered out other review scores"),
-        }).partition(|(_ca, tkts)| *tkts == rewards_slots.excellent_slots);
-
-    let excellent_winning_tkts = std::cmp::min(
-        excellent_tkts.len() as u64,
-        rewards_slots.max_excellent_reviews,
-    ) * rewards_slots.excellent_slots;
-    let good_winning_tkts = std::cmp::min(good_tkts.len() as u64, rewards_slots.max_good_reviews)
-        * rewards_slots.good_slots;
-
-    ProposalTickets::Fund7 {
-        excellent_winning_tkts,
-        good_winning_tkts,
-        excellent_tkts,
-        good_tkts,
-    }
-}
-
-// Run a two stage lottery to reward community advisors
+// Run a multi stage lottery to reward community advisors
 //
-// In the first round, only excellent reviews will be taken into consideration
-// In the second, losing tickets from the first round will compete with good review
-fn double_lottery<R: Rng>(
-    stage1: TicketsDistribution,
-    mut stage2: TicketsDistribution,
-    distribute_first_round: u64,
-    distribute_second_round: u64,
-    rng: &mut R,
-) -> CasWinnings {
-    let (mut stage1_winners, stage1_losers) =
-        lottery::lottery_distribution(stage1, distribute_first_round, rng);
-    stage2.extend(stage1_losers);
-    let (stage2_winners, _stage2_losers) =
-        lottery::lottery_distribution(stage2, distribute_second_round, rng);
-    for (ca, winnings) in stage2_winners {
-        *stage1_winners.entry(ca).or_default() += winnings;
-    }
+// Losing tickets of a stage compete with the tickets of the next stage, e.g. with the fund 7
+// scheme only excellent reviews take part in the first stage, then losing tickets from the first
+// stage compete with good reviews
+fn staged_lottery<R: Rng>(stages: Vec<LotteryStage>, rng: &mut R) -> Vec<CasWinnings> {
+    let total_winning_tickets: u64 = stages.iter().map(|stage| stage.winning_tickets).sum();
+    let mut losers = TicketsDistribution::new();
+    let winners: Vec<CasWinnings> = stages
+        .into_iter()
+        .map(|stage| {
+            let mut tickets = stage.tickets;
+            tickets.extend(std::mem::take(&mut losers));
+            let (winners, stage_losers) =
+                lottery::lottery_distribution(tickets, stage.winning_tickets, rng);
+            losers = stage_losers;
+            winners
+        })
+        .collect();
     assert_eq!(
-        stage1_winners.values().sum::<u64>(),
-        distribute_second_round + distribute_first_round
+        winners
+            .iter()
+            .map(|stage| stage.values().sum::<u64>())
+            .sum::<u64>(),
+        total_winning_tickets
     );
-    stage1_winners
+    winners
 }
 
 fn calculate_ca_rewards_for_proposal<R: Rng>(
     proposal_reward: ProposalRewards,
     rng: &mut R,
-) -> CaRewards {
+) -> (CaRewards, ProposalLotteryAudit) {
     let ProposalRewards {
+        id,
         tickets,
         per_ticket_reward,
+        bonus_reward,
     } = proposal_reward;
 
-    let rewards = match tickets {
-        ProposalTickets::Fund7 {
-            excellent_winning_tkts,
-            good_winning_tkts,
-            excellent_tkts,
-            good_tkts,
-        } => double_lottery(
-            excellent_tkts,
-            good_tkts,
-            excellent_winning_tkts,
-            good_winning_tkts,
-            rng,
-        ),
-        ProposalTickets::Legacy {
-            eligible_assessors,
-            winning_tkts,
-        } => {
-            lottery::lottery_distribution(
-                eligible_assessors.into_iter().map(|ca| (ca, 1)).collect(),
-                winning_tkts,
-                rng,
-            )
-            .0
+    let stages_count = tickets.stages.len();
+    let mut assessors: BTreeMap<CommunityAdvisor, AssessorLotteryAudit> = BTreeMap::new();
+    for stage in &tickets.stages {
+        for (ca, n_tickets) in &stage.tickets {
+            let audit = assessors
+                .entry(ca.clone())
+                .or_insert_with(|| AssessorLotteryAudit::new(ca.clone(), stages_count));
+            match stage.kind {
+                TicketKind::Excellent => audit.excellent_tickets += n_tickets,
+                TicketKind::Good => audit.good_tickets += n_tickets,
+                TicketKind::Legacy => audit.legacy_tickets += n_tickets,
+            }
         }
-    };
+    }
 
-    rewards
-        .into_iter()
-        .map(|(ca, tickets_won)| (ca, Rewards::from(tickets_won) * per_ticket_reward))
-        .collect()
+    let winners = staged_lottery(tickets.stages, rng);
+
+    let mut rewards = CaRewards::new();
+    for (stage, stage_winners) in winners.iter().enumerate() {
+        for (ca, tickets_won) in stage_winners {
+            assessors
+                .entry(ca.clone())
+                .or_insert_with(|| AssessorLotteryAudit::new(ca.clone(), stages_count))
+                .won_per_stage[stage] += tickets_won;
+            *rewards.entry(ca.clone()).or_insert(Rewards::ZERO) +=
+                Rewards::from(*tickets_won) * per_ticket_reward;
+        }
+    }
+    for (ca, audit) in assessors.iter_mut() {
+        audit.rewards = rewards.get(ca).copied().unwrap_or_default();
+    }
+
+    let audit = ProposalLotteryAudit {
+        proposal_id: id,
+        scheme: tickets.scheme,
+        per_ticket_reward,
+        bonus_reward,
+        winning_tickets_per_stage: winners
+            .iter()
+            .map(|stage_winners| stage_winners.values().sum())
+            .collect(),
+        assessors: assessors.into_iter().map(|(_, audit)| audit).collect(),
+    };
+    (rewards, audit)
 }
 
+/// Rewards of the community advisors, with the [`Auto`](scheme::Auto) reward scheme
 pub fn calculate_ca_rewards(
     proposal_reviews: ProposalsReviews,
     approved_proposals: &ApprovedProposals,
@@ -240,24 +237,48 @@ pub fn calculate_ca_rewards(
     rewards_slots: &ProposalRewardSlots,
     seed: Seed,
 ) -> CaRewards {
+    calculate_ca_rewards_with_audit(
+        proposal_reviews,
+        approved_proposals,
+        funding,
+        rewards_slots,
+        &scheme::Auto,
+        seed,
+    )
+    .0
+}
+
+/// Rewards of the community advisors with the given reward scheme, along with the lottery
+/// breakdown of every proposal
+pub fn calculate_ca_rewards_with_audit(
+    proposal_reviews: ProposalsReviews,
+    approved_proposals: &ApprovedProposals,
+    funding: &FundSetting,
+    rewards_slots: &ProposalRewardSlots,
+    scheme: &dyn RewardScheme,
+    seed: Seed,
+) -> (CaRewards, Vec<ProposalLotteryAudit>) {
     let proposal_rewards = calculate_rewards_per_proposal(
         proposal_reviews,
         approved_proposals,
         funding,
         rewards_slots,
+        scheme,
     );
     let mut ca_rewards = CaRewards::new();
+    let mut audits = Vec::new();
     let mut rng = ChaCha8Rng::from_seed(seed);
 
     for proposal_reward in proposal_rewards {
-        let rewards = calculate_ca_rewards_for_proposal(proposal_reward, &mut rng);
+        let (rewards, audit) = calculate_ca_rewards_for_proposal(proposal_reward, &mut rng);
 
         for (ca, rewards) in rewards {
             *ca_rewards.entry(ca).or_insert(Rewards::ZERO) += rewards;
         }
+        audits.push(audit);
     }
 
-    ca_rewards
+    (ca_rewards, audits)
 }
 
 #[cfg(test)]
@@ -272,42 +293,6 @@ mod tests {
             .collect()
     }
 
-    #[test]
-    fn test_legacy_mode() {
-        let reviews = gen_dummy_reviews(5, 10, 1);
-        assert!(matches!(
-            load_tickets_from_reviews(&reviews, &ProposalRewardSlots::default()),
-            ProposalTickets::Legacy {
-                winning_tkts: LEGACY_MAX_WINNING_TICKETS,
-                ..
-            }
-        ));
-    }
-
-    macro_rules! check_fund6_winning_tkts {
-        ($excellent:expr, $good:expr, $expected:expr) => {
-            let p = gen_dummy_reviews($excellent, $good, 0);
-            match load_tickets_from_reviews(&p, &Default::default()) {
-                ProposalTickets::Fund7 {
-                    excellent_winning_tkts,
-                    good_winning_tkts,
-                    ..
-                } => assert_eq!(excellent_winning_tkts + good_winning_tkts, $expected),
-                _ => panic!("invalid lottery setup"),
-            }
-        };
-    }
-
-    #[test]
-    fn test_reviews_limits() {
-        // testcases taken from presentation slides
-        check_fund6_winning_tkts!(3, 2, 32);
-        check_fund6_winning_tkts!(5, 5, 36);
-        check_fund6_winning_tkts!(1, 3, 24);
-        check_fund6_winning_tkts!(5, 0, 24);
-        check_fund6_winning_tkts!(0, 3, 12);
-    }
-
     fn are_close(a: Funds, b: Funds) -> bool {
         const DECIMAL_PRECISION: u32 = 10;
         a.round_dp(DECIMAL_PRECISION) == b.round_dp(DECIMAL_PRECISION)
@@ -407,4 +392,207 @@ mod tests {
             Funds::from(120)
         ));
     }
+
+    #[test]
+    fn test_proposal_without_tickets() {
+        let mut proposals = BTreeMap::new();
+        proposals.insert("1".into(), gen_dummy_reviews(1, 1, 0));
+        proposals.insert("2".into(), Vec::new());
+        let res = calculate_ca_rewards(
+            proposals,
+            &vec![("1".into(), Funds::from(1)), ("2".into(), Funds::from(1))]
+                .into_iter()
+                .collect(),
+            &FundSetting {
+                proposal_ratio: 80,
+                bonus_ratio: 20,
+                total: Funds::from(100),
+            },
+            &Default::default(),
+            [0; 32],
+        );
+        // the bonus share of proposal 2 cannot be won
+        assert!(are_close(res.values().sum::<Funds>(), Funds::from(90)));
+    }
+
+    #[test]
+    fn test_conflicting_reviews_are_not_rewarded() {
+        let mut proposals = BTreeMap::new();
+        proposals.insert(
+            "1".into(),
+            named_reviews(&[("a", ReviewScore::Conflicting), ("b", ReviewScore::Good)]),
+        );
+        let res = calculate_ca_rewards(
+            proposals,
+            &vec![("1".into(), Funds::from(1))].into_iter().collect(),
+            &FundSetting {
+                proposal_ratio: 80,
+                bonus_ratio: 20,
+                total: Funds::from(100),
+            },
+            &Default::default(),
+            [0; 32],
+        );
+        assert!(!res.contains_key("a"));
+        assert!(are_close(res["b"], Funds::from(100)));
+    }
+
+    fn named_reviews(scores: &[(&str, ReviewScore)]) -> Vec<AdvisorReviewRow> {
+        scores
+            .iter()
+            .map(|(assessor, score)| {
+                let mut review = AdvisorReviewRow::dummy(*score);
+                review.assessor = assessor.to_string();
+                review
+            })
+            .collect()
+    }
+
+    // Every ticket wins in these proposals, so that rewards do not depend on the seed
+    #[test]
+    fn test_past_funds_regression() {
+        let mut proposals = BTreeMap::new();
+        // fund 6 style proposal, reviews not rated by vCAs
+        proposals.insert(
+            "1".into(),
+            named_reviews(&[("a", ReviewScore::NA), ("b", ReviewScore::NA)]),
+        );
+        // fund 7 style proposal
+        proposals.insert(
+            "2".into(),
+            named_reviews(&[
+                ("c", ReviewScore::Excellent),
+                ("d", ReviewScore::Excellent),
+                ("e", ReviewScore::Good),
+                ("f", ReviewScore::Good),
+                ("g", ReviewScore::Good),
+            ]),
+        );
+        let res = calculate_ca_rewards(
+            proposals,
+            &vec![("1".into(), Funds::from(1)), ("2".into(), Funds::from(2))]
+                .into_iter()
+                .collect(),
+            &FundSetting {
+                proposal_ratio: 80,
+                bonus_ratio: 20,
+                total: Funds::from(600),
+            },
+            &Default::default(),
+            [0; 32],
+        );
+
+        // 60 base tickets worth 8 each, legacy winning tickets are worth 12 base tickets
+        assert!(are_close(res["a"], Funds::from(116)));
+        assert!(are_close(res["b"], Funds::from(116)));
+        assert!(are_close(res["c"], Funds::from(368) / Funds::from(3)));
+        assert!(are_close(res["d"], Funds::from(368) / Funds::from(3)));
+        assert!(are_close(res["e"], Funds::from(368) / Funds::from(9)));
+        assert!(are_close(res["f"], Funds::from(368) / Funds::from(9)));
+        assert!(are_close(res["g"], Funds::from(368) / Funds::from(9)));
+    }
+
+    struct OneTicketPerReview;
+
+    impl RewardScheme for OneTicketPerReview {
+        fn name(&self) -> &'static str {
+            "one-ticket-per-review"
+        }
+
+        fn proposal_tickets(
+            &self,
+            reviews: &[AdvisorReviewRow],
+            _rewards_slots: &ProposalRewardSlots,
+        ) -> ProposalTickets {
+            ProposalTickets {
+                scheme: self.name(),
+                stages: vec![LotteryStage {
+                    kind: TicketKind::Good,
+                    tickets: reviews
+                        .iter()
+                        .map(|rev| (rev.assessor.clone(), 1))
+                        .collect(),
+                    winning_tickets: reviews.len() as u64,
+                }],
+                base_tickets: reviews.len() as u64,
+                base_reward_ratio: (1, 1),
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_scheme() {
+        let mut proposals = BTreeMap::new();
+        proposals.insert(
+            "1".into(),
+            named_reviews(&[("a", ReviewScore::Excellent), ("b", ReviewScore::NA)]),
+        );
+        proposals.insert("2".into(), named_reviews(&[("a", ReviewScore::Good)]));
+        let (res, audits) = calculate_ca_rewards_with_audit(
+            proposals,
+            &ApprovedProposals::new(),
+            &FundSetting {
+                proposal_ratio: 100,
+                bonus_ratio: 0,
+                total: Funds::from(30),
+            },
+            &Default::default(),
+            &OneTicketPerReview,
+            [0; 32],
+        );
+        assert!(are_close(res["a"], Funds::from(20)));
+        assert!(are_close(res["b"], Funds::from(10)));
+        assert!(audits
+            .iter()
+            .all(|audit| audit.scheme == "one-ticket-per-review"));
+    }
+
+    #[test]
+    fn test_lottery_audit() {
+        let mut proposals = BTreeMap::new();
+        let reviews = gen_dummy_reviews(1, 500, 0);
+        let excellent_assessor = reviews[0].assessor.clone();
+        proposals.insert("1".into(), reviews);
+        proposals.insert("2".into(), gen_dummy_reviews(1, 1, 1));
+        let (rewards, audits) = calculate_ca_rewards_with_audit(
+            proposals,
+            &vec![("1".into(), Funds::from(2))].into_iter().collect(),
+            &FundSetting {
+                proposal_ratio: 80,
+                bonus_ratio: 20,
+                total: Funds::from(240),
+            },
+            &Default::default(),
+            &scheme::Auto,
+            [0; 32],
+        );
+
+        assert_eq!(audits.len(), 2);
+        let fund7 = &audits[0];
+        assert_eq!(fund7.scheme, "fund7");
+        assert_eq!(fund7.assessors.len(), 501);
+        assert_eq!(fund7.winning_tickets_per_stage, vec![12, 12]);
+        let excellent = fund7
+            .assessors
+            .iter()
+            .find(|audit| audit.assessor == excellent_assessor)
+            .unwrap();
+        assert_eq!(excellent.excellent_tickets, 12);
+        assert_eq!(excellent.won_per_stage, vec![12, 0]);
+
+        let legacy = &audits[1];
+        assert_eq!(legacy.scheme, "legacy");
+        assert_eq!(legacy.bonus_reward, Funds::ZERO);
+        assert!(legacy
+            .assessors
+            .iter()
+            .all(|audit| audit.legacy_tickets == 1));
+
+        // the audit accounts for all the rewards
+        let audited: Funds = audits
+            .iter()
+            .flat_map(|audit| audit.assessors.iter().map(|assessor| assessor.rewards))
+            .sum();
+        assert!(are_close(audited, rewards.values().sum::<Funds>()));
+    }
 }
@@ -0,0 +1,244 @@
+use super::lottery::TicketsDistribution;
+use super::{CommunityAdvisor, ProposalRewardSlots};
+use crate::community_advisors::models::{AdvisorReviewRow, ReviewScore};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+const LEGACY_MAX_WINNING_TICKETS: u64 = 3;
+
+/// Turns the reviews of a proposal into the lottery rewarding its assessors. Filtered out and
+/// conflicting reviews are removed before reaching the scheme.
+pub trait RewardScheme {
+    fn name(&self) -> &'static str;
+
+    fn proposal_tickets(
+        &self,
+        reviews: &[AdvisorReviewRow],
+        rewards_slots: &ProposalRewardSlots,
+    ) -> ProposalTickets;
+}
+
+/// Kind of review a ticket was earned with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketKind {
+    Excellent,
+    Good,
+    /// review not rated by veteran community advisors
+    Legacy,
+}
+
+/// A lottery draw, losing tickets of a stage take part in the next one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LotteryStage {
+    pub kind: TicketKind,
+    pub tickets: TicketsDistribution,
+    pub winning_tickets: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalTickets {
+    /// name of the scheme the tickets were made by
+    pub scheme: &'static str,
+    pub stages: Vec<LotteryStage>,
+    /// share of the proposal in the base rewards, which are split among the base tickets of
+    /// every proposal
+    pub base_tickets: u64,
+    /// a winning ticket is paid `base_reward_ratio.0 / base_reward_ratio.1` base tickets, plus
+    /// its share of the proposal bonus
+    pub base_reward_ratio: (u64, u64),
+}
+
+impl ProposalTickets {
+    pub fn winning_tickets(&self) -> u64 {
+        self.stages.iter().map(|stage| stage.winning_tickets).sum()
+    }
+}
+
+/// Every reviewer gets a single ticket, and up to 3 of them win
+pub struct Legacy;
+
+/// Excellent and good reviews get a different amount of tickets, drawn in two stages: first
+/// among excellent tickets, then among good tickets and excellent losing tickets
+pub struct Fund7;
+
+/// The scheme used since fund 7, proposals with any review not rated by veteran community
+/// advisors fall back to the legacy scheme
+pub struct Auto;
+
+impl RewardScheme for Legacy {
+    fn name(&self) -> &'static str {
+        "legacy"
+    }
+
+    fn proposal_tickets(
+        &self,
+        reviews: &[AdvisorReviewRow],
+        rewards_slots: &ProposalRewardSlots,
+    ) -> ProposalTickets {
+        // it would be a bit harder to track it otherwise, and we don't need this additional
+        // complexity now
+        assert_eq!(
+            0,
+            rewards_slots.max_winning_tickets() % LEGACY_MAX_WINNING_TICKETS
+        );
+        let eligible_assessors: BTreeSet<CommunityAdvisor> =
+            reviews.iter().map(|rev| rev.assessor.clone()).collect();
+        let winning_tkts = std::cmp::min(reviews.len() as u64, LEGACY_MAX_WINNING_TICKETS);
+
+        ProposalTickets {
+            scheme: self.name(),
+            stages: vec![LotteryStage {
+                kind: TicketKind::Legacy,
+                tickets: eligible_assessors.into_iter().map(|ca| (ca, 1)).collect(),
+                winning_tickets: winning_tkts,
+            }],
+            base_tickets: winning_tkts
+                * (rewards_slots.max_winning_tickets() / LEGACY_MAX_WINNING_TICKETS),
+            base_reward_ratio: (
+                rewards_slots.max_winning_tickets(),
+                LEGACY_MAX_WINNING_TICKETS,
+            ),
+        }
+    }
+}
+
+impl RewardScheme for Fund7 {
+    fn name(&self) -> &'static str {
+        "fund7"
+    }
+
+    fn proposal_tickets(
+        &self,
+        reviews: &[AdvisorReviewRow],
+        rewards_slots: &ProposalRewardSlots,
+    ) -> ProposalTickets {
+        // assuming only one review per assessor in a single proposal
+        let (excellent_tkts, good_tkts): (TicketsDistribution, TicketsDistribution) =
+            // a full match is used so that we don't forget to consider new review types which may be added in the future
+            reviews.iter().filter_map(|rev| match rev.score() {
+                ReviewScore::Excellent => Some((rev.assessor.clone(), rewards_slots.excellent_slots)),
+                ReviewScore::Good => Some((rev.assessor.clone(), rewards_slots.good_slots)),
+                // reviews not rated by vCAs do not earn tickets in this scheme
+                ReviewScore::NA => None,
+                ReviewScore::FilteredOut | ReviewScore::Conflicting => unreachable!("we've already filtered out other review scores"),
+            }).partition(|(_ca, tkts)| *tkts == rewards_slots.excellent_slots);
+
+        let excellent_winning_tkts = std::cmp::min(
+            excellent_tkts.len() as u64,
+            rewards_slots.max_excellent_reviews,
+        ) * rewards_slots.excellent_slots;
+        let good_winning_tkts =
+            std::cmp::min(good_tkts.len() as u64, rewards_slots.max_good_reviews)
+                * rewards_slots.good_slots;
+
+        ProposalTickets {
+            scheme: self.name(),
+            stages: vec![
+                LotteryStage {
+                    kind: TicketKind::Excellent,
+                    tickets: excellent_tkts,
+                    winning_tickets: excellent_winning_tkts,
+                },
+                LotteryStage {
+                    kind: TicketKind::Good,
+                    tickets: good_tkts,
+                    winning_tickets: good_winning_tkts,
+                },
+            ],
+            base_tickets: excellent_winning_tkts + good_winning_tkts,
+            base_reward_ratio: (1, 1),
+        }
+    }
+}
+
+impl RewardScheme for Auto {
+    fn name(&self) -> &'static str {
+        "auto"
+    }
+
+    fn proposal_tickets(
+        &self,
+        reviews: &[AdvisorReviewRow],
+        rewards_slots: &ProposalRewardSlots,
+    ) -> ProposalTickets {
+        let is_legacy = reviews
+            .iter()
+            .any(|rev| matches!(rev.score(), ReviewScore::NA));
+        if is_legacy {
+            Legacy.proposal_tickets(reviews, rewards_slots)
+        } else {
+            Fund7.proposal_tickets(reviews, rewards_slots)
+        }
+    }
+}
+
+/// Names of the available schemes, as accepted by [`scheme_by_name`]
+pub const SCHEMES: &[&str] = &["auto", "legacy", "fund7"];
+
+pub fn scheme_by_name(name: &str) -> Option<Box<dyn RewardScheme>> {
+    match name {
+        "auto" => Some(Box::new(Auto)),
+        "legacy" => Some(Box::new(Legacy)),
+        "fund7" => Some(Box::new(Fund7)),
+        _ => None,
+    }
+}
+
+/// Reward scheme of a fund, with its rewards slots, usually loaded from a config file
+#[derive(Deserialize)]
+pub struct SchemeConfig {
+    pub scheme: String,
+    #[serde(default)]
+    pub slots: ProposalRewardSlots,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_dummy_reviews(n_excellent: u32, n_good: u32, n_na: u32) -> Vec<AdvisorReviewRow> {
+        (0..n_excellent)
+            .map(|_| AdvisorReviewRow::dummy(ReviewScore::Excellent))
+            .chain((0..n_good).map(|_| AdvisorReviewRow::dummy(ReviewScore::Good)))
+            .chain((0..n_na).map(|_| AdvisorReviewRow::dummy(ReviewScore::NA)))
+            .collect()
+    }
+
+    #[test]
+    fn test_legacy_mode() {
+        let reviews = gen_dummy_reviews(5, 10, 1);
+        let tickets = Auto.proposal_tickets(&reviews, &ProposalRewardSlots::default());
+        assert_eq!(tickets.scheme, "legacy");
+        assert_eq!(tickets.winning_tickets(), LEGACY_MAX_WINNING_TICKETS);
+    }
+
+    macro_rules! check_fund6_winning_tkts {
+        ($excellent:expr, $good:expr, $expected:expr) => {
+            let p = gen_dummy_reviews($excellent, $good, 0);
+            let tickets = Auto.proposal_tickets(&p, &Default::default());
+            assert_eq!(tickets.scheme, "fund7");
+            assert_eq!(tickets.winning_tickets(), $expected);
+        };
+    }
+
+    #[test]
+    fn test_reviews_limits() {
+        // testcases taken from presentation slides
+        check_fund6_winning_tkts!(3, 2, 32);
+        check_fund6_winning_tkts!(5, 5, 36);
+        check_fund6_winning_tkts!(1, 3, 24);
+        check_fund6_winning_tkts!(5, 0, 24);
+        check_fund6_winning_tkts!(0, 3, 12);
+    }
+
+    #[test]
+    fn schemes_by_name() {
+        for name in SCHEMES {
+            assert_eq!(scheme_by_name(name).unwrap().name(), *name);
+        }
+        assert!(scheme_by_name("fund42").is_none());
+    }
+}
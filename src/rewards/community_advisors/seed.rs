@@ -0,0 +1,130 @@
+use super::Seed;
+use crate::archive;
+
+use chain_crypto::{digest::DigestOf, hash::Blake2b256};
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Archive(#[from] archive::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("no block at height {0} in the block store")]
+    BlockNotFound(u32),
+
+    #[error("revealed value hashes to {computed}, which does not match commitment {commitment}")]
+    CommitmentMismatch {
+        commitment: String,
+        computed: String,
+    },
+}
+
+/// Where the lottery seed comes from. Every source resolves to a seed string, which is then
+/// hashed into the actual seed, so that the seed can be recomputed from published values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedSource {
+    /// free-form string chosen by the operator
+    String(String),
+    /// hex encoded hash of the block at `height` in a node block store
+    BlockHash { block_store: PathBuf, height: u32 },
+    /// value revealed in a commit-reveal file, checked against its commitment
+    CommitReveal(PathBuf),
+}
+
+/// A value published in advance through its Blake2b256 hash, and revealed later
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CommitReveal {
+    /// hex encoded Blake2b256 hash of `reveal`
+    pub commitment: String,
+    pub reveal: String,
+}
+
+impl CommitReveal {
+    pub fn verify(&self) -> Result<&str, Error> {
+        let computed = Blake2b256::hash(self.reveal.as_bytes()).to_string();
+        if !computed.eq_ignore_ascii_case(self.commitment.trim()) {
+            return Err(Error::CommitmentMismatch {
+                commitment: self.commitment.clone(),
+                computed,
+            });
+        }
+        Ok(&self.reveal)
+    }
+}
+
+impl SeedSource {
+    pub fn seed_string(&self) -> Result<String, Error> {
+        match self {
+            Self::String(seed) => Ok(seed.clone()),
+            Self::BlockHash {
+                block_store,
+                height,
+            } => block_hash_at_height(block_store, *height),
+            Self::CommitReveal(path) => {
+                let commit_reveal: CommitReveal =
+                    serde_json::from_reader(jcli_lib::utils::io::open_file_read(&Some(path))?)?;
+                commit_reveal.verify().map(str::to_string)
+            }
+        }
+    }
+
+    pub fn seed(&self) -> Result<Seed, Error> {
+        self.seed_string().map(|seed| seed_from_string(&seed))
+    }
+}
+
+pub fn seed_from_string(seed: &str) -> Seed {
+    let seed = seed.to_string();
+    Seed::from(DigestOf::digest(&seed))
+}
+
+fn block_hash_at_height(block_store: &Path, height: u32) -> Result<String, Error> {
+    let mut hash = None;
+    archive::for_each_block(block_store, |block| {
+        if u32::from(block.header().chain_length()) == height {
+            hash = Some(block.header().hash().to_string());
+        }
+        Ok(())
+    })?;
+    hash.ok_or(Error::BlockNotFound(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commitment_is_checked() {
+        let reveal = "fund8 lottery".to_string();
+        let mut commit_reveal = CommitReveal {
+            commitment: Blake2b256::hash(reveal.as_bytes()).to_string(),
+            reveal,
+        };
+        assert_eq!(commit_reveal.verify().unwrap(), "fund8 lottery");
+
+        commit_reveal.reveal = "fund8 lottery, second try".to_string();
+        assert!(matches!(
+            commit_reveal.verify(),
+            Err(Error::CommitmentMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn string_seed_is_unchanged() {
+        // blake2b256 of the seed string, as computed before seed sources were added
+        let seed = SeedSource::String("seed".to_string()).seed().unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "04d8436bb843cd0c10e2cdc6c24103b1c3f388311f9d8f0c874cabc27897117c"
+        );
+        assert_ne!(seed, seed_from_string("other seed"));
+    }
+}
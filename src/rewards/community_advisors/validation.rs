@@ -0,0 +1,208 @@
+use super::{ProposalId, ProposalsReviews};
+use crate::community_advisors::models::{AdvisorReviewRow, ReviewScore};
+
+use serde::Serialize;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// How several reviews of the same assessor for the same proposal are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// keep the first review found in the assessments file
+    KeepFirst,
+    /// keep the best rated review, excellent before good before not rated
+    KeepBest,
+    /// drop every review of the assessor for the proposal
+    DropAll,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep-first" => Ok(Self::KeepFirst),
+            "keep-best" => Ok(Self::KeepBest),
+            "drop-all" => Ok(Self::DropAll),
+            _ => Err(format!(
+                "unknown duplicate reviews policy '{}', expected keep-first, keep-best or drop-all",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// review marked both excellent and good, the review is dropped
+    ConflictingScores,
+    /// several reviews of the same assessor, resolved with the duplicate policy
+    DuplicateReview,
+    /// every review of the proposal was filtered out or dropped, the proposal is left out of the
+    /// lottery
+    AllReviewsFilteredOut,
+    /// reviewed proposal not found in the proposals file, its reviews are kept
+    UnknownProposal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReviewIssue {
+    pub kind: IssueKind,
+    pub proposal_id: ProposalId,
+    /// None for issues of the proposal itself
+    pub assessor: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReviewsReport {
+    pub issues: Vec<ReviewIssue>,
+}
+
+impl ReviewsReport {
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues.iter().filter(|i| i.kind == kind).count()
+    }
+}
+
+/// Check the reviews before they get into the rewards lottery, and group them by proposal.
+/// Reviews which cannot be rewarded as they are are dropped or resolved, and reported.
+pub fn validate_reviews(
+    reviews: Vec<AdvisorReviewRow>,
+    known_proposals: &BTreeSet<ProposalId>,
+    duplicate_policy: DuplicatePolicy,
+) -> (ProposalsReviews, ReviewsReport) {
+    let mut issues = Vec::new();
+    let mut grouped: BTreeMap<ProposalId, BTreeMap<String, Vec<AdvisorReviewRow>>> =
+        BTreeMap::new();
+
+    for review in reviews {
+        if review.score() == ReviewScore::Conflicting {
+            issues.push(ReviewIssue {
+                kind: IssueKind::ConflictingScores,
+                proposal_id: review.proposal_id.clone(),
+                assessor: Some(review.assessor.clone()),
+            });
+            continue;
+        }
+        grouped
+            .entry(review.proposal_id.clone())
+            .or_default()
+            .entry(review.assessor.clone())
+            .or_default()
+            .push(review);
+    }
+
+    let mut proposals_reviews = ProposalsReviews::new();
+    for (proposal_id, assessors) in grouped {
+        if !known_proposals.contains(&proposal_id) {
+            issues.push(ReviewIssue {
+                kind: IssueKind::UnknownProposal,
+                proposal_id: proposal_id.clone(),
+                assessor: None,
+            });
+        }
+
+        let mut reviews = Vec::new();
+        for (assessor, mut assessor_reviews) in assessors {
+            if assessor_reviews.len() > 1 {
+                issues.push(ReviewIssue {
+                    kind: IssueKind::DuplicateReview,
+                    proposal_id: proposal_id.clone(),
+                    assessor: Some(assessor),
+                });
+                match duplicate_policy {
+                    DuplicatePolicy::KeepFirst => assessor_reviews.truncate(1),
+                    DuplicatePolicy::KeepBest => {
+                        let best = (0..assessor_reviews.len())
+                            .min_by_key(|i| score_rank(assessor_reviews[*i].score()))
+                            .unwrap();
+                        assessor_reviews = vec![assessor_reviews.swap_remove(best)];
+                    }
+                    DuplicatePolicy::DropAll => assessor_reviews.clear(),
+                }
+            }
+            reviews.extend(assessor_reviews);
+        }
+
+        if reviews
+            .iter()
+            .all(|review| review.score() == ReviewScore::FilteredOut)
+        {
+            issues.push(ReviewIssue {
+                kind: IssueKind::AllReviewsFilteredOut,
+                proposal_id,
+                assessor: None,
+            });
+            continue;
+        }
+        proposals_reviews.insert(proposal_id, reviews);
+    }
+
+    (proposals_reviews, ReviewsReport { issues })
+}
+
+fn score_rank(score: ReviewScore) -> u8 {
+    match score {
+        ReviewScore::Excellent => 0,
+        ReviewScore::Good => 1,
+        ReviewScore::NA => 2,
+        ReviewScore::FilteredOut => 3,
+        ReviewScore::Conflicting => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(proposal_id: &str, assessor: &str, score: ReviewScore) -> AdvisorReviewRow {
+        let mut review = AdvisorReviewRow::dummy(score);
+        review.proposal_id = proposal_id.to_string();
+        review.assessor = assessor.to_string();
+        review
+    }
+
+    #[test]
+    fn malformed_reviews_are_reported() {
+        let mut conflicting = review("1", "carol", ReviewScore::Excellent);
+        conflicting.set_good(true);
+        let reviews = vec![
+            review("1", "alice", ReviewScore::Good),
+            review("1", "alice", ReviewScore::Excellent),
+            review("1", "bob", ReviewScore::Good),
+            conflicting,
+            review("2", "alice", ReviewScore::FilteredOut),
+            review("3", "bob", ReviewScore::Good),
+        ];
+        let known: BTreeSet<ProposalId> =
+            vec!["1".to_string(), "2".to_string()].into_iter().collect();
+
+        let (proposals, report) =
+            validate_reviews(reviews.clone(), &known, DuplicatePolicy::KeepBest);
+        assert_eq!(report.count(IssueKind::ConflictingScores), 1);
+        assert_eq!(report.count(IssueKind::DuplicateReview), 1);
+        assert_eq!(report.count(IssueKind::AllReviewsFilteredOut), 1);
+        assert_eq!(report.count(IssueKind::UnknownProposal), 1);
+        assert!(!proposals.contains_key("2"));
+        assert!(proposals.contains_key("3"));
+        let alice = proposals["1"]
+            .iter()
+            .find(|review| review.assessor == "alice")
+            .unwrap();
+        assert_eq!(alice.score(), ReviewScore::Excellent);
+
+        let (proposals, _) = validate_reviews(reviews.clone(), &known, DuplicatePolicy::KeepFirst);
+        assert_eq!(proposals["1"].len(), 2);
+        assert_eq!(proposals["1"][0].score(), ReviewScore::Good);
+
+        let (proposals, _) = validate_reviews(reviews, &known, DuplicatePolicy::DropAll);
+        assert_eq!(proposals["1"].len(), 1);
+        assert_eq!(proposals["1"][0].assessor, "bob");
+    }
+}
@@ -1,3 +1,6 @@
 pub mod community_advisors;
+pub mod payout;
+pub mod registrations;
 pub mod veterans;
+pub mod vote_count;
 pub mod voters;
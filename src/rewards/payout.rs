@@ -0,0 +1,471 @@
+use crate::rewards::voters::ADA_TO_LOVELACE_FACTOR;
+
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Serialized size of a transaction without outputs, inputs and witnesses included
+const TX_BASE_SIZE: usize = 300;
+/// Serialized size of an output without its address
+const OUTPUT_BASE_SIZE: usize = 12;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error("line {line}: expected at least {expected} columns")]
+    MissingColumn { line: usize, expected: usize },
+
+    #[error("line {line}: invalid amount '{amount}'")]
+    InvalidAmount { line: usize, amount: String },
+
+    #[error("amount {0} is not a valid lovelace amount")]
+    AmountOutOfRange(Decimal),
+
+    #[error("no address found in the mapping for {0:?}")]
+    MissingAddresses(Vec<String>),
+
+    #[error(
+        "a single output of {size} bytes does not fit in a transaction of {max_tx_size} bytes"
+    )]
+    OutputTooLarge { size: usize, max_tx_size: usize },
+
+    #[error("line {line}: ADA amount {ada} does not match lovelace amount {lovelace}")]
+    InconsistentAmounts {
+        line: usize,
+        ada: String,
+        lovelace: String,
+    },
+
+    #[error(
+        "rewards file total of {total} lovelace does not match the expected {expected} lovelace"
+    )]
+    Unreconciled { total: Decimal, expected: u64 },
+}
+
+/// Rewards files written by the `rewards` commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardsFormat {
+    /// `rewards voters` output, paid to the voter (or reward) address, amounts in lovelace
+    Voters,
+    /// `rewards community-advisors` output, `id,rewards` with amounts in ADA
+    CommunityAdvisors,
+    /// `rewards veterans` output, `id,rewards` with amounts in ADA. It has no header line, as
+    /// the rewards are written as tuples, which the csv writer cannot name columns for.
+    Veterans,
+}
+
+impl FromStr for RewardsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "voters" => Ok(Self::Voters),
+            "community-advisors" => Ok(Self::CommunityAdvisors),
+            "veterans" => Ok(Self::Veterans),
+            _ => Err(format!(
+                "unknown rewards format '{}', expected voters, community-advisors or veterans",
+                s
+            )),
+        }
+    }
+}
+
+impl RewardsFormat {
+    fn has_headers(self) -> bool {
+        !matches!(self, Self::Veterans)
+    }
+
+    /// Column of the amount, and how many lovelace one unit of that column is worth
+    fn amount_column(self) -> (usize, u64) {
+        match self {
+            // address, stake, reward in ADA, reward in lovelace, excluded by rule
+            Self::Voters => (3, 1),
+            Self::CommunityAdvisors | Self::Veterans => (1, ADA_TO_LOVELACE_FACTOR),
+        }
+    }
+}
+
+/// A reward owed to a recipient, in lovelace
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardEntry {
+    pub id: String,
+    pub amount: Decimal,
+}
+
+/// Load the rewards of a rewards file, amounts are converted to lovelace but not rounded. The ADA
+/// and lovelace columns of voters rewards files are checked against each other.
+pub fn load_rewards(path: &Path, format: RewardsFormat) -> Result<Vec<RewardEntry>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(format.has_headers())
+        .from_path(path)?;
+    let (amount_column, unit) = format.amount_column();
+    let first_line = if format.has_headers() { 2 } else { 1 };
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record?;
+            let line = i + first_line;
+            let (id, amount) = match (record.get(0), record.get(amount_column)) {
+                (Some(id), Some(amount)) => (id, amount),
+                _ => {
+                    return Err(Error::MissingColumn {
+                        line,
+                        expected: amount_column + 1,
+                    })
+                }
+            };
+            let parse = |amount: &str| {
+                Decimal::from_str(amount.trim()).map_err(|_| Error::InvalidAmount {
+                    line,
+                    amount: amount.to_string(),
+                })
+            };
+            let amount = parse(amount)?;
+            if format == RewardsFormat::Voters {
+                let ada = record.get(amount_column - 1).unwrap_or_default();
+                if parse(ada)? * Decimal::from(ADA_TO_LOVELACE_FACTOR) != amount {
+                    return Err(Error::InconsistentAmounts {
+                        line,
+                        ada: ada.to_string(),
+                        lovelace: amount.to_string(),
+                    });
+                }
+            }
+            Ok(RewardEntry {
+                id: id.to_string(),
+                amount: amount * Decimal::from(unit),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutSettings {
+    pub max_outputs: usize,
+    /// maximum estimated serialized size of a transaction, in bytes
+    pub max_tx_size: usize,
+    /// outputs below this amount cannot be paid and are left out of the plan
+    pub min_utxo: u64,
+    /// outputs below this amount are paid but flagged
+    pub dust_threshold: u64,
+    /// lovelace the rewards file should add up to, usually the total rewards given to the
+    /// rewards command. Lovelace fractions are rounded to the nearest lovelace.
+    pub expected_total: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedOutput {
+    pub address: String,
+    pub amount: u64,
+    /// ids of the rewards paid by this output
+    pub reward_ids: Vec<String>,
+    pub dust: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedTransaction {
+    pub index: usize,
+    pub outputs: Vec<PlannedOutput>,
+    pub total: u64,
+    pub estimated_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PayoutTotals {
+    /// sum of the rewards file amounts, rounded down to the lovelace
+    pub rewards: u64,
+    /// lovelace fractions lost while rounding down each reward
+    pub rounding_loss: Decimal,
+    pub planned: u64,
+    pub below_min_utxo: u64,
+    pub dust: u64,
+}
+
+/// Payment plan meant to be consumed by a signing tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PaymentPlan {
+    pub transactions: Vec<PlannedTransaction>,
+    /// outputs which could not be paid as they are below the minimum UTxO value
+    pub below_min_utxo: Vec<PlannedOutput>,
+    pub totals: PayoutTotals,
+}
+
+/// Group rewards into transactions paying their recipients. Rewards are mapped to payment
+/// addresses through `addresses` when given, otherwise reward ids are used as addresses.
+/// Rewards paid to the same address are merged into a single output.
+pub fn payment_plan(
+    rewards: &[RewardEntry],
+    addresses: Option<&HashMap<String, String>>,
+    settings: &PayoutSettings,
+) -> Result<PaymentPlan, Error> {
+    let mut rounding_loss = Decimal::ZERO;
+    let mut rewards_total: u64 = 0;
+    let mut missing = Vec::new();
+    let mut outputs: BTreeMap<String, PlannedOutput> = BTreeMap::new();
+    for reward in rewards {
+        let address = match addresses {
+            Some(addresses) => match addresses.get(&reward.id) {
+                Some(address) => address.clone(),
+                None => {
+                    missing.push(reward.id.clone());
+                    continue;
+                }
+            },
+            None => reward.id.clone(),
+        };
+        let amount = reward
+            .amount
+            .round_dp_with_strategy(0, RoundingStrategy::ToZero);
+        rounding_loss += reward.amount - amount;
+        let amount = amount
+            .to_u64()
+            .ok_or_else(|| Error::AmountOutOfRange(reward.amount))?;
+        rewards_total += amount;
+
+        let output = outputs
+            .entry(address.clone())
+            .or_insert_with(|| PlannedOutput {
+                address,
+                amount: 0,
+                reward_ids: Vec::new(),
+                dust: false,
+            });
+        output.amount += amount;
+        output.reward_ids.push(reward.id.clone());
+    }
+    if !missing.is_empty() {
+        return Err(Error::MissingAddresses(missing));
+    }
+    if let Some(expected) = settings.expected_total {
+        let total = Decimal::from(rewards_total) + rounding_loss;
+        if total.round() != Decimal::from(expected) {
+            return Err(Error::Unreconciled { total, expected });
+        }
+    }
+
+    let mut transactions: Vec<PlannedTransaction> = Vec::new();
+    let mut below_min_utxo = Vec::new();
+    let mut dust = 0;
+    for (_, mut output) in outputs {
+        if output.amount == 0 {
+            continue;
+        }
+        if output.amount < settings.min_utxo {
+            below_min_utxo.push(output);
+            continue;
+        }
+        if output.amount < settings.dust_threshold {
+            output.dust = true;
+            dust += output.amount;
+        }
+
+        let size = output_size(&output.address);
+        if TX_BASE_SIZE + size > settings.max_tx_size {
+            return Err(Error::OutputTooLarge {
+                size,
+                max_tx_size: settings.max_tx_size,
+            });
+        }
+        let fits = transactions.last().map_or(false, |tx| {
+            tx.outputs.len() < settings.max_outputs
+                && tx.estimated_size + size <= settings.max_tx_size
+        });
+        if !fits {
+            transactions.push(PlannedTransaction {
+                index: transactions.len(),
+                outputs: Vec::new(),
+                total: 0,
+                estimated_size: TX_BASE_SIZE,
+            });
+        }
+        let tx = transactions.last_mut().unwrap();
+        tx.total += output.amount;
+        tx.estimated_size += size;
+        tx.outputs.push(output);
+    }
+
+    let planned: u64 = transactions.iter().map(|tx| tx.total).sum();
+    let below_min_utxo_total: u64 = below_min_utxo.iter().map(|output| output.amount).sum();
+
+    Ok(PaymentPlan {
+        transactions,
+        below_min_utxo,
+        totals: PayoutTotals {
+            rewards: rewards_total,
+            rounding_loss,
+            planned,
+            below_min_utxo: below_min_utxo_total,
+            dust,
+        },
+    })
+}
+
+/// Estimated serialized size of an output, bech32 addresses are counted by their decoded size
+fn output_size(address: &str) -> usize {
+    let address_size = bech32::decode(address)
+        .map(|(_, data, _)| data.len() * 5 / 8)
+        .unwrap_or_else(|_| address.len());
+    OUTPUT_BASE_SIZE + address_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn reward(id: &str, amount: u64) -> RewardEntry {
+        RewardEntry {
+            id: id.to_string(),
+            amount: Decimal::from(amount),
+        }
+    }
+
+    fn settings(max_outputs: usize) -> PayoutSettings {
+        PayoutSettings {
+            max_outputs,
+            max_tx_size: 16384,
+            min_utxo: 1_000_000,
+            dust_threshold: 2_000_000,
+            expected_total: None,
+        }
+    }
+
+    fn rewards_file(content: &str) -> assert_fs::NamedTempFile {
+        let file = assert_fs::NamedTempFile::new("rewards.csv").unwrap();
+        file.write_str(content).unwrap();
+        file
+    }
+
+    #[test]
+    fn load_voters_rewards() {
+        let file = rewards_file(
+            "Address,Stake of the voter (ADA),Reward for the voter (ADA),Reward for the voter (lovelace),Excluded by rule\n\
+             addr_a,1000,1.5,1500000,\n\
+             addr_b,10,0,0,min_votes(2)\n",
+        );
+        assert_eq!(
+            load_rewards(file.path(), RewardsFormat::Voters).unwrap(),
+            vec![reward("addr_a", 1_500_000), reward("addr_b", 0)]
+        );
+
+        let file = rewards_file(
+            "Address,Stake of the voter (ADA),Reward for the voter (ADA),Reward for the voter (lovelace),Excluded by rule\n\
+             addr_a,1000,1.5,1500001,\n",
+        );
+        assert!(matches!(
+            load_rewards(file.path(), RewardsFormat::Voters),
+            Err(Error::InconsistentAmounts { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn load_community_advisors_rewards() {
+        let file = rewards_file("id,rewards\nca_a,12.3456789\nca_b,1\n");
+        assert_eq!(
+            load_rewards(file.path(), RewardsFormat::CommunityAdvisors).unwrap(),
+            vec![
+                RewardEntry {
+                    id: "ca_a".to_string(),
+                    amount: Decimal::new(123_456_789, 1),
+                },
+                reward("ca_b", 1_000_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_veterans_rewards() {
+        let file = rewards_file("vca_a,50\nvca_b,not a number\n");
+        assert!(matches!(
+            load_rewards(file.path(), RewardsFormat::Veterans),
+            Err(Error::InvalidAmount { line: 2, .. })
+        ));
+
+        let file = rewards_file("vca_a,50\nvca_b,0.5\n");
+        assert_eq!(
+            load_rewards(file.path(), RewardsFormat::Veterans).unwrap(),
+            vec![reward("vca_a", 50_000_000), reward("vca_b", 500_000)]
+        );
+    }
+
+    #[test]
+    fn rewards_are_reconciled_with_the_expected_total() {
+        let rewards = vec![
+            reward("a", 5_000_000),
+            RewardEntry {
+                id: "b".to_string(),
+                amount: Decimal::new(49_999_995, 1),
+            },
+        ];
+        let mut settings = settings(10);
+        settings.expected_total = Some(10_000_000);
+        let plan = payment_plan(&rewards, None, &settings).unwrap();
+        assert_eq!(plan.totals.rewards, 9_999_999);
+
+        settings.expected_total = Some(11_000_000);
+        assert!(matches!(
+            payment_plan(&rewards, None, &settings),
+            Err(Error::Unreconciled {
+                expected: 11_000_000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn outputs_are_batched_and_reconciled() {
+        let rewards = vec![
+            reward("a", 5_000_000),
+            reward("b", 1_500_000),
+            reward("c", 999_999),
+            reward("d", 3_000_000),
+            reward("a2", 1_000_000),
+            RewardEntry {
+                id: "e".to_string(),
+                amount: Decimal::new(40_000_005, 1),
+            },
+        ];
+        let addresses: HashMap<String, String> = vec![
+            ("a", "addr_a"),
+            ("a2", "addr_a"),
+            ("b", "addr_b"),
+            ("c", "addr_c"),
+            ("d", "addr_d"),
+            ("e", "addr_e"),
+        ]
+        .into_iter()
+        .map(|(id, address)| (id.to_string(), address.to_string()))
+        .collect();
+
+        let plan = payment_plan(&rewards, Some(&addresses), &settings(2)).unwrap();
+        assert_eq!(plan.transactions.len(), 2);
+        assert!(plan.transactions.iter().all(|tx| tx.outputs.len() <= 2));
+        assert_eq!(plan.transactions[0].outputs[0].amount, 6_000_000);
+        assert_eq!(plan.transactions[0].outputs[0].reward_ids, vec!["a", "a2"]);
+        assert!(plan.transactions[0].outputs[1].dust);
+        assert_eq!(plan.below_min_utxo.len(), 1);
+        assert_eq!(plan.below_min_utxo[0].address, "addr_c");
+        assert_eq!(plan.totals.rewards, 15_499_999);
+        assert_eq!(plan.totals.planned, 14_500_000);
+        assert_eq!(plan.totals.dust, 1_500_000);
+        assert_eq!(plan.totals.rounding_loss, Decimal::new(5, 1));
+    }
+
+    #[test]
+    fn missing_addresses_are_reported() {
+        let rewards = vec![reward("a", 5_000_000), reward("b", 5_000_000)];
+        let addresses: HashMap<String, String> = vec![("a".to_string(), "addr_a".to_string())]
+            .into_iter()
+            .collect();
+        assert!(matches!(
+            payment_plan(&rewards, Some(&addresses), &settings(10)),
+            Err(Error::MissingAddresses(missing)) if missing == vec!["b".to_string()]
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::rewards::voters::{account_hex_to_address, Eligibility, ParticipationRule, Rewards};
+
+use chain_addr::Discrimination;
+use jormungandr_lib::interfaces::Address;
+use serde::Deserialize;
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid voting key '{0}', expected a hex encoded account public key")]
+    InvalidVotingKey(String),
+}
+
+/// A delegation of voting power from a stake key to a voting key, as found in a registrations
+/// snapshot. A stake key delegating to several voting keys appears once per voting key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Registration {
+    pub stake_public_key: String,
+    /// hex encoded voting key, the account voting on chain
+    pub voting_key: String,
+    /// voting power (in lovelace) delegated to the voting key
+    pub weight: u64,
+    /// address rewards for this delegation are paid to
+    pub reward_address: String,
+}
+
+/// Reward share of a reward address
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RewardAddressShare {
+    pub share: Rewards,
+    /// rules which excluded voting keys this address delegated to, the delegations to those
+    /// voting keys are not rewarded
+    pub excluded_by: Vec<ParticipationRule>,
+}
+
+/// Voting power of every voting key, as the sum of the weights delegated to it. Voting keys in
+/// `committee_keys` are left out.
+pub fn stake_per_voting_key(
+    registrations: &[Registration],
+    committee_keys: &HashSet<Address>,
+    discrimination: Discrimination,
+) -> Result<HashMap<Address, u64>, Error> {
+    let mut stake_per_voting_key: HashMap<Address, u64> = HashMap::new();
+    for registration in registrations {
+        let address = voting_key_address(&registration.voting_key, discrimination)?;
+        if !committee_keys.contains(&address) {
+            *stake_per_voting_key.entry(address).or_default() += registration.weight;
+        }
+    }
+    Ok(stake_per_voting_key)
+}
+
+/// Split the reward share of every voting key back to the reward addresses of the delegations it
+/// received, proportionally to their weight. Shares of the same reward address are added up, and
+/// the exclusions of the voting keys it delegated to are kept along.
+pub fn reward_share_per_reward_address(
+    share_per_voting_key: &HashMap<&Address, (Rewards, Eligibility)>,
+    registrations: &[Registration],
+    discrimination: Discrimination,
+) -> Result<HashMap<String, RewardAddressShare>, Error> {
+    let mut delegations: HashMap<Address, Vec<&Registration>> = HashMap::new();
+    for registration in registrations {
+        let address = voting_key_address(&registration.voting_key, discrimination)?;
+        delegations.entry(address).or_default().push(registration);
+    }
+
+    let mut shares: HashMap<String, RewardAddressShare> = HashMap::new();
+    for (voting_key, (share, eligibility)) in share_per_voting_key {
+        let delegations = match delegations.get(*voting_key) {
+            Some(delegations) => delegations,
+            None => continue,
+        };
+        let total_weight: u64 = delegations.iter().map(|r| r.weight).sum();
+        if total_weight == 0 {
+            continue;
+        }
+        for registration in delegations {
+            let reward_address_share = shares
+                .entry(registration.reward_address.clone())
+                .or_default();
+            reward_address_share.share +=
+                *share * Rewards::from(registration.weight) / Rewards::from(total_weight);
+            if let Eligibility::Excluded(rule) = eligibility {
+                if !reward_address_share.excluded_by.contains(rule) {
+                    reward_address_share.excluded_by.push(rule.clone());
+                }
+            }
+        }
+    }
+    for share in shares.values_mut() {
+        share.excluded_by.sort_by_key(ToString::to_string);
+    }
+    Ok(shares)
+}
+
+fn voting_key_address(voting_key: &str, discrimination: Discrimination) -> Result<Address, Error> {
+    account_hex_to_address(
+        voting_key.trim_start_matches("0x").to_string(),
+        discrimination,
+    )
+    .map_err(|_| Error::InvalidVotingKey(voting_key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(stake: &str, voting_key: u8, weight: u64, reward: &str) -> Registration {
+        Registration {
+            stake_public_key: stake.to_string(),
+            voting_key: hex::encode([voting_key; 32]),
+            weight,
+            reward_address: reward.to_string(),
+        }
+    }
+
+    #[test]
+    fn rewards_are_split_back_to_reward_addresses() {
+        let registrations = vec![
+            registration("stake_a", 1, 100, "reward_a"),
+            registration("stake_b", 1, 300, "reward_b"),
+            // stake_a splits its voting power between two voting keys
+            registration("stake_a", 2, 400, "reward_a"),
+        ];
+        let discrimination = Discrimination::Production;
+
+        let stakes = stake_per_voting_key(&registrations, &HashSet::new(), discrimination).unwrap();
+        assert_eq!(stakes.len(), 2);
+        let total: u64 = stakes.values().sum();
+        let share_per_voting_key: HashMap<&Address, (Rewards, Eligibility)> = stakes
+            .iter()
+            .map(|(address, stake)| {
+                (
+                    address,
+                    (
+                        Rewards::from(*stake) / Rewards::from(total),
+                        Eligibility::Eligible,
+                    ),
+                )
+            })
+            .collect();
+
+        let shares =
+            reward_share_per_reward_address(&share_per_voting_key, &registrations, discrimination)
+                .unwrap();
+        assert_eq!(shares["reward_a"].share.round_dp(20), Rewards::new(625, 3));
+        assert_eq!(shares["reward_b"].share.round_dp(20), Rewards::new(375, 3));
+        assert!(shares.values().all(|share| share.excluded_by.is_empty()));
+    }
+
+    #[test]
+    fn exclusions_are_kept_per_reward_address() {
+        let registrations = vec![
+            registration("stake_a", 1, 100, "reward_a"),
+            registration("stake_b", 1, 300, "reward_b"),
+            registration("stake_a", 2, 400, "reward_a"),
+        ];
+        let discrimination = Discrimination::Production;
+        let rule = ParticipationRule::MinVotes { votes: 2 };
+        let excluded = voting_key_address(&hex::encode([1u8; 32]), discrimination).unwrap();
+        let eligible = voting_key_address(&hex::encode([2u8; 32]), discrimination).unwrap();
+        let share_per_voting_key: HashMap<&Address, (Rewards, Eligibility)> = vec![
+            (
+                &excluded,
+                (Rewards::ZERO, Eligibility::Excluded(rule.clone())),
+            ),
+            (&eligible, (Rewards::ONE, Eligibility::Eligible)),
+        ]
+        .into_iter()
+        .collect();
+
+        let shares =
+            reward_share_per_reward_address(&share_per_voting_key, &registrations, discrimination)
+                .unwrap();
+        assert_eq!(
+            shares["reward_a"],
+            RewardAddressShare {
+                share: Rewards::ONE,
+                excluded_by: vec![rule.clone()],
+            }
+        );
+        assert_eq!(
+            shares["reward_b"],
+            RewardAddressShare {
+                share: Rewards::ZERO,
+                excluded_by: vec![rule],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_voting_key() {
+        let mut registration = registration("stake_a", 1, 100, "reward_a");
+        registration.voting_key = "not hex".to_string();
+        assert!(matches!(
+            stake_per_voting_key(&[registration], &HashSet::new(), Discrimination::Production),
+            Err(Error::InvalidVotingKey(_))
+        ));
+    }
+}
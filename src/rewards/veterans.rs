@@ -1,10 +1,23 @@
+use crate::community_advisors::models::ReviewScore;
+use crate::vca_reviews::VcaReviewedRow;
+
 use serde::Deserialize;
 
+use std::collections::{BTreeMap, BTreeSet};
+
 pub type VeteranAdvisorId = String;
 pub type VeteranAdvisorReward = rust_decimal::Decimal;
 
 pub type VeteranAdvisorRewards = Vec<(VeteranAdvisorId, VeteranAdvisorReward)>;
 
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(
+        "none of the veterans of the reviews count file were found in the vca aggregated file"
+    )]
+    NoVeteranFound,
+}
+
 #[derive(Deserialize)]
 pub struct VeteranReviewsCount {
     name: VeteranAdvisorId,
@@ -12,21 +25,324 @@ pub struct VeteranReviewsCount {
     number_of_reviews: usize,
 }
 
+/// Reviews of a veteran advisor, as counted for rewards
+#[derive(Debug, Clone, PartialEq)]
+pub struct VeteranReviews {
+    pub name: VeteranAdvisorId,
+    pub number_of_reviews: usize,
+    /// reviews weighted by their outcome
+    pub weighted_reviews: VeteranAdvisorReward,
+}
+
+/// Rewards rules for veteran advisors, usually loaded from a config file
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VeteranRewardsConfig {
+    /// rewards multipliers by amount of reviews, veterans below the lowest tier are not
+    /// rewarded. Without tiers every veteran gets a multiplier of 1.
+    #[serde(default)]
+    pub tiers: Vec<RewardTier>,
+    /// rewards cap of a single veteran, the excess is redistributed among the other veterans
+    #[serde(default)]
+    pub max_rewards: Option<VeteranAdvisorReward>,
+    /// weight of reviews by outcome, only available when reviews are counted from the vca
+    /// aggregated file
+    #[serde(default)]
+    pub outcome_weights: OutcomeWeights,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RewardTier {
+    pub min_reviews: usize,
+    pub multiplier: VeteranAdvisorReward,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct OutcomeWeights {
+    pub excellent: VeteranAdvisorReward,
+    pub good: VeteranAdvisorReward,
+    /// reviewed assessments which ended up not rated
+    pub not_rated: VeteranAdvisorReward,
+    pub filtered_out: VeteranAdvisorReward,
+}
+
+impl Default for OutcomeWeights {
+    fn default() -> Self {
+        Self {
+            excellent: VeteranAdvisorReward::ONE,
+            good: VeteranAdvisorReward::ONE,
+            not_rated: VeteranAdvisorReward::ONE,
+            filtered_out: VeteranAdvisorReward::ONE,
+        }
+    }
+}
+
+impl OutcomeWeights {
+    /// None for malformed reviews, which are not counted at all
+    fn weight(&self, score: ReviewScore) -> Option<VeteranAdvisorReward> {
+        match score {
+            ReviewScore::Excellent => Some(self.excellent),
+            ReviewScore::Good => Some(self.good),
+            ReviewScore::NA => Some(self.not_rated),
+            ReviewScore::FilteredOut => Some(self.filtered_out),
+            ReviewScore::Conflicting => None,
+        }
+    }
+}
+
+impl VeteranRewardsConfig {
+    fn multiplier(&self, number_of_reviews: usize) -> VeteranAdvisorReward {
+        if self.tiers.is_empty() {
+            return VeteranAdvisorReward::ONE;
+        }
+        self.tiers
+            .iter()
+            .filter(|tier| number_of_reviews >= tier.min_reviews)
+            .max_by_key(|tier| tier.min_reviews)
+            .map_or(VeteranAdvisorReward::ZERO, |tier| tier.multiplier)
+    }
+}
+
+/// Amount of reviews of a veteran advisor entered in the reviews count file which differs from
+/// the amount found in the vca aggregated file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountMismatch {
+    pub name: VeteranAdvisorId,
+    pub entered: usize,
+    pub derived: usize,
+}
+
+/// Reviews as entered in the reviews count file, without outcome weighting
+pub fn veteran_reviews_from_counts(counts: &[VeteranReviewsCount]) -> Vec<VeteranReviews> {
+    counts
+        .iter()
+        .map(|count| VeteranReviews {
+            name: count.name.clone(),
+            number_of_reviews: count.number_of_reviews,
+            weighted_reviews: VeteranAdvisorReward::from(count.number_of_reviews),
+        })
+        .collect()
+}
+
+/// Reviews of the veterans listed in the reviews count file, counted from the vca aggregated
+/// file rows instead and weighted by their outcome. Veterans are matched by their name in the
+/// reviewing vCAs of each row, counts which differ from the entered ones are returned along.
+/// Fails if no veteran is found at all, as every veteran would silently get nothing otherwise.
+pub fn veteran_reviews_from_aggregated(
+    counts: &[VeteranReviewsCount],
+    aggregated: &[VcaReviewedRow],
+    weights: &OutcomeWeights,
+) -> Result<(Vec<VeteranReviews>, Vec<CountMismatch>), Error> {
+    let veterans: BTreeSet<&str> = counts.iter().map(|count| count.name.as_str()).collect();
+    let mut derived: BTreeMap<&str, (usize, VeteranAdvisorReward)> = BTreeMap::new();
+    for row in aggregated {
+        let weight = match weights.weight(row.review.score()) {
+            Some(weight) => weight,
+            None => continue,
+        };
+        for vca in row
+            .vcas
+            .iter()
+            .map(String::as_str)
+            .filter(|vca| veterans.contains(vca))
+        {
+            let (reviews, weighted) = derived
+                .entry(vca)
+                .or_insert((0, VeteranAdvisorReward::ZERO));
+            *reviews += 1;
+            *weighted += weight;
+        }
+    }
+    if derived.is_empty() {
+        return Err(Error::NoVeteranFound);
+    }
+
+    let mut mismatches = Vec::new();
+    let reviews = counts
+        .iter()
+        .map(|count| {
+            let (number_of_reviews, weighted_reviews) = derived
+                .get(count.name.as_str())
+                .copied()
+                .unwrap_or((0, VeteranAdvisorReward::ZERO));
+            if number_of_reviews != count.number_of_reviews {
+                mismatches.push(CountMismatch {
+                    name: count.name.clone(),
+                    entered: count.number_of_reviews,
+                    derived: number_of_reviews,
+                });
+            }
+            VeteranReviews {
+                name: count.name.clone(),
+                number_of_reviews,
+                weighted_reviews,
+            }
+        })
+        .collect();
+    Ok((reviews, mismatches))
+}
+
 pub fn calculate_veteran_advisors_rewards(
     veteran_reviews: &[VeteranReviewsCount],
     base_rewards: VeteranAdvisorReward,
 ) -> VeteranAdvisorRewards {
-    let total_reviews: usize = veteran_reviews.iter().map(|vr| vr.number_of_reviews).sum();
+    calculate_veteran_advisors_rewards_with_config(
+        &veteran_reviews_from_counts(veteran_reviews),
+        base_rewards,
+        &VeteranRewardsConfig::default(),
+    )
+}
+
+/// Split `base_rewards` proportionally to the weighted reviews of each veteran times its tier
+/// multiplier. Veterans whose share goes over the cap get the cap, and the excess is split again
+/// among the others, until no share goes over the cap. If every veteran is capped, the excess is
+/// not distributed.
+pub fn calculate_veteran_advisors_rewards_with_config(
+    veteran_reviews: &[VeteranReviews],
+    base_rewards: VeteranAdvisorReward,
+    config: &VeteranRewardsConfig,
+) -> VeteranAdvisorRewards {
+    let weights: Vec<VeteranAdvisorReward> = veteran_reviews
+        .iter()
+        .map(|vr| vr.weighted_reviews * config.multiplier(vr.number_of_reviews))
+        .collect();
+
+    let mut rewards = vec![VeteranAdvisorReward::ZERO; veteran_reviews.len()];
+    let mut capped = vec![false; veteran_reviews.len()];
+    let mut remaining = base_rewards;
+    loop {
+        let total_weight: VeteranAdvisorReward = weights
+            .iter()
+            .zip(&capped)
+            .filter(|(_, capped)| !**capped)
+            .map(|(weight, _)| *weight)
+            .sum();
+        if total_weight.is_zero() {
+            break;
+        }
+        let shares: Vec<Option<VeteranAdvisorReward>> = weights
+            .iter()
+            .zip(&capped)
+            .map(|(weight, capped)| (!capped).then(|| (*weight / total_weight) * remaining))
+            .collect();
+
+        let mut newly_capped = false;
+        if let Some(max_rewards) = config.max_rewards {
+            for (i, share) in shares.iter().enumerate() {
+                if matches!(share, Some(share) if *share > max_rewards) {
+                    capped[i] = true;
+                    rewards[i] = max_rewards;
+                    remaining -= max_rewards;
+                    newly_capped = true;
+                }
+            }
+        }
+        if !newly_capped {
+            for (reward, share) in rewards.iter_mut().zip(shares) {
+                if let Some(share) = share {
+                    *reward = share;
+                }
+            }
+            break;
+        }
+    }
 
     veteran_reviews
         .iter()
-        .map(|vr| {
-            (
-                vr.name.clone(),
-                (VeteranAdvisorReward::from(vr.number_of_reviews)
-                    / VeteranAdvisorReward::from(total_reviews))
-                    * base_rewards,
-            )
-        })
+        .zip(rewards)
+        .map(|(vr, reward)| (vr.name.clone(), reward))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::community_advisors::models::AdvisorReviewRow;
+
+    fn count(name: &str, number_of_reviews: usize) -> VeteranReviewsCount {
+        VeteranReviewsCount {
+            name: name.to_string(),
+            number_of_reviews,
+        }
+    }
+
+    #[test]
+    fn tiers_and_cap() {
+        let counts = vec![
+            count("a", 10),
+            count("b", 50),
+            count("c", 100),
+            count("d", 200),
+        ];
+        let config: VeteranRewardsConfig = serde_yaml::from_str(
+            r#"
+tiers:
+  - min_reviews: 50
+    multiplier: "1"
+  - min_reviews: 100
+    multiplier: "2"
+max_rewards: "500"
+"#,
+        )
+        .unwrap();
+
+        let rewards = calculate_veteran_advisors_rewards_with_config(
+            &veteran_reviews_from_counts(&counts),
+            VeteranAdvisorReward::from(1000),
+            &config,
+        );
+        let rewards: BTreeMap<_, _> = rewards.into_iter().collect();
+        // `a` is below the lowest tier, weights are then 50, 200 and 400: `d` goes over the cap
+        // and the excess is split between `b` and `c`
+        assert_eq!(rewards["a"], VeteranAdvisorReward::ZERO);
+        assert_eq!(rewards["d"], VeteranAdvisorReward::from(500));
+        assert_eq!(rewards["b"], VeteranAdvisorReward::from(100));
+        assert_eq!(rewards["c"], VeteranAdvisorReward::from(400));
+    }
+
+    #[test]
+    fn counts_from_aggregated_file() {
+        let counts = vec![count("a", 2), count("b", 5)];
+        let rows: Vec<VcaReviewedRow> = [
+            (ReviewScore::Excellent, "a, b"),
+            (ReviewScore::Good, "b"),
+            (ReviewScore::FilteredOut, "a,c"),
+        ]
+        .iter()
+        .map(|(score, vcas)| VcaReviewedRow {
+            review: AdvisorReviewRow::dummy(*score),
+            vcas: vcas.split(',').map(|vca| vca.trim().to_string()).collect(),
+        })
+        .collect();
+        let weights = OutcomeWeights {
+            filtered_out: VeteranAdvisorReward::ZERO,
+            ..Default::default()
+        };
+
+        let (reviews, mismatches) =
+            veteran_reviews_from_aggregated(&counts, &rows, &weights).unwrap();
+        assert_eq!(reviews[0].number_of_reviews, 2);
+        assert_eq!(reviews[0].weighted_reviews, VeteranAdvisorReward::ONE);
+        assert_eq!(reviews[1].number_of_reviews, 2);
+        assert_eq!(
+            mismatches,
+            vec![CountMismatch {
+                name: "b".to_string(),
+                entered: 5,
+                derived: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn veterans_have_to_be_found_in_aggregated_file() {
+        let rows = vec![VcaReviewedRow {
+            review: AdvisorReviewRow::dummy(ReviewScore::Good),
+            vcas: vec!["c".to_string()],
+        }];
+        assert!(matches!(
+            veteran_reviews_from_aggregated(&[count("a", 2)], &rows, &Default::default()),
+            Err(Error::NoVeteranFound)
+        ));
+    }
+}
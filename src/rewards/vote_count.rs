@@ -0,0 +1,282 @@
+use crate::archive;
+use crate::recovery::tally::{voteplans_from_block0, VoteFragmentFilter};
+use crate::rewards::voters::VoteCount;
+
+use chain_impl_mockchain::{
+    block::{Block, BlockDate},
+    certificate::VotePlanId,
+    fragment::Fragment,
+    transaction::InputEnum,
+};
+use jormungandr_lib::interfaces::PersistentFragmentLog;
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Archive(#[from] archive::Error),
+
+    #[error(transparent)]
+    Recovery(#[from] crate::recovery::tally::Error),
+}
+
+/// Distinct proposals (voteplan and proposal index) voted, indexed by hex encoded voter account
+pub type Ballots = HashMap<String, HashSet<(VotePlanId, u8)>>;
+
+/// Count, per voter account, the amount of distinct proposals voted
+pub struct VoteCounter {
+    voting_periods: HashMap<VotePlanId, Range<BlockDate>>,
+    only_valid_ballots: bool,
+    ballots: Ballots,
+}
+
+impl VoteCounter {
+    /// If `only_valid_ballots` is set, ballots for voteplans not in block0 or cast outside of
+    /// the voteplan voting period are not counted
+    pub fn new(block0: &Block, only_valid_ballots: bool) -> Self {
+        let voting_periods = voteplans_from_block0(block0)
+            .into_iter()
+            .map(|(id, voteplan)| (id, voteplan.vote_start()..voteplan.vote_end()))
+            .collect();
+        Self {
+            voting_periods,
+            only_valid_ballots,
+            ballots: HashMap::new(),
+        }
+    }
+
+    /// Count `fragment` if it is a ballot, returns whether it was counted
+    pub fn count(&mut self, fragment: &Fragment, date: BlockDate) -> bool {
+        let tx = match fragment {
+            Fragment::VoteCast(tx) => tx,
+            _ => return false,
+        };
+        let tx = tx.as_slice();
+        let account = match tx.inputs().iter().next().map(|input| input.to_enum()) {
+            Some(InputEnum::AccountInput(account, _)) => account,
+            _ => return false,
+        };
+        let certificate = tx.payload().into_payload();
+
+        if self.only_valid_ballots {
+            match self.voting_periods.get(certificate.vote_plan()) {
+                Some(period) if period.contains(&date) => {}
+                _ => return false,
+            }
+        }
+
+        self.ballots
+            .entry(hex::encode(account.as_ref()))
+            .or_default()
+            .insert((
+                certificate.vote_plan().clone(),
+                certificate.proposal_index(),
+            ))
+    }
+
+    pub fn into_ballots(self) -> Ballots {
+        self.ballots
+    }
+}
+
+pub fn vote_count(ballots: &Ballots) -> VoteCount {
+    ballots
+        .iter()
+        .map(|(account, ballots)| (account.clone(), ballots.len() as u64))
+        .collect()
+}
+
+/// Collect ballots from the blocks in a node block store
+pub fn ballots_from_block_store(
+    jormungandr_database: &Path,
+    block0: &Block,
+    only_valid_ballots: bool,
+) -> Result<Ballots, Error> {
+    let mut counter = VoteCounter::new(block0, only_valid_ballots);
+    archive::for_each_block(jormungandr_database, |block| {
+        let date = block.header().block_date();
+        for fragment in block.fragments() {
+            counter.count(fragment, date);
+        }
+        Ok(())
+    })?;
+    Ok(counter.into_ballots())
+}
+
+/// Collect ballots from persistent fragment logs, run through [`VoteFragmentFilter`].
+/// Fragments rejected by the filter (invalid signatures or spending counters, replays...) are
+/// never counted, `only_valid_ballots` only adds the voteplan and voting period checks.
+pub fn ballots_from_fragment_logs<I: Iterator<Item = PersistentFragmentLog>>(
+    block0: Block,
+    range_check: Range<u32>,
+    fragments: I,
+    only_valid_ballots: bool,
+) -> Result<Ballots, Error> {
+    let mut counter = VoteCounter::new(&block0, only_valid_ballots);
+    let filter = VoteFragmentFilter::new(block0, range_check, fragments)?;
+    for validated in filter.flatten() {
+        counter.count(&validated.fragment, validated.recorded_date);
+    }
+    Ok(counter.into_ballots())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::Discrimination;
+    use chain_core::property::Block as _;
+    use chain_impl_mockchain::certificate::VotePlan;
+    use chain_impl_mockchain::vote::Choice;
+    use jormungandr_lib::interfaces::Block0Configuration;
+    use jormungandr_lib::time::SecondsSinceUnixEpoch;
+    use jormungandr_testing_utils::testing::jormungandr::ConfigurationBuilder;
+    use jormungandr_testing_utils::testing::{vote_plan_cert, VotePlanBuilder};
+    use jormungandr_testing_utils::wallet::Wallet as TestWallet;
+    use rand::rngs::OsRng;
+
+    struct Setup {
+        voter: TestWallet,
+        vote_plan: VotePlan,
+        block0_configuration: Block0Configuration,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let voter =
+                TestWallet::new_account_with_discrimination(&mut OsRng, Discrimination::Production);
+            let vote_plan = VotePlanBuilder::new().proposals_count(3).public().build();
+            let vote_plan_cert = vote_plan_cert(
+                &voter,
+                BlockDate {
+                    epoch: 1,
+                    slot_id: 0,
+                },
+                &vote_plan,
+            )
+            .into();
+            let block0_configuration = ConfigurationBuilder::new()
+                .with_funds(vec![voter.to_initial_fund(1_000_000)])
+                .with_certs(vec![vote_plan_cert])
+                .with_discrimination(Discrimination::Production)
+                .with_committees(&[&voter])
+                .build_block0();
+            Self {
+                voter,
+                vote_plan,
+                block0_configuration,
+            }
+        }
+
+        fn block0(&self) -> Block {
+            self.block0_configuration.to_block()
+        }
+
+        fn ballot(&mut self, vote_plan: &VotePlan, proposal_index: u8) -> Fragment {
+            self.voter
+                .issue_vote_cast_cert(
+                    &self.block0().id().into(),
+                    &self
+                        .block0_configuration
+                        .blockchain_configuration
+                        .linear_fees,
+                    BlockDate {
+                        epoch: 10,
+                        slot_id: 0,
+                    },
+                    vote_plan,
+                    proposal_index,
+                    &Choice::new(1),
+                )
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn distinct_proposals_are_counted() {
+        let mut setup = Setup::new();
+        let vote_plan = setup.vote_plan.clone();
+        let date = vote_plan.vote_start();
+        let mut counter = VoteCounter::new(&setup.block0(), true);
+
+        assert!(counter.count(&setup.ballot(&vote_plan, 0), date));
+        assert!(!counter.count(&setup.ballot(&vote_plan, 0), date));
+        assert!(counter.count(&setup.ballot(&vote_plan, 2), date));
+
+        let vote_count = vote_count(&counter.into_ballots());
+        assert_eq!(vote_count.len(), 1);
+        assert_eq!(vote_count.values().next(), Some(&2));
+    }
+
+    #[test]
+    fn only_valid_ballots_are_in_block0_voting_periods() {
+        let mut setup = Setup::new();
+        let vote_plan = setup.vote_plan.clone();
+        let unknown_vote_plan = VotePlanBuilder::new().proposals_count(4).public().build();
+        let fragments = vec![
+            (setup.ballot(&vote_plan, 0), vote_plan.vote_start()),
+            // the voting period ends at vote_end, exclusive
+            (setup.ballot(&vote_plan, 1), vote_plan.vote_end()),
+            (
+                setup.ballot(&unknown_vote_plan, 0),
+                unknown_vote_plan.vote_start(),
+            ),
+        ];
+
+        let mut counter = VoteCounter::new(&setup.block0(), true);
+        let counted: Vec<_> = fragments
+            .iter()
+            .map(|(fragment, date)| counter.count(fragment, *date))
+            .collect();
+        assert_eq!(counted, vec![true, false, false]);
+
+        let mut counter = VoteCounter::new(&setup.block0(), false);
+        assert!(fragments
+            .iter()
+            .all(|(fragment, date)| counter.count(fragment, *date)));
+    }
+
+    #[test]
+    fn rejected_log_fragments_are_never_counted() {
+        let mut setup = Setup::new();
+        let vote_plan = setup.vote_plan.clone();
+        let block0_date = setup
+            .block0_configuration
+            .blockchain_configuration
+            .block0_date;
+        let ballots = vec![setup.ballot(&vote_plan, 0), setup.ballot(&vote_plan, 1)];
+        let logs = || {
+            ballots
+                .iter()
+                .zip(vec![
+                    block0_date,
+                    // logged before the blockchain started
+                    SecondsSinceUnixEpoch::from_secs(0),
+                ])
+                .map(|(fragment, time)| PersistentFragmentLog {
+                    time,
+                    fragment: fragment.clone(),
+                })
+        };
+
+        let counted = ballots_from_fragment_logs(setup.block0(), 0..1000, logs(), false).unwrap();
+        assert_eq!(vote_count(&counted).values().collect::<Vec<_>>(), vec![&1]);
+
+        // no spending counter is accepted, so every signature check fails
+        let counted = ballots_from_fragment_logs(setup.block0(), 0..0, logs(), false).unwrap();
+        assert!(counted.is_empty());
+    }
+
+    #[test]
+    fn other_fragments_are_not_counted() {
+        let setup = Setup::new();
+        let block0 = setup.block0();
+        let mut counter = VoteCounter::new(&block0, false);
+        for fragment in block0.fragments() {
+            assert!(!counter.count(fragment, BlockDate::first()));
+        }
+        assert!(counter.into_ballots().is_empty());
+    }
+}
@@ -1,13 +1,17 @@
-use fixed::types::U64F64;
+use crate::rewards::vote_count::Ballots;
 
 use chain_addr::{Discrimination, Kind};
+use chain_impl_mockchain::certificate::VotePlanId;
 use chain_impl_mockchain::transaction::UnspecifiedAccountIdentifier;
-use std::collections::{HashMap, HashSet};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 
 use jormungandr_lib::interfaces::{Address, Block0Configuration, Initial};
 
 pub const ADA_TO_LOVELACE_FACTOR: u64 = 1_000_000;
-pub type Rewards = U64F64;
+pub type Rewards = Decimal;
 
 pub fn calculate_stake<'address>(
     committee_keys: &HashSet<Address>,
@@ -46,7 +50,7 @@ pub fn calculate_reward_share<'address>(
         .map(|(k, v)| {
             // if it doesnt appear in the votes count, it means it did not vote
             let reward = if *threshold_addresses.get(k).unwrap_or(&0u64) >= threshold {
-                Rewards::from_num(*v) / total_stake as u128
+                Rewards::from(*v) / Rewards::from(total_stake)
             } else {
                 Rewards::ZERO
             };
@@ -57,7 +61,60 @@ pub fn calculate_reward_share<'address>(
 
 /// get the proportional reward from a share and total rewards amount
 pub fn reward_from_share(share: Rewards, total_reward: u64) -> Rewards {
-    Rewards::from_num(total_reward) * share
+    Rewards::from(total_reward) * share
+}
+
+/// Turn shares into whole lovelace rewards adding up to exactly `total_rewards`.
+///
+/// Shares are taken relative to their sum: the share of voters left without rewards (excluded by
+/// a rule or below the threshold) is redistributed among the others, proportionally to their
+/// shares. Rewards are then rounded down to the lovelace, and the lovelace lost in rounding are
+/// handed out one at a time to the entries with the largest rounded off remainder, ties going to
+/// the smallest key. Nothing is paid if no entry has a positive share.
+pub fn rewards_to_lovelace<K: Ord>(
+    shares: impl IntoIterator<Item = (K, Rewards)>,
+    total_rewards: u64,
+) -> BTreeMap<K, u64> {
+    let shares: Vec<(K, Rewards)> = shares.into_iter().collect();
+    let total_share: Rewards = shares.iter().map(|(_, share)| *share).sum();
+    let mut rewards: Vec<(K, u64, Rewards)> = shares
+        .into_iter()
+        .map(|(key, share)| {
+            if total_share.is_zero() {
+                return (key, 0, Rewards::ZERO);
+            }
+            let reward = reward_from_share(share / total_share, total_rewards);
+            let lovelace = reward.round_dp_with_strategy(0, RoundingStrategy::ToZero);
+            let lovelace_u64 = lovelace
+                .to_u64()
+                .expect("a share of the total rewards fits in u64");
+            (key, lovelace_u64, reward - lovelace)
+        })
+        .collect();
+
+    let paid: u64 = rewards.iter().map(|(_, lovelace, _)| lovelace).sum();
+    let rounding_remainder = total_rewards.saturating_sub(paid);
+    rewards.sort_by(|(key_a, _, remainder_a), (key_b, _, remainder_b)| {
+        remainder_b.cmp(remainder_a).then_with(|| key_a.cmp(key_b))
+    });
+    let mut candidates: Vec<&mut u64> = rewards
+        .iter_mut()
+        .filter(|(_, lovelace, remainder)| *lovelace > 0 || !remainder.is_zero())
+        .map(|(_, lovelace, _)| lovelace)
+        .collect();
+    // every candidate gets at most one lovelace, unless the remainders lost decimal precision
+    for i in 0..rounding_remainder as usize {
+        if candidates.is_empty() {
+            break;
+        }
+        let count = candidates.len();
+        *candidates[i % count] += 1;
+    }
+
+    rewards
+        .into_iter()
+        .map(|(key, lovelace, _)| (key, lovelace))
+        .collect()
 }
 
 pub type VoteCount = HashMap<String, u64>;
@@ -80,7 +137,7 @@ pub fn vote_count_with_addresses(
         .collect()
 }
 
-fn account_hex_to_address(
+pub(crate) fn account_hex_to_address(
     account_hex: String,
     discrimination: Discrimination,
 ) -> Result<Address, hex::FromHexError> {
@@ -97,3 +154,386 @@ fn account_hex_to_address(
         ),
     )))
 }
+
+/// Challenge id of every proposal, indexed by voteplan and proposal index
+pub type ProposalChallenges = HashMap<(VotePlanId, u8), u32>;
+
+/// Eligibility rules for voter rewards, usually loaded from a rules file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ParticipationRules {
+    #[serde(default)]
+    pub rules: Vec<ParticipationRule>,
+    /// stake (in lovelace) counted for a single voter at most
+    #[serde(default)]
+    pub stake_cap: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParticipationRule {
+    /// minimum amount of votes overall
+    MinVotes { votes: u64 },
+    /// minimum amount of votes in a voteplan
+    MinVotesInVoteplan { voteplan: String, votes: u64 },
+    /// minimum amount of votes in a challenge
+    MinVotesInChallenge { challenge: u32, votes: u64 },
+    /// minimum amount of distinct challenges voted in
+    MinChallenges { challenges: u64 },
+}
+
+/// Votes of a voter, with per voteplan and per challenge details when ballots are available
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoterParticipation {
+    pub votes: u64,
+    pub details: Option<ParticipationDetails>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParticipationDetails {
+    /// indexed by hex encoded voteplan id
+    pub votes_per_voteplan: HashMap<String, u64>,
+    pub votes_per_challenge: HashMap<u32, u64>,
+}
+
+pub type AddressesParticipation = HashMap<Address, VoterParticipation>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eligibility {
+    Eligible,
+    Excluded(ParticipationRule),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RulesError {
+    #[error("rule {0} needs individual ballots, votes count alone are not enough")]
+    MissingBallots(ParticipationRule),
+
+    #[error("no challenge found for proposal {index} of voteplan {voteplan}")]
+    UnknownProposal { voteplan: VotePlanId, index: u8 },
+}
+
+impl Display for ParticipationRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinVotes { votes } => write!(f, "min_votes({})", votes),
+            Self::MinVotesInVoteplan { voteplan, votes } => {
+                write!(f, "min_votes_in_voteplan({}, {})", voteplan, votes)
+            }
+            Self::MinVotesInChallenge { challenge, votes } => {
+                write!(f, "min_votes_in_challenge({}, {})", challenge, votes)
+            }
+            Self::MinChallenges { challenges } => write!(f, "min_challenges({})", challenges),
+        }
+    }
+}
+
+impl Display for Eligibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eligible => Ok(()),
+            Self::Excluded(rule) => rule.fmt(f),
+        }
+    }
+}
+
+impl ParticipationRule {
+    fn needs_details(&self) -> bool {
+        !matches!(self, Self::MinVotes { .. })
+    }
+
+    fn is_satisfied(&self, participation: &VoterParticipation) -> Result<bool, RulesError> {
+        if let Self::MinVotes { votes } = self {
+            return Ok(participation.votes >= *votes);
+        }
+        let details = participation
+            .details
+            .as_ref()
+            .ok_or_else(|| RulesError::MissingBallots(self.clone()))?;
+        Ok(match self {
+            Self::MinVotes { .. } => unreachable!(),
+            Self::MinVotesInVoteplan { voteplan, votes } => {
+                details
+                    .votes_per_voteplan
+                    .get(voteplan)
+                    .copied()
+                    .unwrap_or(0)
+                    >= *votes
+            }
+            Self::MinVotesInChallenge { challenge, votes } => {
+                details
+                    .votes_per_challenge
+                    .get(challenge)
+                    .copied()
+                    .unwrap_or(0)
+                    >= *votes
+            }
+            Self::MinChallenges { challenges } => {
+                details.votes_per_challenge.len() as u64 >= *challenges
+            }
+        })
+    }
+}
+
+impl ParticipationRules {
+    /// Rules equivalent to the single global vote threshold
+    pub fn from_vote_threshold(threshold: u64) -> Self {
+        Self {
+            rules: vec![ParticipationRule::MinVotes { votes: threshold }],
+            stake_cap: None,
+        }
+    }
+
+    pub fn needs_ballots(&self) -> bool {
+        self.rules.iter().any(ParticipationRule::needs_details)
+    }
+
+    pub fn needs_challenges(&self) -> bool {
+        self.rules.iter().any(|rule| {
+            matches!(
+                rule,
+                ParticipationRule::MinVotesInChallenge { .. }
+                    | ParticipationRule::MinChallenges { .. }
+            )
+        })
+    }
+
+    /// First rule not satisfied by the voter, if any. Voters without participation did not vote.
+    pub fn eligibility(
+        &self,
+        participation: Option<&VoterParticipation>,
+    ) -> Result<Eligibility, RulesError> {
+        let not_voted = VoterParticipation {
+            votes: 0,
+            details: Some(ParticipationDetails::default()),
+        };
+        let participation = participation.unwrap_or(&not_voted);
+        for rule in &self.rules {
+            if !rule.is_satisfied(participation)? {
+                return Ok(Eligibility::Excluded(rule.clone()));
+            }
+        }
+        Ok(Eligibility::Eligible)
+    }
+
+    pub fn capped_stake(&self, stake: u64) -> u64 {
+        self.stake_cap.map_or(stake, |cap| stake.min(cap))
+    }
+}
+
+/// Voters participation from their ballots, individual ballots are assigned to challenges
+/// through `proposal_challenges`
+pub fn participation_from_ballots(
+    ballots: &Ballots,
+    proposal_challenges: Option<&ProposalChallenges>,
+    block0: &Block0Configuration,
+) -> Result<AddressesParticipation, RulesError> {
+    let discrimination = block0.blockchain_configuration.discrimination;
+    ballots
+        .iter()
+        .map(|(account_hex, ballots)| {
+            let mut details = ParticipationDetails::default();
+            for (voteplan, index) in ballots {
+                *details
+                    .votes_per_voteplan
+                    .entry(voteplan.to_string())
+                    .or_default() += 1;
+                if let Some(proposal_challenges) = proposal_challenges {
+                    let challenge = proposal_challenges
+                        .get(&(voteplan.clone(), *index))
+                        .ok_or_else(|| RulesError::UnknownProposal {
+                            voteplan: voteplan.clone(),
+                            index: *index,
+                        })?;
+                    *details.votes_per_challenge.entry(*challenge).or_default() += 1;
+                }
+            }
+            Ok((
+                account_hex_to_address(account_hex.clone(), discrimination)
+                    .expect("Valid hex encoded UnspecifiedAccountIdentifier"),
+                VoterParticipation {
+                    votes: ballots.len() as u64,
+                    details: Some(details),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Voters participation from votes count only, without details
+pub fn participation_from_vote_count(vote_count: AddressesVoteCount) -> AddressesParticipation {
+    vote_count
+        .into_iter()
+        .map(|(address, votes)| {
+            (
+                address,
+                VoterParticipation {
+                    votes,
+                    details: None,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Like [`calculate_reward_share`], but eligibility is decided by `rules` and voters stake is
+/// capped by the rules stake cap. The eligibility of each voter is returned along its share.
+pub fn calculate_reward_share_with_rules<'address>(
+    stake_per_voter: &HashMap<&'address Address, u64>,
+    participation: &AddressesParticipation,
+    rules: &ParticipationRules,
+) -> Result<HashMap<&'address Address, (Rewards, Eligibility)>, RulesError> {
+    let total_stake: u64 = stake_per_voter
+        .values()
+        .map(|stake| rules.capped_stake(*stake))
+        .sum();
+    stake_per_voter
+        .iter()
+        .map(|(address, stake)| {
+            let eligibility = rules.eligibility(participation.get(*address))?;
+            let reward = if eligibility == Eligibility::Eligible && total_stake > 0 {
+                Rewards::from(rules.capped_stake(*stake)) / Rewards::from(total_stake)
+            } else {
+                Rewards::ZERO
+            };
+            Ok((*address, (reward, eligibility)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(i: u8) -> Address {
+        account_hex_to_address(hex::encode([i; 32]), Discrimination::Production).unwrap()
+    }
+
+    fn participation(votes: u64, per_challenge: &[(u32, u64)]) -> VoterParticipation {
+        VoterParticipation {
+            votes,
+            details: Some(ParticipationDetails {
+                votes_per_voteplan: HashMap::new(),
+                votes_per_challenge: per_challenge.iter().copied().collect(),
+            }),
+        }
+    }
+
+    #[test]
+    fn rules_exclude_voters() {
+        let rules: ParticipationRules = serde_yaml::from_str(
+            r#"
+rules:
+  - type: min_votes
+    votes: 2
+  - type: min_challenges
+    challenges: 2
+  - type: min_votes_in_challenge
+    challenge: 1
+    votes: 2
+stake_cap: 100
+"#,
+        )
+        .unwrap();
+
+        let (a, b, c, d) = (address(0), address(1), address(2), address(3));
+        let stakes: HashMap<&Address, u64> = vec![(&a, 1000), (&b, 50), (&c, 10), (&d, 10)]
+            .into_iter()
+            .collect();
+        let participation: AddressesParticipation = vec![
+            (a.clone(), participation(3, &[(1, 2), (2, 1)])),
+            (b.clone(), participation(2, &[(1, 2)])),
+            (c.clone(), participation(1, &[(1, 1)])),
+        ]
+        .into_iter()
+        .collect();
+
+        let shares = calculate_reward_share_with_rules(&stakes, &participation, &rules).unwrap();
+        assert_eq!(shares[&a].1, Eligibility::Eligible);
+        assert_eq!(
+            shares[&b].1,
+            Eligibility::Excluded(ParticipationRule::MinChallenges { challenges: 2 })
+        );
+        assert_eq!(
+            shares[&c].1,
+            Eligibility::Excluded(ParticipationRule::MinVotes { votes: 2 })
+        );
+        assert_eq!(
+            shares[&d].1,
+            Eligibility::Excluded(ParticipationRule::MinVotes { votes: 2 })
+        );
+        // stake of `a` is capped to 100, out of 100 + 50 + 10 + 10
+        assert_eq!(shares[&a].0, Rewards::from(100) / Rewards::from(170));
+    }
+
+    #[test]
+    fn paid_lovelace_add_up_to_total_rewards() {
+        let stakes: Vec<u64> = vec![1, 3, 7, 11, 1_000_003, 0, 42, 999_999_999_989];
+        let total_stake: u64 = stakes.iter().sum();
+        for &total_rewards in &[0u64, 1, 7, 1_000, 1_234_567_891, 30_000_000_000_000] {
+            let shares = stakes
+                .iter()
+                .enumerate()
+                .map(|(i, stake)| (i, Rewards::from(*stake) / Rewards::from(total_stake)));
+            let rewards = rewards_to_lovelace(shares, total_rewards);
+            assert_eq!(rewards.values().sum::<u64>(), total_rewards);
+            // voters without stake are not paid any dust
+            assert_eq!(rewards[&5], 0);
+
+            // shares of excluded voters are zeroed, the others do not add up to 1 anymore
+            let shares = stakes.iter().enumerate().map(|(i, stake)| {
+                let share = if i % 2 == 0 {
+                    Rewards::from(*stake) / Rewards::from(total_stake)
+                } else {
+                    Rewards::ZERO
+                };
+                (i, share)
+            });
+            let rewards = rewards_to_lovelace(shares, total_rewards);
+            assert_eq!(rewards.values().sum::<u64>(), total_rewards);
+            assert!(rewards.iter().all(|(i, reward)| i % 2 == 0 || *reward == 0));
+        }
+    }
+
+    #[test]
+    fn excluded_stake_is_redistributed_proportionally() {
+        let rewards = rewards_to_lovelace(
+            vec![
+                ("a", Rewards::new(4, 1)),
+                ("b", Rewards::new(1, 1)),
+                ("excluded", Rewards::ZERO),
+            ],
+            100,
+        );
+        assert_eq!(rewards["a"], 80);
+        assert_eq!(rewards["b"], 20);
+        assert_eq!(rewards["excluded"], 0);
+    }
+
+    #[test]
+    fn dust_goes_to_largest_remainders() {
+        let third = Rewards::ONE / Rewards::from(3);
+        let rewards = rewards_to_lovelace(vec![("a", third), ("b", third), ("c", third)], 10);
+        assert_eq!(rewards["a"], 4);
+        assert_eq!(rewards["b"], 3);
+        assert_eq!(rewards["c"], 3);
+
+        let rewards = rewards_to_lovelace(vec![("a", Rewards::ZERO)], 10);
+        assert_eq!(rewards["a"], 0);
+    }
+
+    #[test]
+    fn detailed_rules_need_ballots() {
+        let rules = ParticipationRules {
+            rules: vec![ParticipationRule::MinChallenges { challenges: 1 }],
+            stake_cap: None,
+        };
+        let participation = VoterParticipation {
+            votes: 1,
+            details: None,
+        };
+        assert!(matches!(
+            rules.eligibility(Some(&participation)),
+            Err(RulesError::MissingBallots(_))
+        ));
+    }
+}
@@ -11,6 +11,20 @@ pub enum Error {
 
     #[error("Couldn't parse advisor review tag for question: {0}")]
     CouldntParseTag(String),
+
+    #[error(
+        "column '{column}' not found in the vca aggregated file, available columns: {available:?}"
+    )]
+    MissingColumn {
+        column: String,
+        available: Vec<String>,
+    },
+}
+
+/// A row of the vca aggregated file along with the veteran community advisors who reviewed it
+pub struct VcaReviewedRow {
+    pub review: AdvisorReviewRow,
+    pub vcas: Vec<String>,
 }
 
 impl AdvisorReviewRow {
@@ -37,3 +51,72 @@ pub fn read_vca_reviews_aggregated_file(filepath: &Path) -> Result<Vec<AdvisorRe
             .collect(),
     )
 }
+
+/// Rows of the vca aggregated file along with the vCAs who reviewed them, listed (separated by
+/// commas) in `vcas_column`. The aggregated file does not name vCAs by default, so the column
+/// has to be added to it, and it is an error if it is missing.
+pub fn read_vca_reviews_aggregated_rows_with_vcas(
+    filepath: &Path,
+    vcas_column: &str,
+) -> Result<Vec<VcaReviewedRow>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(filepath)?;
+    let headers = reader.headers()?.clone();
+    let vcas_index = headers
+        .iter()
+        .position(|header| header == vcas_column)
+        .ok_or_else(|| Error::MissingColumn {
+            column: vcas_column.to_string(),
+            available: headers.iter().map(str::to_string).collect(),
+        })?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(VcaReviewedRow {
+                review: record.deserialize(Some(&headers))?,
+                vcas: record
+                    .get(vcas_index)
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|vca| !vca.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn vcas_column_is_required() {
+        let path = Path::new("./resources/testing/valid_assessments.csv");
+        assert!(matches!(
+            read_vca_reviews_aggregated_rows_with_vcas(path, "vCAs"),
+            Err(Error::MissingColumn { column, .. }) if column == "vCAs"
+        ));
+    }
+
+    #[test]
+    fn vcas_are_read_from_their_column() {
+        let file = assert_fs::NamedTempFile::new("aggregated.csv").unwrap();
+        file.write_str(
+            "proposal_id,Idea URL,Assessor,Impact / Alignment Note,Impact / Alignment Rating,\
+             Feasibility Note,Feasibility Rating,Auditability Note,Auditability Rating,Excellent,Good,vCAs\n\
+             1,url,assessor,note,5,note,4,note,3,x,,\"Andreas, Tomi\"\n\
+             2,url,assessor,note,5,note,4,note,3,,x,\n",
+        )
+        .unwrap();
+
+        let rows = read_vca_reviews_aggregated_rows_with_vcas(file.path(), "vCAs").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].vcas, vec!["Andreas", "Tomi"]);
+        assert!(rows[1].vcas.is_empty());
+    }
+}
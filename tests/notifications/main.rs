@@ -1,3 +1,5 @@
+mod send;
+mod stub;
 mod verifier;
 
 use crate::verifier::NotificationsVerifier;
@@ -0,0 +1,104 @@
+use crate::stub::StubServer;
+use assert_fs::fixture::PathChild;
+use assert_fs::TempDir;
+use catalyst_toolbox::notifications::{
+    ledger::SentLedger,
+    requests::{
+        create_message::{ContentSettingsBuilder, CreateMessageBuilder},
+        Request, RequestData,
+    },
+    responses::create_message::CreateMessageResponse,
+    send::{RetryPolicy, SendOutcome, Sender},
+    Error,
+};
+use std::time::Duration;
+
+const OK_RESPONSE: &str = r#"{"status_code": 200, "status_message": "OK", "response": {"Messages": ["C3F8-C3863ED4-334AD4F1"]}}"#;
+
+fn create_message_request() -> Request {
+    let content_settings = ContentSettingsBuilder::new()
+        .with_plain_content("hello".to_string())
+        .build()
+        .unwrap();
+    let message = CreateMessageBuilder::new()
+        .with_auth("auth".to_string())
+        .with_application("application".to_string())
+        .add_content_settings(content_settings)
+        .build()
+        .unwrap();
+    Request::new(RequestData::CreateMessageRequest(message))
+}
+
+fn fast_retries(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        backoff: Duration::from_millis(1),
+    }
+}
+
+#[test]
+pub fn retry_on_server_error() {
+    let server = StubServer::start(vec![(503, "unavailable"), (200, OK_RESPONSE)]);
+    let mut sender = Sender::new(fast_retries(2), None);
+
+    let outcome = sender
+        .send::<CreateMessageResponse>(server.url().clone(), &create_message_request())
+        .unwrap();
+
+    assert!(matches!(outcome, SendOutcome::Sent(_)));
+    assert_eq!(server.received_requests().len(), 2);
+}
+
+#[test]
+pub fn error_status_in_response_body() {
+    let server = StubServer::start(vec![(
+        200,
+        r#"{"status_code": 210, "status_message": "Argument error", "response": null}"#,
+    )]);
+    let mut sender = Sender::new(fast_retries(2), None);
+
+    let result =
+        sender.send::<CreateMessageResponse>(server.url().clone(), &create_message_request());
+
+    assert!(matches!(
+        result,
+        Err(Error::RejectedRequest {
+            status_code: Some(210),
+            ..
+        })
+    ));
+    assert_eq!(server.received_requests().len(), 1);
+}
+
+#[test]
+pub fn ledger_prevents_sending_twice() {
+    let temp_dir = TempDir::new().unwrap();
+    let ledger_path = temp_dir.child("ledger.json");
+    let server = StubServer::start(vec![(200, OK_RESPONSE)]);
+    let request = create_message_request();
+
+    let mut sender = Sender::new(
+        fast_retries(0),
+        Some(SentLedger::load(ledger_path.path()).unwrap()),
+    );
+    let outcome = sender
+        .send::<CreateMessageResponse>(server.url().clone(), &request)
+        .unwrap();
+    assert!(matches!(outcome, SendOutcome::Sent(_)));
+
+    // a new run loads the ledger from disk
+    let mut sender = Sender::new(
+        fast_retries(0),
+        Some(SentLedger::load(ledger_path.path()).unwrap()),
+    );
+    match sender
+        .send::<CreateMessageResponse>(server.url().clone(), &request)
+        .unwrap()
+    {
+        SendOutcome::AlreadySent(entry) => {
+            assert_eq!(entry.message_codes, vec!["C3F8-C3863ED4-334AD4F1"]);
+        }
+        SendOutcome::Sent(_) => panic!("request should not be sent twice"),
+    }
+    assert_eq!(server.received_requests().len(), 1);
+}
@@ -0,0 +1,65 @@
+use reqwest::Url;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
+
+/// Minimal http server answering each incoming connection with the next canned response
+pub struct StubServer {
+    url: Url,
+    handle: JoinHandle<Vec<String>>,
+}
+
+impl StubServer {
+    pub fn start(responses: Vec<(u16, &str)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let responses: Vec<(u16, String)> = responses
+            .into_iter()
+            .map(|(status, body)| (status, body.to_string()))
+            .collect();
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    let lowercase = line.to_lowercase();
+                    if let Some(value) = lowercase.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                requests.push(String::from_utf8(request_body).unwrap());
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} STUB\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                stream.flush().unwrap();
+            }
+            requests
+        });
+        Self { url, handle }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Wait for all the canned responses to be served and return the received request bodies
+    pub fn received_requests(self) -> Vec<String> {
+        self.handle.join().unwrap()
+    }
+}